@@ -0,0 +1,23 @@
+-- Add migration script here
+CREATE EXTENSION IF NOT EXISTS "uuid-ossp";
+
+CREATE TABLE IF NOT EXISTS trades (
+    id UUID PRIMARY KEY DEFAULT uuid_generate_v4(),
+    position_id UUID NOT NULL,
+    user_pubkey VARCHAR(255) NOT NULL,
+    token_pubkey VARCHAR(255) NOT NULL,
+    vs_token_symbol VARCHAR(255) NOT NULL,
+    side VARCHAR(10) NOT NULL,
+    quantity DOUBLE PRECISION NOT NULL,
+    vs_token_amount DOUBLE PRECISION NOT NULL,
+    expected_price DOUBLE PRECISION NOT NULL,
+    execution_price DOUBLE PRECISION NOT NULL,
+    slippage_bps DOUBLE PRECISION NOT NULL,
+    max_slippage_bps INTEGER NOT NULL,
+    price_source VARCHAR(50) NOT NULL,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    FOREIGN KEY (position_id) REFERENCES positions(id)
+);
+
+CREATE INDEX IF NOT EXISTS trades_position_id_idx ON trades (position_id);
+CREATE INDEX IF NOT EXISTS trades_user_pubkey_idx ON trades (user_pubkey);
@@ -0,0 +1,13 @@
+-- Add migration script here
+CREATE TABLE IF NOT EXISTS token_price_snapshots (
+    id BIGSERIAL PRIMARY KEY,
+    mint_pubkey VARCHAR(255) NOT NULL,
+    price DOUBLE PRECISION NOT NULL,
+    volume_24h_usd DOUBLE PRECISION NOT NULL,
+    liquidity DOUBLE PRECISION NOT NULL,
+    market_cap DOUBLE PRECISION NOT NULL,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    FOREIGN KEY (mint_pubkey) REFERENCES tokens(mint_pubkey)
+);
+
+CREATE INDEX IF NOT EXISTS token_price_snapshots_mint_created_idx ON token_price_snapshots (mint_pubkey, created_at);
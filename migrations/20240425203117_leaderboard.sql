@@ -0,0 +1,11 @@
+-- Add migration script here
+CREATE TABLE IF NOT EXISTS leaderboard_entries (
+    time_window VARCHAR(10) NOT NULL,
+    user_pubkey VARCHAR(255) NOT NULL,
+    positions_count INTEGER NOT NULL,
+    cost_basis_usd DOUBLE PRECISION NOT NULL,
+    pnl_abs_usd DOUBLE PRECISION NOT NULL,
+    pnl_pct DOUBLE PRECISION NOT NULL,
+    computed_at TIMESTAMPTZ NOT NULL,
+    PRIMARY KEY (time_window, user_pubkey)
+);
@@ -0,0 +1,19 @@
+-- Add migration script here
+CREATE TABLE IF NOT EXISTS spin_seeds (
+    id UUID PRIMARY KEY DEFAULT uuid_generate_v4(),
+    server_seed VARCHAR(64) NOT NULL,
+    server_seed_hash VARCHAR(64) NOT NULL,
+    used_at TIMESTAMPTZ,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT CURRENT_TIMESTAMP
+);
+
+CREATE TABLE IF NOT EXISTS spins (
+    id UUID PRIMARY KEY DEFAULT uuid_generate_v4(),
+    seed_id UUID NOT NULL UNIQUE,
+    client_seed VARCHAR(64) NOT NULL,
+    nonce BIGINT NOT NULL,
+    candidate_mints TEXT[] NOT NULL,
+    selected_mint VARCHAR(255) NOT NULL,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    FOREIGN KEY (seed_id) REFERENCES spin_seeds(id)
+);
@@ -0,0 +1,7 @@
+-- Add migration script here
+ALTER TABLE spins
+ADD COLUMN user_pubkey VARCHAR(255) DEFAULT NULL,
+ADD COLUMN position_id UUID UNIQUE DEFAULT NULL,
+ADD FOREIGN KEY (position_id) REFERENCES positions(id);
+
+CREATE INDEX IF NOT EXISTS spins_user_pubkey_created_idx ON spins (user_pubkey, created_at);
@@ -0,0 +1,4 @@
+-- Add migration script here
+ALTER TABLE spins
+ADD COLUMN mode VARCHAR(20) NOT NULL DEFAULT 'uniform',
+ADD COLUMN candidate_weights BIGINT[] DEFAULT NULL;
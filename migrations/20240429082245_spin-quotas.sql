@@ -0,0 +1,7 @@
+-- Add migration script here
+CREATE TABLE IF NOT EXISTS spin_quotas (
+    user_pubkey VARCHAR(255) PRIMARY KEY,
+    quota_day DATE NOT NULL,
+    spins_today INTEGER NOT NULL DEFAULT 0,
+    last_spin_at TIMESTAMPTZ DEFAULT NULL
+);
@@ -0,0 +1,38 @@
+-- Add migration script here
+UPDATE tgbotusers SET points = COALESCE(points, 0), referrals = COALESCE(referrals, 0);
+
+-- older rows could share an access code, the lowest user_id keeps it and the rest get a fresh one
+DO $$
+DECLARE
+    duplicate_user_id BIGINT;
+    new_code TEXT;
+BEGIN
+    FOR duplicate_user_id IN
+        SELECT user_id FROM (
+            SELECT user_id, ROW_NUMBER() OVER (PARTITION BY access_code ORDER BY user_id) AS occurrence
+            FROM tgbotusers
+            WHERE access_code IS NOT NULL
+        ) codes
+        WHERE occurrence > 1
+    LOOP
+        LOOP
+            new_code := UPPER(SUBSTR(MD5(RANDOM()::TEXT), 1, 8));
+            EXIT WHEN NOT EXISTS (SELECT 1 FROM tgbotusers WHERE access_code = new_code);
+        END LOOP;
+
+        UPDATE tgbotusers SET access_code = new_code WHERE user_id = duplicate_user_id;
+    END LOOP;
+END $$;
+
+-- referrers that were deleted or never registered would break the foreign key
+UPDATE tgbotusers SET referred_by = NULL
+WHERE referred_by IS NOT NULL
+AND referred_by NOT IN (SELECT user_id FROM tgbotusers);
+
+ALTER TABLE tgbotusers
+ALTER COLUMN points SET NOT NULL,
+ALTER COLUMN referrals SET NOT NULL,
+ADD FOREIGN KEY (referred_by) REFERENCES tgbotusers(user_id);
+
+CREATE UNIQUE INDEX IF NOT EXISTS tgbotusers_access_code_idx ON tgbotusers (access_code);
+CREATE INDEX IF NOT EXISTS tgbotusers_referred_by_idx ON tgbotusers (referred_by);
@@ -0,0 +1,15 @@
+-- Add migration script here
+ALTER TABLE users
+ADD COLUMN referred_by VARCHAR(255) DEFAULT NULL,
+ADD COLUMN points INTEGER NOT NULL DEFAULT 0,
+ADD COLUMN referral_rewarded_at TIMESTAMPTZ DEFAULT NULL,
+ADD FOREIGN KEY (referred_by) REFERENCES users(user_pubkey);
+
+CREATE TABLE IF NOT EXISTS referral_codes (
+    code VARCHAR(32) PRIMARY KEY,
+    user_pubkey VARCHAR(255) NOT NULL UNIQUE,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    FOREIGN KEY (user_pubkey) REFERENCES users(user_pubkey)
+);
+
+CREATE INDEX IF NOT EXISTS users_referred_by_idx ON users (referred_by);
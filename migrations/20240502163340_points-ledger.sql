@@ -0,0 +1,66 @@
+-- Add migration script here
+CREATE EXTENSION IF NOT EXISTS "uuid-ossp";
+
+-- every transaction is two or more entries whose amounts sum to zero,
+-- user accounts are wallet pubkeys or tg:<user_id>, system accounts are prefixed with system:
+CREATE TABLE IF NOT EXISTS points_ledger (
+    id BIGSERIAL PRIMARY KEY,
+    transaction_id UUID NOT NULL,
+    account VARCHAR(255) NOT NULL,
+    amount BIGINT NOT NULL,
+    kind VARCHAR(50) NOT NULL,
+    reference VARCHAR(255) DEFAULT NULL,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT CURRENT_TIMESTAMP
+);
+
+CREATE INDEX IF NOT EXISTS points_ledger_account_created_idx ON points_ledger (account, created_at);
+CREATE INDEX IF NOT EXISTS points_ledger_transaction_id_idx ON points_ledger (transaction_id);
+
+CREATE OR REPLACE FUNCTION points_ledger_check_balanced() RETURNS TRIGGER AS $$
+BEGIN
+    IF (SELECT SUM(amount) FROM points_ledger WHERE transaction_id = NEW.transaction_id) <> 0 THEN
+        RAISE EXCEPTION 'points transaction % is not balanced', NEW.transaction_id;
+    END IF;
+    RETURN NULL;
+END;
+$$ LANGUAGE plpgsql;
+
+CREATE CONSTRAINT TRIGGER points_ledger_balanced
+AFTER INSERT ON points_ledger
+DEFERRABLE INITIALLY DEFERRED
+FOR EACH ROW EXECUTE FUNCTION points_ledger_check_balanced();
+
+CREATE OR REPLACE FUNCTION points_ledger_reject_changes() RETURNS TRIGGER AS $$
+BEGIN
+    RAISE EXCEPTION 'points_ledger is append only';
+END;
+$$ LANGUAGE plpgsql;
+
+CREATE TRIGGER points_ledger_append_only
+BEFORE UPDATE OR DELETE ON points_ledger
+FOR EACH ROW EXECUTE FUNCTION points_ledger_reject_changes();
+
+CREATE OR REPLACE VIEW points_balances AS
+SELECT account, SUM(amount)::BIGINT AS balance
+FROM points_ledger
+GROUP BY account;
+
+-- carry over the balances kept in integer columns so far
+WITH opening_balances AS (
+    SELECT uuid_generate_v4() AS transaction_id, 'tg:' || user_id AS account, points
+    FROM tgbotusers
+    WHERE points <> 0
+    UNION ALL
+    SELECT uuid_generate_v4() AS transaction_id, user_pubkey AS account, points
+    FROM users
+    WHERE points <> 0
+)
+INSERT INTO points_ledger (transaction_id, account, amount, kind)
+SELECT transaction_id, account, points, 'opening_balance' FROM opening_balances
+UNION ALL
+SELECT transaction_id, 'system:rewards', -points, 'opening_balance' FROM opening_balances;
+
+ALTER TABLE tgbotusers DROP COLUMN points;
+ALTER TABLE users DROP COLUMN points;
+
+ALTER TABLE spin_quotas ADD COLUMN streak_days INTEGER NOT NULL DEFAULT 0;
@@ -0,0 +1,31 @@
+-- Add migration script here
+CREATE TABLE IF NOT EXISTS selection_runs (
+    id UUID PRIMARY KEY DEFAULT uuid_generate_v4(),
+    run_date DATE NOT NULL,
+    min_market_cap_usd DOUBLE PRECISION,
+    min_liquidity_usd DOUBLE PRECISION,
+    min_trades_24h INTEGER,
+    selection_size INTEGER,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT CURRENT_TIMESTAMP
+);
+
+CREATE INDEX IF NOT EXISTS selection_runs_run_date_idx ON selection_runs (run_date, created_at);
+
+ALTER TABLE selected_tokens ADD COLUMN run_id UUID;
+
+-- rows written before runs were recorded get one run per day, without filter values
+INSERT INTO selection_runs (run_date, created_at)
+SELECT (created_at AT TIME ZONE 'UTC')::DATE, MIN(created_at)
+FROM selected_tokens
+GROUP BY (created_at AT TIME ZONE 'UTC')::DATE;
+
+UPDATE selected_tokens
+SET run_id = selection_runs.id
+FROM selection_runs
+WHERE selection_runs.run_date = (selected_tokens.created_at AT TIME ZONE 'UTC')::DATE;
+
+ALTER TABLE selected_tokens
+ALTER COLUMN run_id SET NOT NULL,
+ADD FOREIGN KEY (run_id) REFERENCES selection_runs(id);
+
+CREATE INDEX IF NOT EXISTS selected_tokens_run_id_idx ON selected_tokens (run_id);
@@ -0,0 +1,16 @@
+-- Add migration script here
+-- single row, seeded at startup from secrets and edited through /admin/selection-config
+CREATE TABLE IF NOT EXISTS selection_config (
+    id BOOLEAN PRIMARY KEY DEFAULT TRUE CHECK (id),
+    min_market_cap_usd DOUBLE PRECISION NOT NULL,
+    min_liquidity_usd DOUBLE PRECISION NOT NULL,
+    min_trades_24h INTEGER NOT NULL,
+    selection_size INTEGER NOT NULL,
+    page_count INTEGER NOT NULL,
+    sort_by VARCHAR(30) NOT NULL,
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT CURRENT_TIMESTAMP
+);
+
+ALTER TABLE selection_runs
+ADD COLUMN page_count INTEGER,
+ADD COLUMN sort_by VARCHAR(30);
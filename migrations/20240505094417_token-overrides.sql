@@ -0,0 +1,40 @@
+-- Add migration script here
+CREATE TABLE IF NOT EXISTS token_overrides (
+    id UUID PRIMARY KEY DEFAULT uuid_generate_v4(),
+    mint_pubkey VARCHAR(255) NOT NULL UNIQUE,
+    kind VARCHAR(20) NOT NULL CHECK (kind IN ('exclude', 'force_include', 'pin')),
+    reason TEXT NOT NULL,
+    expires_at TIMESTAMPTZ,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT CURRENT_TIMESTAMP
+);
+
+-- exclusions the coin selector used to hardcode
+INSERT INTO token_overrides (mint_pubkey, kind, reason) VALUES
+    ('EPjFWdd5AufqSSqeM2qN1xzybapC8G4wEGGkZwyTDt1v', 'exclude', 'Stablecoin'),
+    ('Es9vMFrzaCERmJfrF4H2FYD4KCoNkY11McCe8BenwNYB', 'exclude', 'Stablecoin'),
+    ('7kbnvuGBxxj8AG9qp8Scn56muWGaRaFqxg1FsRp3PaFT', 'exclude', 'Stablecoin'),
+    ('So11111111111111111111111111111111111111112', 'exclude', 'Wrapped asset'),
+    ('JUPyiwrYJFskUPiHa7hkeR8VUtAeFoSYbKedZNsDvCN', 'exclude', 'Blue chip'),
+    ('bSo13r4TkiE4KumL71LsHTPpL2euBYLFx6h9HP3piy1', 'exclude', 'Liquid staking token'),
+    ('J1toso1uCk3RLmjorhTtrVwY9HJ7X8V9yYac6Y7kGCPn', 'exclude', 'Liquid staking token'),
+    ('mSoLzYCxHdYgdzU16g5QSh3i5K3z3KZK7ytfqcJm7So', 'exclude', 'Liquid staking token'),
+    ('7dHbWXmci3dT8UFYWYZweBLXgycu7Y3iL6trKn1Y7ARj', 'exclude', 'Liquid staking token'),
+    ('4k3Dyjzvzp8eMZWUXbBCjEvwSkkk59S5iCNLY3QrkX6R', 'exclude', 'Blue chip'),
+    ('ZEUS1aR7aX8DFFJf5QjWj2ftDDdNTroMNGo8YoQm3Gq', 'exclude', 'Blue chip'),
+    ('jtojtomepa8beP8AuQc6eXt5FriJwfFMwQx2v2f9mCL', 'exclude', 'Blue chip'),
+    ('85VBFQZC9TZkfaptBWjvUw7YbZjy52A6mjtPGjstQAmQ', 'exclude', 'Blue chip'),
+    ('HZ1JovNiVvGrGNiiYvEozEVgZ58xaU3RKwX8eACQBCt3', 'exclude', 'Blue chip'),
+    ('27G8MtK7VtTcCHkpASjSDdkWWYfoqT6ggEuKidVJidD4', 'exclude', 'Blue chip'),
+    ('7vfCXTUXx5WJV5JADk17DUJ4ksgau7utNKj4b963voxs', 'exclude', 'Wrapped asset'),
+    ('3NZ9JMVBmGAqocybic2c7LQCJScmgsAZ6vQqTDzcqmJh', 'exclude', 'Wrapped asset'),
+    ('rndrizKT3MK1iimdxRdWabcF7Zg7AR5T4nud4EkHBof', 'exclude', 'Blue chip'),
+    ('SHDWyBxihqiCj6YekG2GUr7wqKLeLAMK1gHZck9pL6y', 'exclude', 'Blue chip'),
+    ('LFNTYraetVioAPnGJht4yNg2aUZFXR776cMeN9VMjXp', 'exclude', 'Blue chip'),
+    ('orcaEKTdK7LKz57vaAYr9QeNsVEPfiu6QeMU1kektZE', 'exclude', 'Blue chip'),
+    ('hntyVP6YFm1Hg25TN9WGLqM12b8TQmcknKrdu1oxWux', 'exclude', 'Blue chip'),
+    ('nosXBVoaCTtYdLvKY6Csb4AC8JCdQKKAaWYtx2ZMoo7', 'exclude', 'Blue chip'),
+    ('mb1eu7TzEc71KxDpsmsKoucSSuuoGLv1drys1oP2jh6', 'exclude', 'Blue chip'),
+    ('SLNDpmoWTVADgEdndyvWzroNL7zSi1dF9PC3xHGtPwp', 'exclude', 'Blue chip'),
+    ('ATLASXmbPQxBUYbxPsV97usA3fPQYEqzQBUHgiFCUsXx', 'exclude', 'Blue chip')
+ON CONFLICT (mint_pubkey) DO NOTHING;
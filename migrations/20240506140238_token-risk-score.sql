@@ -0,0 +1,14 @@
+-- Add migration script here
+ALTER TABLE tokens ADD COLUMN risk_score INTEGER;
+
+ALTER TABLE selected_tokens ADD COLUMN risk_score INTEGER;
+
+ALTER TABLE selection_config
+ADD COLUMN max_risk_score INTEGER NOT NULL DEFAULT 20,
+ADD COLUMN max_top10_holder_share DOUBLE PRECISION NOT NULL DEFAULT 0.5,
+ADD COLUMN max_creator_share DOUBLE PRECISION NOT NULL DEFAULT 0.1;
+
+ALTER TABLE selection_runs
+ADD COLUMN max_risk_score INTEGER,
+ADD COLUMN max_top10_holder_share DOUBLE PRECISION,
+ADD COLUMN max_creator_share DOUBLE PRECISION;
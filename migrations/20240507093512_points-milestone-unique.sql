@@ -0,0 +1,19 @@
+-- Add migration script here
+-- concurrent buys could pay the same milestone twice, the extra credits stay but get their own reference
+ALTER TABLE points_ledger DISABLE TRIGGER points_ledger_append_only;
+
+UPDATE points_ledger SET reference = reference || ':duplicate:' || id
+WHERE id IN (
+    SELECT id FROM (
+        SELECT id, ROW_NUMBER() OVER (PARTITION BY account, reference ORDER BY id) AS occurrence
+        FROM points_ledger
+        WHERE kind = 'trading_milestone' AND amount > 0
+    ) credits
+    WHERE occurrence > 1
+);
+
+ALTER TABLE points_ledger ENABLE TRIGGER points_ledger_append_only;
+
+-- only the credit side, the rewards account pays every user's milestone under the same reference
+CREATE UNIQUE INDEX IF NOT EXISTS points_ledger_trading_milestone_idx ON points_ledger (account, kind, reference)
+WHERE kind = 'trading_milestone' AND amount > 0;
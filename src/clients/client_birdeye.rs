@@ -1,15 +1,28 @@
-use axum::http::{HeaderMap, HeaderValue};
-use reqwest::Client;
+use std::time::Duration;
+
+use reqwest::header::{HeaderMap, HeaderValue};
+use serde::de::DeserializeOwned;
 use crate::errors::api_errors::{Result, ApiError};
-use super::clients_structs::{ResponseSecurity, ResponseOverview, ResponseTokens};
+use super::{clients_structs::{ResponsePrice, ResponseSecurity, ResponseOverview, ResponseTokens}, external_http::{ExternalHttp, ExternalHttpError, UpstreamStats}, rate_limiter::RateLimiter};
+
+// the token list is a heavier query than the per-token endpoints
+const TOKEN_LIST_TIMEOUT: Duration = Duration::from_secs(20);
+const TOKEN_SECURITY_TIMEOUT: Duration = Duration::from_secs(10);
+const TOKEN_OVERVIEW_TIMEOUT: Duration = Duration::from_secs(10);
+// used as a live price fallback, so it gets the same budget as Jupiter
+const TOKEN_PRICE_TIMEOUT: Duration = Duration::from_secs(5);
+
+pub const BIRDEYE_DEFAULT_BASE_URL: &str = "https://public-api.birdeye.so";
 
 #[derive(Clone)]
 pub struct BirdeyeClient {
-    pub client: reqwest::Client
+    http: ExternalHttp,
+    base_url: String
 }
 
 impl BirdeyeClient {
-    pub fn new(birdeye_api_key: &str) -> Self {
+    /// `requests_per_sec` should match the rate limit of the API key's Birdeye tier.
+    pub fn new(birdeye_api_key: &str, base_url: &str, requests_per_sec: f64) -> Self {
         let mut headers = HeaderMap::new();
 
         headers.insert(
@@ -17,87 +30,80 @@ impl BirdeyeClient {
             HeaderValue::from_str(birdeye_api_key).expect("Failed to add header auth for birdeye")
         );
 
-        let client = Client::builder()
-            .default_headers(headers)
-            .build()
-            .expect("Failed to birdeye build client with headers");
+        let http = ExternalHttp::new("birdeye", headers)
+            .with_rate_limiter(RateLimiter::new(requests_per_sec));
+
+        Self { http, base_url: base_url.trim_end_matches('/').to_string() }
+    }
 
-        Self { client }
+    pub fn stats(&self) -> UpstreamStats {
+        self.http.stats()
+    }
+
+    async fn get<T: DeserializeOwned>(&self, query_url: &str, timeout: Duration, context: &str) -> Result<T> {
+        self.http.get_json::<T>(query_url, timeout)
+            .await
+            .map_err(|e| {
+                println!("Birdeye client failed in {}. Error: {}", context, e);
+
+                match e {
+                    ExternalHttpError::Deserialize(_) => ApiError::BirdeyeDeserializationFail,
+                    _ => ApiError::BirdeyeFetchFail
+                }
+            })
     }
 }
 
 impl BirdeyeClient {
-    pub async fn get_tokens_list(&self, page: u32) -> Result<ResponseTokens> {
+    pub async fn get_tokens_list(&self, page: u32, sort_by: &str) -> Result<ResponseTokens> {
         println!("->> {:<12} - get_tokens_list", "CLIENT");
 
         let offset = (page - 1) * 50;
 
         let query_url = format!(
-            "https://public-api.birdeye.so/defi/tokenlist?sort_by=v24hUSD&sort_type=desc&offset={}&limit=50",
+            "{}/defi/tokenlist?sort_by={}&sort_type=desc&offset={}&limit=50",
+            self.base_url,
+            sort_by,
             offset
         );
 
-        let response = self.client.get(query_url).send()
-            .await
-            .map_err(|e| {
-                println!("Birdeye client failed fetching data. Error: {}", e);
-                ApiError::BirdeyeFetchFail
-            })?
-            .json::<ResponseTokens>()
-            .await
-            .map_err(|e| {
-                println!("Birdeye client failed deserializing data in get_tokens_list. Error: {}", e);
-                ApiError::BirdeyeDeserializationFail
-            })?;
-            
-        Ok(response)
+        self.get::<ResponseTokens>(&query_url, TOKEN_LIST_TIMEOUT, "get_tokens_list").await
     }
 
     pub async fn get_token_security(&self, token_pubkey: &str) -> Result<ResponseSecurity> {
         println!("->> {:<12} - get_token_security", "CLIENT");
 
         let query_url = format!(
-            "https://public-api.birdeye.so/defi/token_security?address={}",
+            "{}/defi/token_security?address={}",
+            self.base_url,
             token_pubkey
         );
 
-        let response = self.client.get(query_url).send()
-            .await
-            .map_err(|e| {
-                println!("Birdeye client failed fetching data. Error: {}", e);
-                ApiError::BirdeyeFetchFail
-            })?
-            .json::<ResponseSecurity>()
-            .await
-            .map_err(|e| {
-                println!("Birdeye client failed deserializing data in get_token_security. Token pubkey: {}. Error: {}", token_pubkey, e);
-                ApiError::BirdeyeDeserializationFail
-            })?;
-
-        Ok(response)
+        self.get::<ResponseSecurity>(&query_url, TOKEN_SECURITY_TIMEOUT, &format!("get_token_security for pubkey: {}", token_pubkey)).await
     }
 
     pub async fn get_token_overview(&self, token_pubkey: &str) -> Result<ResponseOverview> {
         println!("->> {:<12} - get_token_overview", "CLIENT");
 
         let query_url = format!(
-            "https://public-api.birdeye.so/defi/token_overview?address={}",
+            "{}/defi/token_overview?address={}",
+            self.base_url,
             token_pubkey
         );
 
-        let response = self.client.get(query_url).send()
-            .await
-            .map_err(|e| {
-                println!("Birdeye client failed fetching data in get_token_overview for pubkey: {}. Error: {}", token_pubkey, e);
-                ApiError::BirdeyeFetchFail
-            })?
-            .json::<ResponseOverview>()
-            .await
-            .map_err(|e| {
-                println!("Birdeye client failed deserializing data in get_token_overview for pubkey: {}. Error: {}", token_pubkey, e);
-                ApiError::BirdeyeDeserializationFail
-            })?;
+        self.get::<ResponseOverview>(&query_url, TOKEN_OVERVIEW_TIMEOUT, &format!("get_token_overview for pubkey: {}", token_pubkey)).await
+    }
+
+    /// USD price of one token.
+    pub async fn get_token_price(&self, token_pubkey: &str) -> Result<ResponsePrice> {
+        println!("->> {:<12} - get_token_price", "CLIENT");
+
+        let query_url = format!(
+            "{}/defi/price?address={}",
+            self.base_url,
+            token_pubkey
+        );
 
-        Ok(response)
+        self.get::<ResponsePrice>(&query_url, TOKEN_PRICE_TIMEOUT, &format!("get_token_price for pubkey: {}", token_pubkey)).await
     }
 }
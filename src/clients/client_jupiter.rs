@@ -1,37 +1,71 @@
+use std::{collections::HashMap, time::Duration};
 
+use reqwest::header::HeaderMap;
 use crate::errors::api_errors::{Result, ApiError};
-use super::clients_structs::JupiterResponse;
-pub struct JupiterClient;
+use super::{clients_structs::JupiterResponse, external_http::{ExternalHttp, ExternalHttpError, UpstreamStats}};
+
+// prices sit on the trade path, so fail fast rather than hold a buy open
+const PRICE_TIMEOUT: Duration = Duration::from_secs(5);
+
+pub const JUPITER_DEFAULT_BASE_URL: &str = "https://price.jup.ag/v4";
+
+#[derive(Clone)]
+pub struct JupiterClient {
+    http: ExternalHttp,
+    base_url: String
+}
 
 impl JupiterClient {
-    pub async fn get_token_price(
-        token_pubkey: &str,
+    pub fn new(base_url: &str) -> Self {
+        Self {
+            http: ExternalHttp::new("jupiter", HeaderMap::new()),
+            base_url: base_url.trim_end_matches('/').to_string()
+        }
+    }
+
+    pub fn stats(&self) -> UpstreamStats {
+        self.http.stats()
+    }
+
+    async fn get_prices(&self, query_url: &str) -> Result<JupiterResponse> {
+        self.http.get_json::<JupiterResponse>(query_url, PRICE_TIMEOUT)
+            .await
+            .map_err(|e| match e {
+                ExternalHttpError::Deserialize(_) => {
+                    println!("Jupiter client failed deserializing data. Error: {}", e);
+                    ApiError::JupiterDeserializationFail
+                },
+                _ => {
+                    println!("Jupiter client failed fetching data. Error: {}", e);
+                    ApiError::JupiterFetchFail
+                }
+            })
+    }
+}
+
+impl JupiterClient {
+    /// Fetches every price in one request. Mints Jupiter has no price for are left out of the map.
+    pub async fn get_token_prices(
+        &self,
+        token_pubkeys: &[String],
         vs_token_symbol: &str
-    ) -> Result<f64> {
-        println!("->> {:<12} - get_token_price", "CLIENT");
+    ) -> Result<HashMap<String, f64>> {
+        println!("->> {:<12} - get_token_prices", "CLIENT");
 
         let query_url = format!(
-            "https://price.jup.ag/v4/price?ids={}&vsToken={}",
-            token_pubkey,
+            "{}/price?ids={}&vsToken={}",
+            self.base_url,
+            token_pubkeys.join(","),
             vs_token_symbol
         );
 
-        let response = reqwest::get(query_url)
-            .await
-            .map_err(|e| {
-                println!("Jupiter client failed fetching data. Error: {}", e);
-                ApiError::JupiterFetchFail
-            })?
-            .json::<JupiterResponse>()
-            .await
-            .map_err(|e| {
-                println!("Jupiter client failed deserializing data. Error: {}", e);
-                ApiError::JupiterDeserializationFail
-            })?;
+        let response = self.get_prices(&query_url).await?;
 
-        
-        let new_price = response.data.get(token_pubkey).unwrap().price;
+        let prices = response.data
+            .into_iter()
+            .map(|(token_pubkey, token_data)| (token_pubkey, token_data.price))
+            .collect();
 
-        Ok(new_price)
+        Ok(prices)
     }
 }
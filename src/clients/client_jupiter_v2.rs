@@ -0,0 +1,76 @@
+use std::{collections::HashMap, time::Duration};
+
+use reqwest::header::HeaderMap;
+use crate::errors::api_errors::{Result, ApiError};
+use super::{clients_structs::JupiterV2Response, external_http::{ExternalHttp, ExternalHttpError, UpstreamStats}};
+
+const PRICE_TIMEOUT: Duration = Duration::from_secs(5);
+
+pub const JUPITER_V2_DEFAULT_BASE_URL: &str = "https://api.jup.ag/price/v2";
+
+/// Jupiter's Price API v2, the replacement for the deprecated v4 endpoint. Quotes are against a vs token mint, not a symbol.
+#[derive(Clone)]
+pub struct JupiterV2Client {
+    http: ExternalHttp,
+    base_url: String
+}
+
+impl JupiterV2Client {
+    pub fn new(base_url: &str) -> Self {
+        Self {
+            http: ExternalHttp::new("jupiter_v2", HeaderMap::new()),
+            base_url: base_url.trim_end_matches('/').to_string()
+        }
+    }
+
+    pub fn stats(&self) -> UpstreamStats {
+        self.http.stats()
+    }
+}
+
+impl JupiterV2Client {
+    /// Fetches every price in one request. Mints Jupiter has no price for are left out of the map.
+    pub async fn get_token_prices(
+        &self,
+        token_pubkeys: &[String],
+        vs_token_pubkey: &str
+    ) -> Result<HashMap<String, f64>> {
+        println!("->> {:<12} - get_token_prices_v2", "CLIENT");
+
+        let query_url = format!(
+            "{}?ids={}&vsToken={}",
+            self.base_url,
+            token_pubkeys.join(","),
+            vs_token_pubkey
+        );
+
+        let response = self.http.get_json::<JupiterV2Response>(&query_url, PRICE_TIMEOUT)
+            .await
+            .map_err(|e| match e {
+                ExternalHttpError::Deserialize(_) => {
+                    println!("Jupiter v2 client failed deserializing data. Error: {}", e);
+                    ApiError::JupiterDeserializationFail
+                },
+                _ => {
+                    println!("Jupiter v2 client failed fetching data. Error: {}", e);
+                    ApiError::JupiterFetchFail
+                }
+            })?;
+
+        let mut prices = HashMap::with_capacity(response.data.len());
+
+        for (token_pubkey, token_data) in response.data {
+            let Some(token_data) = token_data else { continue };
+
+            match token_data.price.parse::<f64>() {
+                Ok(price) => { prices.insert(token_pubkey, price); },
+                Err(e) => {
+                    println!("Jupiter v2 client got an invalid price for pubkey: {}. Error: {}", token_pubkey, e);
+                    return Err(ApiError::JupiterDeserializationFail)
+                }
+            }
+        }
+
+        Ok(prices)
+    }
+}
@@ -6,6 +6,7 @@ use std::collections::HashMap;
 // TOKEN SECURITY
 
 #[derive(Deserialize, Debug)]
+#[allow(dead_code)]
 pub struct ResponseSecurity {
     pub data: SecurityData,
     pub success: bool,
@@ -19,11 +20,26 @@ pub struct SecurityData {
     pub owner_address: Option<String>,
     #[serde(rename = "freezeAuthority")]
     pub freeze_authority: Option<String>,
+    #[serde(rename = "mintAuthority")]
+    pub mint_authority: Option<String>,
+    /// Share of supply held by the 10 largest holders, 0.35 is 35%.
+    #[serde(rename = "top10HolderPercent")]
+    pub top10_holder_percent: Option<f64>,
+    #[serde(rename = "mutableMetadata")]
+    pub mutable_metadata: Option<bool>,
+    #[serde(rename = "transferFeeEnable")]
+    pub transfer_fee_enable: Option<bool>,
+    #[serde(rename = "creatorBalance")]
+    pub creator_balance: Option<f64>,
+    /// Share of supply still held by the creator, 0.05 is 5%.
+    #[serde(rename = "creatorPercentage")]
+    pub creator_percentage: Option<f64>,
 }
 
 // TOKEN OVERVIEW
 
 #[derive(Deserialize, Debug)]
+#[allow(dead_code)]
 pub struct ResponseOverview {
     pub data: OverviewData,
     pub success: bool,
@@ -38,6 +54,10 @@ pub struct OverviewData {
     pub price_change_24h_percent: Option<f64>,
     #[serde(rename = "v24hUSD")]
     pub volume_24h_usd: Option<f64>,
+    pub price: Option<f64>,
+    pub liquidity: Option<f64>,
+    #[serde(rename = "mc")]
+    pub market_cap: Option<f64>,
     pub extensions: Option<OverviewExtensionData>
 }
 
@@ -76,6 +96,22 @@ pub struct TokenFromClient {
     pub volume_24h_usd: f64,
 }
 
+// TOKEN PRICE
+
+#[derive(Deserialize, Debug)]
+#[allow(dead_code)]
+pub struct ResponsePrice {
+    /// `None` when Birdeye has no price for the address.
+    pub data: Option<PriceData>,
+    pub success: bool,
+}
+
+#[derive(Deserialize, Debug)]
+pub struct PriceData {
+    /// USD price.
+    pub value: f64,
+}
+
 // JUPITER API
 
 #[derive(Deserialize, Debug)]
@@ -88,3 +124,17 @@ pub struct JupiterTokenData {
     pub price: f64
 }
 
+// JUPITER PRICE API V2
+
+#[derive(Deserialize, Debug)]
+pub struct JupiterV2Response {
+    /// Mints Jupiter can't price map to `None`.
+    pub data: HashMap<String, Option<JupiterV2TokenData>>,
+}
+
+#[derive(Deserialize, Debug)]
+pub struct JupiterV2TokenData {
+    /// Decimal string, e.g. "132.28".
+    pub price: String
+}
+
@@ -0,0 +1,406 @@
+use std::{fmt, sync::{atomic::{AtomicU64, Ordering}, Arc, Mutex}, time::{Duration, Instant}};
+
+use rand::Rng;
+use reqwest::{header::{HeaderMap, RETRY_AFTER}, Client, Response, StatusCode};
+use serde::{de::DeserializeOwned, Serialize};
+
+use super::rate_limiter::RateLimiter;
+
+const MAX_ATTEMPTS: u32 = 3;
+const RETRY_BASE_DELAY: Duration = Duration::from_millis(500);
+const RETRY_MAX_DELAY: Duration = Duration::from_secs(10);
+/// A `Retry-After` longer than this fails the request instead of holding it.
+const MAX_RETRY_AFTER: Duration = Duration::from_secs(30);
+/// Consecutive failed requests that open the circuit.
+const BREAKER_FAILURE_THRESHOLD: u32 = 5;
+/// How long an open circuit rejects requests before letting one through again.
+const BREAKER_COOLDOWN: Duration = Duration::from_secs(30);
+
+#[derive(Debug)]
+pub enum ExternalHttpError {
+    /// The upstream failed too often recently, the request was not sent.
+    CircuitOpen,
+    Request(reqwest::Error),
+    Status(StatusCode),
+    Deserialize(reqwest::Error),
+}
+
+impl fmt::Display for ExternalHttpError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ExternalHttpError::CircuitOpen => write!(f, "circuit open"),
+            ExternalHttpError::Request(e) => write!(f, "request failed: {}", e),
+            ExternalHttpError::Status(status) => write!(f, "status {}", status),
+            ExternalHttpError::Deserialize(e) => write!(f, "deserialization failed: {}", e),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Serialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum CircuitState {
+    Closed,
+    Open,
+    /// Cooldown is over, a single trial request decides whether the circuit closes or opens again.
+    HalfOpen,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Admission {
+    Admitted,
+    /// The half open trial, every other request is rejected until it reports back.
+    Trial,
+    Rejected
+}
+
+#[derive(Debug, Serialize)]
+pub struct UpstreamStats {
+    pub upstream: &'static str,
+    /// Attempts sent, retries included.
+    pub requests: u64,
+    /// Attempts that failed, from transport errors, error statuses or bad bodies.
+    pub errors: u64,
+    pub retries: u64,
+    /// Requests rejected without being sent because the circuit was open.
+    pub short_circuited: u64,
+    pub circuit_state: CircuitState
+}
+
+#[derive(Default)]
+struct UpstreamMetrics {
+    requests: AtomicU64,
+    errors: AtomicU64,
+    retries: AtomicU64,
+    short_circuited: AtomicU64
+}
+
+#[derive(Default)]
+struct CircuitBreaker {
+    consecutive_failures: u32,
+    open_until: Option<Instant>,
+    trial_in_flight: bool
+}
+
+impl CircuitBreaker {
+    fn state(&self, now: Instant) -> CircuitState {
+        match self.open_until {
+            Some(open_until) if now < open_until && !self.trial_in_flight => CircuitState::Open,
+            Some(_) => CircuitState::HalfOpen,
+            None => CircuitState::Closed
+        }
+    }
+
+    /// Letting the trial through re-arms the cooldown, so a trial that never reports back
+    /// (its request was dropped) only holds the circuit until the next cooldown ends.
+    fn admit(&mut self, now: Instant) -> Admission {
+        match self.open_until {
+            None => Admission::Admitted,
+            Some(open_until) if now < open_until => Admission::Rejected,
+            Some(_) => {
+                self.open_until = Some(now + BREAKER_COOLDOWN);
+                self.trial_in_flight = true;
+                Admission::Trial
+            }
+        }
+    }
+
+    fn record_success(&mut self) {
+        self.consecutive_failures = 0;
+        self.open_until = None;
+        self.trial_in_flight = false;
+    }
+
+    /// Past the threshold every failure, including a failed half open trial, restarts the cooldown.
+    fn record_failure(&mut self, now: Instant) {
+        self.consecutive_failures += 1;
+        self.trial_in_flight = false;
+
+        if self.consecutive_failures >= BREAKER_FAILURE_THRESHOLD {
+            self.open_until = Some(now + BREAKER_COOLDOWN);
+        }
+    }
+
+    /// The trial ended in a way that says nothing about the upstream, the next request gets to try instead.
+    fn release_trial(&mut self, now: Instant) {
+        if self.trial_in_flight {
+            self.trial_in_flight = false;
+            self.open_until = Some(now);
+        }
+    }
+}
+
+/// HTTP client shared by the external API clients. Retries 429s, 5xx responses and transport errors
+/// with jittered exponential backoff, honors `Retry-After`, and stops calling an upstream that keeps failing.
+/// Clones share the connection pool, rate limiter, circuit breaker and metrics.
+#[derive(Clone)]
+pub struct ExternalHttp {
+    upstream: &'static str,
+    client: Client,
+    rate_limiter: Option<RateLimiter>,
+    breaker: Arc<Mutex<CircuitBreaker>>,
+    metrics: Arc<UpstreamMetrics>
+}
+
+impl ExternalHttp {
+    pub fn new(upstream: &'static str, default_headers: HeaderMap) -> Self {
+        let client = Client::builder()
+            .default_headers(default_headers)
+            .build()
+            .unwrap_or_else(|e| panic!("Failed to build {} http client. Error: {}", upstream, e));
+
+        Self {
+            upstream,
+            client,
+            rate_limiter: None,
+            breaker: Arc::new(Mutex::new(CircuitBreaker::default())),
+            metrics: Arc::new(UpstreamMetrics::default())
+        }
+    }
+
+    pub fn with_rate_limiter(mut self, rate_limiter: RateLimiter) -> Self {
+        self.rate_limiter = Some(rate_limiter);
+        self
+    }
+
+    pub fn stats(&self) -> UpstreamStats {
+        UpstreamStats {
+            upstream: self.upstream,
+            requests: self.metrics.requests.load(Ordering::Relaxed),
+            errors: self.metrics.errors.load(Ordering::Relaxed),
+            retries: self.metrics.retries.load(Ordering::Relaxed),
+            short_circuited: self.metrics.short_circuited.load(Ordering::Relaxed),
+            circuit_state: self.breaker.lock().expect("circuit breaker lock poisoned").state(Instant::now())
+        }
+    }
+
+    /// GETs `url` and deserializes the body, giving each attempt `timeout` to complete.
+    pub async fn get_json<T: DeserializeOwned>(
+        &self,
+        url: &str,
+        timeout: Duration
+    ) -> std::result::Result<T, ExternalHttpError> {
+        let admission = self.breaker.lock().expect("circuit breaker lock poisoned").admit(Instant::now());
+
+        if admission == Admission::Rejected {
+            self.metrics.short_circuited.fetch_add(1, Ordering::Relaxed);
+            return Err(ExternalHttpError::CircuitOpen)
+        }
+
+        let result = self.send_with_retries(url, timeout).await;
+
+        self.record_outcome(&result, admission == Admission::Trial);
+
+        result?.json::<T>()
+            .await
+            .map_err(|e| {
+                self.metrics.errors.fetch_add(1, Ordering::Relaxed);
+                ExternalHttpError::Deserialize(e)
+            })
+    }
+
+    /// Only transient failures count against the upstream, a bad request or body is on our side.
+    fn record_outcome(&self, result: &std::result::Result<Response, ExternalHttpError>, is_trial: bool) {
+        let mut breaker = self.breaker.lock().expect("circuit breaker lock poisoned");
+
+        match result {
+            Ok(_) => breaker.record_success(),
+            Err(ExternalHttpError::Request(_)) => breaker.record_failure(Instant::now()),
+            Err(ExternalHttpError::Status(status)) if is_transient_status(*status) => breaker.record_failure(Instant::now()),
+            Err(_) if is_trial => breaker.release_trial(Instant::now()),
+            Err(_) => {}
+        }
+    }
+
+    async fn send_with_retries(
+        &self,
+        url: &str,
+        timeout: Duration
+    ) -> std::result::Result<Response, ExternalHttpError> {
+        let mut attempt = 1;
+
+        loop {
+            if let Some(rate_limiter) = &self.rate_limiter {
+                rate_limiter.acquire().await;
+            }
+
+            if attempt > 1 {
+                self.metrics.retries.fetch_add(1, Ordering::Relaxed);
+            }
+
+            self.metrics.requests.fetch_add(1, Ordering::Relaxed);
+
+            let (error, retry_after) = match self.client.get(url).timeout(timeout).send().await {
+                Ok(response) if response.status().is_success() => return Ok(response),
+                Ok(response) if is_transient_status(response.status()) => {
+                    let retry_after = parse_retry_after(response.headers());
+                    (ExternalHttpError::Status(response.status()), retry_after)
+                },
+                Ok(response) => {
+                    self.metrics.errors.fetch_add(1, Ordering::Relaxed);
+                    return Err(ExternalHttpError::Status(response.status()))
+                },
+                Err(e) if e.is_timeout() || e.is_connect() || e.is_request() => (ExternalHttpError::Request(e), None),
+                Err(e) => {
+                    self.metrics.errors.fetch_add(1, Ordering::Relaxed);
+                    return Err(ExternalHttpError::Request(e))
+                }
+            };
+
+            self.metrics.errors.fetch_add(1, Ordering::Relaxed);
+
+            if attempt >= MAX_ATTEMPTS || retry_after.is_some_and(|retry_after| retry_after > MAX_RETRY_AFTER) {
+                return Err(error)
+            }
+
+            let delay = retry_after.unwrap_or_else(|| backoff_delay(attempt));
+
+            println!("->> {:<12} - retrying {} after attempt {} in {:?}. Error: {}", "HTTP", self.upstream, attempt, delay, error);
+
+            tokio::time::sleep(delay).await;
+            attempt += 1;
+        }
+    }
+}
+
+fn is_transient_status(status: StatusCode) -> bool {
+    status == StatusCode::TOO_MANY_REQUESTS || status.is_server_error()
+}
+
+/// Exponential backoff with jitter, drawn between half and all of the exponential delay.
+fn backoff_delay(attempt: u32) -> Duration {
+    let delay = RETRY_BASE_DELAY
+        .saturating_mul(2u32.saturating_pow(attempt - 1))
+        .min(RETRY_MAX_DELAY);
+
+    rand::thread_rng().gen_range(delay / 2..=delay)
+}
+
+/// Only the delay-seconds form, HTTP dates fall back to regular backoff.
+fn parse_retry_after(headers: &HeaderMap) -> Option<Duration> {
+    headers.get(RETRY_AFTER)?
+        .to_str().ok()?
+        .trim()
+        .parse::<u64>().ok()
+        .map(Duration::from_secs)
+}
+
+#[cfg(test)]
+mod tests {
+    use reqwest::header::HeaderValue;
+
+    use super::*;
+
+    fn open_breaker(now: Instant) -> CircuitBreaker {
+        let mut breaker = CircuitBreaker::default();
+
+        for _ in 0..BREAKER_FAILURE_THRESHOLD {
+            breaker.record_failure(now);
+        }
+
+        breaker
+    }
+
+    fn retry_after_headers(value: &str) -> HeaderMap {
+        let mut headers = HeaderMap::new();
+        headers.insert(RETRY_AFTER, HeaderValue::from_str(value).unwrap());
+        headers
+    }
+
+    #[test]
+    fn backoff_doubles_within_jitter_and_caps() {
+        for _ in 0..100 {
+            let first = backoff_delay(1);
+            assert!(first >= RETRY_BASE_DELAY / 2 && first <= RETRY_BASE_DELAY);
+
+            let third = backoff_delay(3);
+            assert!(third >= RETRY_BASE_DELAY * 2 && third <= RETRY_BASE_DELAY * 4);
+
+            let capped = backoff_delay(30);
+            assert!(capped >= RETRY_MAX_DELAY / 2 && capped <= RETRY_MAX_DELAY);
+        }
+    }
+
+    #[test]
+    fn parses_retry_after_seconds_only() {
+        assert_eq!(parse_retry_after(&retry_after_headers("5")), Some(Duration::from_secs(5)));
+        assert_eq!(parse_retry_after(&retry_after_headers(" 12 ")), Some(Duration::from_secs(12)));
+        assert_eq!(parse_retry_after(&retry_after_headers("Wed, 21 Oct 2015 07:28:00 GMT")), None);
+        assert_eq!(parse_retry_after(&retry_after_headers("-1")), None);
+        assert_eq!(parse_retry_after(&HeaderMap::new()), None);
+    }
+
+    #[test]
+    fn opens_after_consecutive_failures() {
+        let now = Instant::now();
+        let mut breaker = CircuitBreaker::default();
+
+        for _ in 1..BREAKER_FAILURE_THRESHOLD {
+            breaker.record_failure(now);
+        }
+        assert_eq!(breaker.state(now), CircuitState::Closed);
+        assert_eq!(breaker.admit(now), Admission::Admitted);
+
+        breaker.record_failure(now);
+        assert_eq!(breaker.state(now), CircuitState::Open);
+        assert_eq!(breaker.admit(now + BREAKER_COOLDOWN / 2), Admission::Rejected);
+    }
+
+    #[test]
+    fn success_resets_the_failure_count() {
+        let now = Instant::now();
+        let mut breaker = CircuitBreaker::default();
+
+        for _ in 1..BREAKER_FAILURE_THRESHOLD {
+            breaker.record_failure(now);
+        }
+        breaker.record_success();
+        breaker.record_failure(now);
+
+        assert_eq!(breaker.state(now), CircuitState::Closed);
+    }
+
+    #[test]
+    fn half_open_lets_a_single_trial_through() {
+        let now = Instant::now();
+        let mut breaker = open_breaker(now);
+        let after_cooldown = now + BREAKER_COOLDOWN;
+
+        assert_eq!(breaker.state(after_cooldown), CircuitState::HalfOpen);
+        assert_eq!(breaker.admit(after_cooldown), Admission::Trial);
+        assert_eq!(breaker.admit(after_cooldown), Admission::Rejected);
+        assert_eq!(breaker.state(after_cooldown), CircuitState::HalfOpen);
+
+        breaker.record_success();
+        assert_eq!(breaker.state(after_cooldown), CircuitState::Closed);
+        assert_eq!(breaker.admit(after_cooldown), Admission::Admitted);
+    }
+
+    #[test]
+    fn failed_trial_reopens_the_circuit() {
+        let now = Instant::now();
+        let mut breaker = open_breaker(now);
+        let after_cooldown = now + BREAKER_COOLDOWN;
+
+        assert_eq!(breaker.admit(after_cooldown), Admission::Trial);
+        breaker.record_failure(after_cooldown);
+
+        assert_eq!(breaker.state(after_cooldown), CircuitState::Open);
+        assert_eq!(breaker.admit(after_cooldown + BREAKER_COOLDOWN / 2), Admission::Rejected);
+        assert_eq!(breaker.admit(after_cooldown + BREAKER_COOLDOWN), Admission::Trial);
+    }
+
+    #[test]
+    fn released_or_abandoned_trials_let_another_through() {
+        let now = Instant::now();
+        let mut breaker = open_breaker(now);
+        let after_cooldown = now + BREAKER_COOLDOWN;
+
+        assert_eq!(breaker.admit(after_cooldown), Admission::Trial);
+        breaker.release_trial(after_cooldown);
+        assert_eq!(breaker.admit(after_cooldown), Admission::Trial);
+
+        // never reports back
+        assert_eq!(breaker.admit(after_cooldown + BREAKER_COOLDOWN / 2), Admission::Rejected);
+        assert_eq!(breaker.admit(after_cooldown + BREAKER_COOLDOWN), Admission::Trial);
+    }
+}
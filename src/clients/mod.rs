@@ -1,3 +1,6 @@
 pub mod client_jupiter;
 pub mod client_birdeye;
-pub mod clients_structs;
\ No newline at end of file
+pub mod clients_structs;
+pub mod rate_limiter;
+pub mod external_http;
+pub mod client_jupiter_v2;
\ No newline at end of file
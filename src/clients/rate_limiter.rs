@@ -0,0 +1,110 @@
+use std::{sync::Arc, time::Duration};
+
+use tokio::{sync::Mutex, time::Instant};
+
+/// Token bucket shared by every clone, so all callers of an API draw from one budget.
+#[derive(Clone)]
+pub struct RateLimiter {
+    bucket: Arc<Mutex<Bucket>>,
+    capacity: f64,
+    refill_per_sec: f64
+}
+
+struct Bucket {
+    tokens: f64,
+    last_refill: Instant
+}
+
+impl RateLimiter {
+    /// Allows `requests_per_sec` on average, with bursts of up to the same amount.
+    /// Panics unless the rate is positive and finite, a zero rate would never refill.
+    pub fn new(requests_per_sec: f64) -> Self {
+        assert!(
+            requests_per_sec.is_finite() && requests_per_sec > 0.0,
+            "Rate limit must be a positive number of requests per second, got: {}", requests_per_sec
+        );
+
+        let capacity = requests_per_sec.max(1.0);
+
+        Self {
+            bucket: Arc::new(Mutex::new(Bucket { tokens: capacity, last_refill: Instant::now() })),
+            capacity,
+            refill_per_sec: requests_per_sec
+        }
+    }
+
+    /// Waits until a request may be sent.
+    pub async fn acquire(&self) {
+        loop {
+            let wait = {
+                let mut bucket = self.bucket.lock().await;
+
+                let now = Instant::now();
+                let elapsed = now.duration_since(bucket.last_refill).as_secs_f64();
+
+                bucket.tokens = (bucket.tokens + elapsed * self.refill_per_sec).min(self.capacity);
+                bucket.last_refill = now;
+
+                if bucket.tokens >= 1.0 {
+                    bucket.tokens -= 1.0;
+                    return
+                }
+
+                Duration::from_secs_f64((1.0 - bucket.tokens) / self.refill_per_sec)
+            };
+
+            tokio::time::sleep(wait).await;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // the clock is paused and only moves when every task sleeps, so the waits are exact
+    #[tokio::test(start_paused = true)]
+    async fn allows_a_burst_then_paces_requests() {
+        let rate_limiter = RateLimiter::new(20.0);
+        let started = Instant::now();
+
+        for _ in 0..20 {
+            rate_limiter.acquire().await;
+        }
+        assert_eq!(started.elapsed(), Duration::ZERO);
+
+        rate_limiter.acquire().await;
+        rate_limiter.acquire().await;
+
+        // two more tokens take 100ms to refill at 20 per second
+        assert_eq!(started.elapsed(), Duration::from_millis(100));
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn shares_the_budget_between_clones() {
+        let rate_limiter = RateLimiter::new(10.0);
+        let clone = rate_limiter.clone();
+        let started = Instant::now();
+
+        for _ in 0..5 {
+            rate_limiter.acquire().await;
+            clone.acquire().await;
+        }
+        assert_eq!(started.elapsed(), Duration::ZERO);
+
+        clone.acquire().await;
+        assert_eq!(started.elapsed(), Duration::from_millis(100));
+    }
+
+    #[test]
+    #[should_panic(expected = "positive number")]
+    fn rejects_a_zero_rate() {
+        RateLimiter::new(0.0);
+    }
+
+    #[test]
+    #[should_panic(expected = "positive number")]
+    fn rejects_a_negative_rate() {
+        RateLimiter::new(-1.0);
+    }
+}
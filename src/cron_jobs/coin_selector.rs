@@ -1,16 +1,27 @@
 use std::collections::HashSet;
 
+use futures::{stream, StreamExt};
 use tokio_cron_scheduler::Job;
 
 use crate::{
-    clients::{client_birdeye::BirdeyeClient, clients_structs::TokenFromClient}, 
+    clients::{client_birdeye::BirdeyeClient, clients_structs::{SecurityData, TokenFromClient}}, 
     errors::cron_errors::{CronError, Result}, 
-    models::model_token::{Token, TokenForCreate}, 
+    models::{model_selection::{SelectedToken, SelectionConfig, SelectionRunForCreate}, model_token::TokenForCreate, model_token_override::SelectionOverrides}, 
     AppState
 };
 
 use super::cron_structs::TokenForCron;
 
+/// Birdeye requests in flight at once, the client's rate limiter still paces them.
+const BIRDEYE_CONCURRENCY: usize = 4;
+
+// risk score weights, they add up to MAX_RISK_SCORE
+const MINT_AUTHORITY_RISK: i32 = 30;
+const TRANSFER_FEE_RISK: i32 = 25;
+const TOP10_HOLDERS_RISK: i32 = 25;
+const CREATOR_BALANCE_RISK: i32 = 10;
+const MUTABLE_METADATA_RISK: i32 = 10;
+
 pub struct CoinSelector;
 
 impl CoinSelector {
@@ -55,40 +66,75 @@ impl CoinSelector {
 
         let birdeye_client = &state.birdeye_client;
 
-        let excluded_addresses = get_excluded_addresses();
-    
-        let list_response_1 = birdeye_client.get_tokens_list(1)
-            .await.map_err(|_| CronError::BirdeyeClientFail)?;
+        let config = state.selection_repo.get_selection_config()
+            .await.map_err(|_| CronError::SelectionConfigFetchFail)?;
 
-        let list_response_2 = birdeye_client.get_tokens_list(2)
-            .await.map_err(|_| CronError::BirdeyeClientFail)?;
+        let overrides = SelectionOverrides::from_overrides(
+            state.token_override_repo.get_active_token_overrides()
+                .await.map_err(|_| CronError::TokenOverridesFetchFail)?
+        );
 
-        let token_list = join_token_lists(
-            list_response_1.data.tokens, 
-            list_response_2.data.tokens,
+        let mut pinned_tokens = get_pinned_tokens(&overrides, state.clone()).await?;
+        pinned_tokens.truncate(config.selection_size as usize);
 
-        );
+        let open_slots = config.selection_size as usize - pinned_tokens.len();
+
+        let mut token_list = Vec::new();
+
+        for page in 1..=config.page_count.max(1) as u32 {
+            let list_response = birdeye_client.get_tokens_list(page, config.sort_key().birdeye_sort_by())
+                .await.map_err(|_| CronError::BirdeyeClientFail)?;
+
+            token_list.extend(list_response.data.tokens);
+        }
         
         let partially_filtered_tokens = filter_by_mc_liquidity_and_addresses(
             token_list, 
-            excluded_addresses
+            &overrides,
+            &config,
+            open_slots
         )?;
 
-        let fully_filtered_tokens = filter_by_24htrade_and_security(
+        let listed_tokens = filter_by_24htrade_and_security(
             partially_filtered_tokens, 
-            birdeye_client
+            birdeye_client,
+            &overrides,
+            &config,
+            open_slots
         ).await?;
 
+        let fully_filtered_tokens: Vec<TokenForCron> = pinned_tokens.into_iter()
+            .chain(listed_tokens)
+            .collect();
+
         println!("fully_filtered_tokens lenght: {}", fully_filtered_tokens.len());
 
         let current_active_tokens_pubkey: Vec<String> = get_current_active_pubkeys(state.clone()).await?;
 
+        let selection_run = SelectionRunForCreate {
+            config,
+            tokens: fully_filtered_tokens.iter()
+                .map(|token| SelectedToken {
+                    mint_pubkey: token.address.clone(),
+                    symbol: token.symbol.clone(),
+                    name: token.name.clone(),
+                    logo_url: token.logo_uri.clone(),
+                    price_change_24h_percent: token.price_change_24h_percent,
+                    volume_24h_usd: token.volume_24h_usd,
+                    risk_score: token.risk_score
+                })
+                .collect()
+        };
+
         update_or_create_tokens(
             fully_filtered_tokens, 
             current_active_tokens_pubkey, 
             state.clone()
         ).await?;
 
+        state.selection_repo.create_selection_run(selection_run)
+            .await.map_err(|_| CronError::SelectionArchiveFail)?;
+
         Ok(())
     }
 }
@@ -104,21 +150,26 @@ async fn update_or_create_tokens(
     let current_active_set: HashSet<String> = current_active_tokens_pubkey.clone().into_iter().collect();
 
     for token in token_list {
+        // a no-op for tokens created below, they get the score on insert
+        if let Some(risk_score) = token.risk_score {
+            state.token_repo.update_token_risk_score(&token.address, risk_score)
+                .await
+                .map_err(|_| CronError::UpdateTokenStatusFail)?;
+        }
+
         if current_active_tokens_pubkey.contains(&token.address) {
             println!("Token is currently active: {}", token.address);
             continue;
         } else {
             println!("Token is not currently active: {}", token.address);
-            match Token::get_token(&token.address, state.clone()
-            )
+            match state.token_repo.get_token(&token.address)
             .await
             .map_err(|_| CronError::UpdateTokenStatusFail)? {
                 Some(token) => {
                     println!("Token exists... changing it to active: {}", &token.mint_pubkey);
-                    Token::update_token_state(
+                    state.token_repo.update_token_state(
                         &token.mint_pubkey, 
-                        true, 
-                        state.clone()
+                        true
                     )
                     .await
                     .map_err(|_| CronError::UpdateTokenStatusFail)?
@@ -137,12 +188,12 @@ async fn update_or_create_tokens(
                         website_url: token.website,
                         telegram_url: token.telegram,
                         decimals: token.decimals,
-                        is_active: true
+                        is_active: true,
+                        risk_score: token.risk_score
                     };
 
-                    Token::create_token(
-                        new_token, 
-                        state.clone()
+                    state.token_repo.create_token(
+                        new_token
                     )
                     .await
                     .map_err(|_| CronError::UpdateTokenStatusFail)?;
@@ -153,10 +204,9 @@ async fn update_or_create_tokens(
 
     for pubkey in current_active_set.difference(&token_list_pubkeys) {
         println!("Token is no longer active: {}", pubkey);
-        Token::update_token_state(
+        state.token_repo.update_token_state(
             pubkey,
-             false, 
-            state.clone()
+             false
         )
         .await.map_err(|_| CronError::UpdateTokenStatusFail)?;
     }
@@ -168,7 +218,7 @@ async fn get_current_active_pubkeys(
     state: AppState
 ) -> Result<Vec<String>> {
         Ok(
-            Token::get_all_active_tokens(state.clone())
+            state.token_repo.get_all_active_tokens()
                 .await
                 .map_err(|_| CronError::UpdateTokenStatusFail)?
                 .into_iter()
@@ -177,119 +227,395 @@ async fn get_current_active_pubkeys(
         )
 }
 
-fn join_token_lists(
-    token_list_1: Vec<TokenFromClient>,
-    token_list_2: Vec<TokenFromClient>,
-) -> Vec<TokenFromClient> {
-    token_list_1
-        .into_iter()
-        .chain(token_list_2.into_iter())
-        .collect()
+/// Pinned tokens skip every filter, but must already be in the tokens table since Birdeye may not list them.
+async fn get_pinned_tokens(
+    overrides: &SelectionOverrides,
+    state: AppState
+) -> Result<Vec<TokenForCron>> {
+    let mut pinned_tokens = Vec::new();
+
+    for mint_pubkey in &overrides.pinned {
+        match state.token_repo.get_token(mint_pubkey)
+            .await
+            .map_err(|_| CronError::PinnedTokensFetchFail)? {
+            Some(token) => pinned_tokens.push(TokenForCron::create_from_token(token)),
+            None => println!("Pinned token is unknown, skipping it: {}", mint_pubkey)
+        }
+    }
+
+    Ok(pinned_tokens)
+}
+
+/// Adds up the weights of every flag Birdeye reports. Owner and freeze authority are rejected outright instead.
+fn compute_risk_score(
+    security: &SecurityData,
+    config: &SelectionConfig
+) -> i32 {
+    let mut risk_score = 0;
+
+    if security.mint_authority.is_some() {
+        risk_score += MINT_AUTHORITY_RISK;
+    }
+
+    if security.transfer_fee_enable.unwrap_or(false) {
+        risk_score += TRANSFER_FEE_RISK;
+    }
+
+    if security.top10_holder_percent.unwrap_or(0.0) > config.max_top10_holder_share {
+        risk_score += TOP10_HOLDERS_RISK;
+    }
+
+    // without a share, any balance left with the creator counts
+    let creator_holds_too_much = match security.creator_percentage {
+        Some(creator_share) => creator_share > config.max_creator_share,
+        None => security.creator_balance.unwrap_or(0.0) > 0.0
+    };
+
+    if creator_holds_too_much {
+        risk_score += CREATOR_BALANCE_RISK;
+    }
+
+    if security.mutable_metadata.unwrap_or(false) {
+        risk_score += MUTABLE_METADATA_RISK;
+    }
+
+    risk_score
 }
 
 fn filter_by_mc_liquidity_and_addresses(
     token_list: Vec<TokenFromClient>,
-    excluded_addresses: HashSet<String>
+    overrides: &SelectionOverrides,
+    config: &SelectionConfig,
+    open_slots: usize
 ) -> Result<Vec<TokenFromClient>> {
-    let filtered_token_list: Vec<TokenFromClient> = token_list.into_iter()
+    let mut filtered_token_list: Vec<TokenFromClient> = token_list.into_iter()
         .filter(|token| {
-            token.market_cap >= 500_000.0 
-            && token.liquidity >= 100_000.0
-            && !excluded_addresses.contains(token.address.as_str())
+            !overrides.skips_listing(&token.address)
+            && (overrides.forced.contains(&token.address)
+                || (token.market_cap >= config.min_market_cap_usd 
+                    && token.liquidity >= config.min_liquidity_usd))
         })
         .collect();
 
-    if filtered_token_list.len() < 25 {
+    // forced tokens take the open slots first, the rest keeps Birdeye's order
+    filtered_token_list.sort_by_key(|token| !overrides.forced.contains(&token.address));
+
+    if filtered_token_list.len() < open_slots {
         Err(CronError::FilteredTokensLengthFail)
     } else {
         Ok(filtered_token_list)
     }
 }
 
+/// Checks trades and security for one token. A token whose Birdeye calls still fail after retries is skipped,
+/// so one bad mint doesn't fail the whole run.
+async fn screen_token(
+    token: TokenFromClient,
+    birdeye_client: &BirdeyeClient,
+    overrides: &SelectionOverrides,
+    config: &SelectionConfig
+) -> Option<TokenForCron> {
+    let mut token_for_cron = TokenForCron::create_from_client_token(token);
+
+    let Ok(token_overview) = birdeye_client.get_token_overview(&token_for_cron.address).await else {
+        println!("Skipping token, overview unavailable: {}", token_for_cron.address);
+        return None
+    };
+
+    if !overrides.forced.contains(&token_for_cron.address)
+        && token_overview.data.trade_24h.unwrap_or(0) < config.min_trades_24h as u64
+    {
+        return None
+    }
+
+    token_for_cron.price_change_24h_percent = token_overview.data.price_change_24h_percent.unwrap_or(0.0);
+    token_for_cron.decimals = token_overview.data.decimals;
+
+    if let Some(extensions) = token_overview.data.extensions {
+        token_for_cron.discord = extensions.discord;
+        token_for_cron.twitter = extensions.twitter;
+        token_for_cron.telegram = extensions.telegram;
+        token_for_cron.website = extensions.website;
+    }
+
+    let Ok(token_security) = birdeye_client.get_token_security(&token_for_cron.address).await else {
+        println!("Skipping token, security unavailable: {}", token_for_cron.address);
+        return None
+    };
+
+    if token_security.data.owner_address.is_some() || token_security.data.freeze_authority.is_some() {
+        return None
+    }
+
+    let risk_score = compute_risk_score(&token_security.data, config);
+
+    if risk_score > config.max_risk_score {
+        println!("Token risk score {} is above the limit: {}", risk_score, token_for_cron.address);
+        return None
+    }
+
+    token_for_cron.risk_score = Some(risk_score);
+
+    Some(token_for_cron)
+}
+
 async fn filter_by_24htrade_and_security(
     token_list: Vec<TokenFromClient>,
-    birdeye_client: &BirdeyeClient
+    birdeye_client: &BirdeyeClient,
+    overrides: &SelectionOverrides,
+    config: &SelectionConfig,
+    open_slots: usize
 ) -> Result<Vec<TokenForCron>> {
     let mut seen_pubkeys = HashSet::new();
 
-    let mut fully_filtered_tokens = Vec::new();
-
-    for token in token_list {
-        if seen_pubkeys.insert(token.address.clone()) {
-            let mut token_for_cron = TokenForCron::create_from_client_token(token);
-
+    let unique_tokens: Vec<TokenFromClient> = token_list.into_iter()
+        .filter(|token| seen_pubkeys.insert(token.address.clone()))
+        .collect();
 
-            let token_overview = birdeye_client.get_token_overview(&token_for_cron.address)
-                .await.map_err(|_| CronError::BirdeyeClientFail)?;
-                
-            if token_overview.data.trade_24h.unwrap_or(0) >= 500
-            {
-                token_for_cron.price_change_24h_percent = token_overview.data.price_change_24h_percent.unwrap_or(0.0);
-                token_for_cron.decimals = token_overview.data.decimals;
-
-                if token_overview.data.extensions.is_some() {
-                    token_for_cron.discord = token_overview.data.extensions.clone().unwrap().discord;
-                    token_for_cron.twitter = token_overview.data.extensions.clone().unwrap().twitter;
-                    token_for_cron.telegram = token_overview.data.extensions.clone().unwrap().telegram;
-                    token_for_cron.website = token_overview.data.extensions.unwrap().website;
-                } else {
-                    token_for_cron.discord = None;
-                    token_for_cron.twitter = None;
-                    token_for_cron.telegram = None;
-                    token_for_cron.website = None;
-                }
+    // buffered keeps the list order, so forced tokens still come first
+    let screened_tokens: Vec<Option<TokenForCron>> = stream::iter(unique_tokens)
+        .map(|token| screen_token(token, birdeye_client, overrides, config))
+        .buffered(BIRDEYE_CONCURRENCY)
+        .collect()
+        .await;
 
-                let token_security =  birdeye_client.get_token_security(&token_for_cron.address)
-                    .await.map_err(|_| CronError::BirdeyeClientFail)?;
-    
-                if token_security.data.owner_address.is_none() && token_security.data.freeze_authority.is_none() {
-                    fully_filtered_tokens.push(token_for_cron)
-                }
-            }
-        }
-   }
+    let mut fully_filtered_tokens: Vec<TokenForCron> = screened_tokens.into_iter()
+        .flatten()
+        .collect();
 
    println!("the length is: {}", fully_filtered_tokens.len());
 
-   if fully_filtered_tokens.len() < 25 {
-        return Err(CronError::FilteredTokensLengthFail)
+   if fully_filtered_tokens.len() < open_slots {
+        Err(CronError::FilteredTokensLengthFail)
    } else {
-        let drained_list: Vec<TokenForCron> =  fully_filtered_tokens.drain(0..25).collect();
+        let drained_list: Vec<TokenForCron> =  fully_filtered_tokens.drain(0..open_slots).collect();
 
         Ok(drained_list)
    }
 }
 
-fn get_excluded_addresses() -> HashSet<String> {
-    vec![
-        "EPjFWdd5AufqSSqeM2qN1xzybapC8G4wEGGkZwyTDt1v",
-        "Es9vMFrzaCERmJfrF4H2FYD4KCoNkY11McCe8BenwNYB",
-        "7kbnvuGBxxj8AG9qp8Scn56muWGaRaFqxg1FsRp3PaFT",
-        "So11111111111111111111111111111111111111112",
-        "JUPyiwrYJFskUPiHa7hkeR8VUtAeFoSYbKedZNsDvCN",
-        "bSo13r4TkiE4KumL71LsHTPpL2euBYLFx6h9HP3piy1",
-        "J1toso1uCk3RLmjorhTtrVwY9HJ7X8V9yYac6Y7kGCPn",
-        "mSoLzYCxHdYgdzU16g5QSh3i5K3z3KZK7ytfqcJm7So",
-        "7dHbWXmci3dT8UFYWYZweBLXgycu7Y3iL6trKn1Y7ARj",
-        "4k3Dyjzvzp8eMZWUXbBCjEvwSkkk59S5iCNLY3QrkX6R",
-        "ZEUS1aR7aX8DFFJf5QjWj2ftDDdNTroMNGo8YoQm3Gq",
-        "jtojtomepa8beP8AuQc6eXt5FriJwfFMwQx2v2f9mCL",
-        "85VBFQZC9TZkfaptBWjvUw7YbZjy52A6mjtPGjstQAmQ",
-        "HZ1JovNiVvGrGNiiYvEozEVgZ58xaU3RKwX8eACQBCt3",
-        "27G8MtK7VtTcCHkpASjSDdkWWYfoqT6ggEuKidVJidD4",
-        "7vfCXTUXx5WJV5JADk17DUJ4ksgau7utNKj4b963voxs",
-        "3NZ9JMVBmGAqocybic2c7LQCJScmgsAZ6vQqTDzcqmJh",
-        "rndrizKT3MK1iimdxRdWabcF7Zg7AR5T4nud4EkHBof",
-        "SHDWyBxihqiCj6YekG2GUr7wqKLeLAMK1gHZck9pL6y",
-        "LFNTYraetVioAPnGJht4yNg2aUZFXR776cMeN9VMjXp",
-        "orcaEKTdK7LKz57vaAYr9QeNsVEPfiu6QeMU1kektZE",
-        "hntyVP6YFm1Hg25TN9WGLqM12b8TQmcknKrdu1oxWux",
-        "nosXBVoaCTtYdLvKY6Csb4AC8JCdQKKAaWYtx2ZMoo7",
-        "mb1eu7TzEc71KxDpsmsKoucSSuuoGLv1drys1oP2jh6",
-        "SLNDpmoWTVADgEdndyvWzroNL7zSi1dF9PC3xHGtPwp",
-        "ATLASXmbPQxBUYbxPsV97usA3fPQYEqzQBUHgiFCUsXx"
-    ]
-    .iter()
-    .map(|&pubkey| pubkey.to_string())
-    .collect()
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::Utc;
+    use crate::{
+        models::{model_selection::{SelectionConfigForUpdate, SelectionSortKey}, model_token_override::{TokenOverrideForCreate, TokenOverrideKind}},
+        test_utils::{MockUpstream, TestContext}
+    };
+
+    fn test_config(selection_size: i32) -> SelectionConfigForUpdate {
+        SelectionConfigForUpdate {
+            min_market_cap_usd: 100_000.0,
+            min_liquidity_usd: 50_000.0,
+            min_trades_24h: 100,
+            selection_size,
+            page_count: 1,
+            sort_by: SelectionSortKey::Volume24hUsd,
+            ..SelectionConfigForUpdate::default()
+        }
+    }
+
+    fn risk_config() -> SelectionConfig {
+        let defaults = SelectionConfigForUpdate::default();
+
+        SelectionConfig {
+            min_market_cap_usd: defaults.min_market_cap_usd,
+            min_liquidity_usd: defaults.min_liquidity_usd,
+            min_trades_24h: defaults.min_trades_24h,
+            selection_size: defaults.selection_size,
+            page_count: defaults.page_count,
+            sort_by: defaults.sort_by.as_str().to_string(),
+            max_risk_score: defaults.max_risk_score,
+            max_top10_holder_share: 0.5,
+            max_creator_share: 0.1,
+            updated_at: Utc::now()
+        }
+    }
+
+    fn clean_security() -> SecurityData {
+        SecurityData {
+            owner_address: None,
+            freeze_authority: None,
+            mint_authority: None,
+            top10_holder_percent: Some(0.2),
+            mutable_metadata: Some(false),
+            transfer_fee_enable: Some(false),
+            creator_balance: Some(0.0),
+            creator_percentage: Some(0.0)
+        }
+    }
+
+    #[test]
+    fn clean_tokens_score_zero() {
+        assert_eq!(compute_risk_score(&clean_security(), &risk_config()), 0);
+    }
+
+    #[test]
+    fn every_flag_adds_its_weight() {
+        let config = risk_config();
+
+        let cases = [
+            (SecurityData { mint_authority: Some("authority".to_string()), ..clean_security() }, MINT_AUTHORITY_RISK),
+            (SecurityData { transfer_fee_enable: Some(true), ..clean_security() }, TRANSFER_FEE_RISK),
+            (SecurityData { top10_holder_percent: Some(0.6), ..clean_security() }, TOP10_HOLDERS_RISK),
+            (SecurityData { creator_percentage: Some(0.2), ..clean_security() }, CREATOR_BALANCE_RISK),
+            (SecurityData { mutable_metadata: Some(true), ..clean_security() }, MUTABLE_METADATA_RISK)
+        ];
+
+        for (security, expected) in cases {
+            assert_eq!(compute_risk_score(&security, &config), expected, "{:?}", security);
+        }
+
+        let everything = SecurityData {
+            mint_authority: Some("authority".to_string()),
+            transfer_fee_enable: Some(true),
+            top10_holder_percent: Some(0.9),
+            mutable_metadata: Some(true),
+            creator_percentage: Some(0.5),
+            ..clean_security()
+        };
+        assert_eq!(
+            compute_risk_score(&everything, &config),
+            MINT_AUTHORITY_RISK + TRANSFER_FEE_RISK + TOP10_HOLDERS_RISK + CREATOR_BALANCE_RISK + MUTABLE_METADATA_RISK
+        );
+    }
+
+    #[test]
+    fn holder_shares_only_count_above_the_configured_limits() {
+        let config = risk_config();
+
+        let at_limits = SecurityData { top10_holder_percent: Some(0.5), creator_percentage: Some(0.1), ..clean_security() };
+        assert_eq!(compute_risk_score(&at_limits, &config), 0);
+
+        let stricter = SelectionConfig { max_top10_holder_share: 0.4, max_creator_share: 0.05, ..risk_config() };
+        assert_eq!(compute_risk_score(&at_limits, &stricter), TOP10_HOLDERS_RISK + CREATOR_BALANCE_RISK);
+    }
+
+    #[test]
+    fn missing_creator_share_falls_back_to_the_balance() {
+        let config = risk_config();
+
+        let holds_balance = SecurityData { creator_percentage: None, creator_balance: Some(1.0), ..clean_security() };
+        assert_eq!(compute_risk_score(&holds_balance, &config), CREATOR_BALANCE_RISK);
+
+        let unknown = SecurityData {
+            top10_holder_percent: None,
+            mutable_metadata: None,
+            transfer_fee_enable: None,
+            creator_balance: None,
+            creator_percentage: None,
+            ..clean_security()
+        };
+        assert_eq!(compute_risk_score(&unknown, &config), 0);
+    }
+
+    async fn active_pubkeys(state: AppState) -> Vec<String> {
+        let mut pubkeys = get_current_active_pubkeys(state).await.unwrap();
+        pubkeys.sort();
+        pubkeys
+    }
+
+    #[tokio::test]
+    #[ignore = "needs DATABASE_URL"]
+    async fn selects_listed_tokens_that_pass_every_filter() {
+        let context = TestContext::start().await;
+        let state = context.state.clone();
+
+        state.selection_repo.update_selection_config(test_config(3)).await.unwrap();
+
+        CoinSelector::run_coin_selection(state.clone()).await.unwrap();
+
+        assert_eq!(active_pubkeys(state.clone()).await, vec!["MockMintAlpha", "MockMintBeta", "MockMintGamma"]);
+
+        let alpha = state.token_repo.get_token("MockMintAlpha").await.unwrap().unwrap();
+        assert_eq!(alpha.price_change_24h_percent, 12.5);
+        assert_eq!(alpha.twitter_url.as_deref(), Some("https://twitter.com/MockMintAlpha"));
+        // mutable metadata is the only flag the mock reports
+        assert_eq!(alpha.risk_score, Some(10));
+
+        let selections = state.selection_repo.get_selections(Utc::now().date_naive()).await.unwrap();
+        assert_eq!(selections.len(), 1);
+        assert_eq!(selections[0].tokens.len(), 3);
+        assert_eq!(selections[0].run.selection_size, Some(3));
+
+        // the set stays spinnable on later days until the selector runs again
+        let carried_over = state.selection_repo.get_selections(Utc::now().date_naive() + chrono::Days::new(2)).await.unwrap();
+        assert_eq!(carried_over.len(), 1);
+        assert_eq!(carried_over[0].run.id, selections[0].run.id);
+
+        let before_first_run = state.selection_repo.get_selections(Utc::now().date_naive() - chrono::Days::new(1)).await.unwrap();
+        assert!(before_first_run.is_empty());
+
+        context.cleanup().await;
+    }
+
+    #[tokio::test]
+    async fn runs_against_in_memory_repos() {
+        let upstream = MockUpstream::start().await;
+        let state = upstream.in_memory_state();
+
+        state.selection_repo.update_selection_config(test_config(2)).await.unwrap();
+        state.token_override_repo.create_token_override(
+            TokenOverrideForCreate {
+                mint_pubkey: "MockMintAlpha".to_string(),
+                kind: TokenOverrideKind::Exclude,
+                reason: "delisted".to_string(),
+                expires_at: None
+            }
+        ).await.unwrap();
+
+        CoinSelector::run_coin_selection(state.clone()).await.unwrap();
+
+        assert_eq!(active_pubkeys(state.clone()).await, vec!["MockMintBeta", "MockMintGamma"]);
+
+        let selections = state.selection_repo.get_selections(Utc::now().date_naive()).await.unwrap();
+        assert_eq!(selections.len(), 1);
+        assert_eq!(selections[0].run.selection_size, Some(2));
+        assert_eq!(selections[0].tokens.len(), 2);
+    }
+
+    #[tokio::test]
+    #[ignore = "needs DATABASE_URL"]
+    async fn deactivates_tokens_dropped_from_the_selection() {
+        let context = TestContext::start().await;
+        let state = context.state.clone();
+
+        state.selection_repo.update_selection_config(test_config(3)).await.unwrap();
+        CoinSelector::run_coin_selection(state.clone()).await.unwrap();
+
+        state.token_override_repo.create_token_override(
+            TokenOverrideForCreate {
+                mint_pubkey: "MockMintAlpha".to_string(),
+                kind: TokenOverrideKind::Exclude,
+                reason: "delisted".to_string(),
+                expires_at: None
+            }
+        ).await.unwrap();
+
+        state.selection_repo.update_selection_config(test_config(2)).await.unwrap();
+        CoinSelector::run_coin_selection(state.clone()).await.unwrap();
+
+        assert_eq!(active_pubkeys(state.clone()).await, vec!["MockMintBeta", "MockMintGamma"]);
+
+        let alpha = state.token_repo.get_token("MockMintAlpha").await.unwrap().unwrap();
+        assert!(!alpha.is_active);
+
+        context.cleanup().await;
+    }
+
+    #[tokio::test]
+    #[ignore = "needs DATABASE_URL"]
+    async fn fails_when_too_few_tokens_pass() {
+        let context = TestContext::start().await;
+        let state = context.state.clone();
+
+        state.selection_repo.update_selection_config(test_config(4)).await.unwrap();
+
+        let result = CoinSelector::run_coin_selection(state.clone()).await;
+
+        assert!(matches!(result, Err(CronError::FilteredTokensLengthFail)));
+        assert!(active_pubkeys(state.clone()).await.is_empty());
+
+        context.cleanup().await;
+    }
 }
\ No newline at end of file
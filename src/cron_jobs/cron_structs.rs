@@ -1,4 +1,4 @@
-use crate::clients::clients_structs::TokenFromClient;
+use crate::{clients::clients_structs::TokenFromClient, models::model_token::Token};
 
 
 #[derive(Debug)]
@@ -13,7 +13,8 @@ pub struct TokenForCron {
     pub twitter: Option<String>,
     pub website: Option<String>,
     pub telegram: Option<String>,
-    pub decimals: i32
+    pub decimals: i32,
+    pub risk_score: Option<i32>
 }
 
 impl TokenForCron {
@@ -29,7 +30,25 @@ impl TokenForCron {
             twitter: None,
             website: None,
             telegram: None,
-            decimals: 0,
+            decimals: client_token.decimals,
+            risk_score: None,
+        }
+    }
+
+    pub fn create_from_token(token: Token) -> Self {
+        Self {
+            address: token.mint_pubkey,
+            symbol: token.symbol,
+            name: token.name,
+            logo_uri: token.logo_url,
+            volume_24h_usd: token.volume_24h_usd,
+            price_change_24h_percent: token.price_change_24h_percent,
+            discord: token.discord_url,
+            twitter: token.twitter_url,
+            website: token.website_url,
+            telegram: token.telegram_url,
+            decimals: token.decimals,
+            risk_score: token.risk_score,
         }
     }
 }
\ No newline at end of file
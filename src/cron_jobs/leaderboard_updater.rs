@@ -0,0 +1,150 @@
+use std::collections::HashSet;
+
+use chrono::Utc;
+use tokio_cron_scheduler::Job;
+
+use crate::{
+    errors::cron_errors::{CronError, Result},
+    models::{model_leaderboard::{compute_user_performance, LeaderboardWindow}, model_position::Position},
+    services::price_source::VsToken,
+    AppState
+};
+
+pub struct LeaderboardUpdater;
+
+impl LeaderboardUpdater {
+    pub fn init_job(
+        job_schedule: &str,
+        state: AppState
+    ) -> Job {
+        Job::new_async(job_schedule, move |_, _| {
+            let state_copy = state.clone();
+            Box::pin(async move {
+                let mut attempts = 0;
+
+                while attempts < 3 {
+                    match Self::run_leaderboard_updater(
+                        state_copy.clone(),
+                    ).await {
+                        Ok(_) => {
+                            println!("->> {:<12} - run_leaderboard_updater succeeded", "CRON");
+                            break;
+                        },
+                        Err(e) => {
+                            println!("->> {:<12} - run_leaderboard_updater failed. Error: {e}", "CRON");
+                            attempts += 1;
+
+                            if attempts >= 3 {
+                                println!("->> {:<12} - run_leaderboard_updater failed 3 times", "CRON");
+                                break;
+                            }
+                        }
+                    }
+                }
+            })
+        }).expect("Failed to add job")
+    }
+}
+
+impl LeaderboardUpdater {
+    /// Prices every position once, then materializes the ranking of each window,
+    /// so serving the leaderboard never hits Jupiter.
+    pub async fn run_leaderboard_updater(
+        state: AppState
+    ) -> Result<()> {
+        println!("->> {:<12} - run_leaderboard_updater", "UPDATER");
+
+        let positions = state.position_repo.get_positions()
+            .await.map_err(|_| CronError::PositionsFetchFail)?;
+
+        // only open positions need a current price, closed ones are fully realized
+        let mut pairs: HashSet<(String, VsToken)> = HashSet::new();
+        let mut vs_token_pubkeys: HashSet<String> = HashSet::new();
+
+        for position in &positions {
+            if position.current_quantity > 0.0 {
+                pairs.insert((position.token_pubkey.clone(), VsToken::new(&position.vs_token_pubkey, &position.vs_token_symbol)));
+            }
+
+            vs_token_pubkeys.insert(position.vs_token_pubkey.clone());
+        }
+
+        let pairs: Vec<(String, VsToken)> = pairs.into_iter().collect();
+        let vs_token_pubkeys: Vec<String> = vs_token_pubkeys.into_iter().collect();
+
+        let token_prices = state.price_service.get_prices_for_pairs(&pairs)
+            .await.map_err(|_| CronError::PriceFetchFail)?;
+
+        let usd_prices = state.price_service.get_prices(&vs_token_pubkeys, &VsToken::usd())
+            .await.map_err(|_| CronError::PriceFetchFail)?;
+
+        let computed_at = Utc::now();
+
+        for window in LeaderboardWindow::ALL_WINDOWS {
+            let window_positions: Vec<Position> = match window.starts_at(computed_at) {
+                Some(starts_at) => positions.iter()
+                    .filter(|position| position.created_at >= starts_at)
+                    .cloned()
+                    .collect(),
+                None => positions.clone()
+            };
+
+            let performance = compute_user_performance(&window_positions, &token_prices, &usd_prices);
+
+            state.leaderboard_repo.replace_window_entries(
+                window,
+                performance,
+                computed_at
+            ).await.map_err(|_| CronError::LeaderboardUpdateFail)?;
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        models::{model_leaderboard::{LeaderboardMetric, LeaderboardParams}, model_position::PositionForCreate},
+        test_utils::MockUpstream
+    };
+
+    fn position(user_pubkey: &str, token_pubkey: &str, purchase_price: f64) -> PositionForCreate {
+        PositionForCreate {
+            user_pubkey: user_pubkey.to_string(),
+            token_pubkey: token_pubkey.to_string(),
+            token_symbol: token_pubkey.to_string(),
+            token_logo_url: String::new(),
+            vs_token_pubkey: "EPjFWdd5AufqSSqeM2qN1xzybapC8G4wEGGkZwyTDt1v".to_string(),
+            vs_token_symbol: "USDC".to_string(),
+            vs_token_logo_url: String::new(),
+            quantity: 10.0,
+            purchase_price
+        }
+    }
+
+    #[tokio::test]
+    async fn ranks_users_from_in_memory_repos() {
+        let upstream = MockUpstream::start().await;
+        let state = upstream.in_memory_state();
+
+        // Alpha trades at 1.5, so the first wallet is up 50% and the second down 25%
+        state.position_repo.create_position(position("Winner", "MockMintAlpha", 1.0)).await.unwrap();
+        state.position_repo.create_position(position("Loser", "MockMintAlpha", 2.0)).await.unwrap();
+
+        LeaderboardUpdater::run_leaderboard_updater(state.clone()).await.unwrap();
+
+        for window in LeaderboardWindow::ALL_WINDOWS {
+            let leaderboard = state.leaderboard_repo.get_leaderboard(
+                LeaderboardParams { window, metric: LeaderboardMetric::PnlPct, limit: None }
+            ).await.unwrap();
+
+            let ranking: Vec<(usize, &str, f64)> = leaderboard.entries.iter()
+                .map(|ranked| (ranked.rank, ranked.entry.user_pubkey.as_str(), ranked.entry.pnl_pct))
+                .collect();
+
+            assert_eq!(ranking, vec![(1, "Winner", 50.0), (2, "Loser", -25.0)]);
+        }
+    }
+}
@@ -1,3 +1,4 @@
 pub mod coin_selector;
 pub mod token_updater;
-pub mod cron_structs;
\ No newline at end of file
+pub mod cron_structs;
+pub mod leaderboard_updater;
\ No newline at end of file
@@ -1,7 +1,9 @@
 use tokio_cron_scheduler::Job;
 
 use crate::{
-    errors::cron_errors::{CronError, Result}, models::model_token::Token, AppState
+    errors::cron_errors::{CronError, Result}, 
+    models::model_token_snapshot::SnapshotForCreate, 
+    AppState
 };
 
 pub struct TokenUpdater;
@@ -49,14 +51,16 @@ impl TokenUpdater {
         let birdeye_client = &state.birdeye_client;
 
         // get all tokens
-        let tokens = Token::get_tokens(state.clone())
+        let tokens = state.token_repo.get_tokens()
             .await.map_err(|_| CronError::UpdateTokenStatusFail)?;
 
-        if tokens.len() == 0 {
+        if tokens.is_empty() {
             println!("No tokens to update");
             return Err(CronError::UpdateTokenStatusFail)
         }
 
+        let mut snapshots: Vec<SnapshotForCreate> = Vec::with_capacity(tokens.len());
+
         // fetch token overview for them
         for token in tokens {
             let token_overview = 
@@ -65,17 +69,151 @@ impl TokenUpdater {
 
             // update the data
 
-            Token::update_token_financial_data(
+            state.token_repo.update_token_financial_data(
                 &token.mint_pubkey, 
                 token_overview.data.price_change_24h_percent.unwrap_or(0.0), 
                 token_overview.data.volume_24h_usd.unwrap_or(0.0), 
-                token_overview.data.decimals, 
-                state.clone()
+                token_overview.data.decimals
             ).await.map_err(|_| CronError::UpdateTokenStatusFail)?;
 
+            // keep a history row for charts, skipped when Birdeye has no price for the token
+            if let Some(price) = token_overview.data.price {
+                snapshots.push(SnapshotForCreate {
+                    mint_pubkey: token.mint_pubkey.clone(),
+                    price,
+                    volume_24h_usd: token_overview.data.volume_24h_usd.unwrap_or(0.0),
+                    liquidity: token_overview.data.liquidity.unwrap_or(0.0),
+                    market_cap: token_overview.data.market_cap.unwrap_or(0.0)
+                });
+            }
         }
 
+        // written once every token is fetched, so a failed run that init_job retries leaves no duplicates
+        state.snapshot_repo.create_snapshots(snapshots)
+            .await.map_err(|_| CronError::SnapshotCreateFail)?;
 
         Ok(())
     }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    use chrono::Utc;
+    use crate::{models::model_token::TokenForCreate, test_utils::{mock_token, MockUpstream, TestContext}};
+
+    fn stale_token(mint_pubkey: &str) -> TokenForCreate {
+        TokenForCreate {
+            mint_pubkey: mint_pubkey.to_string(),
+            symbol: mint_pubkey.to_uppercase(),
+            name: mint_pubkey.to_string(),
+            logo_url: String::new(),
+            price_change_24h_percent: 0.0,
+            volume_24h_usd: 0.0,
+            discord_url: None,
+            twitter_url: None,
+            website_url: None,
+            telegram_url: None,
+            decimals: 9,
+            is_active: true,
+            risk_score: None
+        }
+    }
+
+    #[tokio::test]
+    #[ignore = "needs DATABASE_URL"]
+    async fn refreshes_financial_data_and_records_snapshots() {
+        let context = TestContext::start().await;
+        let state = context.state.clone();
+
+        for mint_pubkey in ["MockMintAlpha", "MockMintBeta"] {
+            state.token_repo.create_token(stale_token(mint_pubkey)).await.unwrap();
+        }
+
+        TokenUpdater::run_token_updater(state.clone()).await.unwrap();
+
+        for mint_pubkey in ["MockMintAlpha", "MockMintBeta"] {
+            let mock = mock_token(mint_pubkey).unwrap();
+            let token = state.token_repo.get_token(mint_pubkey).await.unwrap().unwrap();
+
+            assert_eq!(token.volume_24h_usd, mock.volume_24h_usd);
+            assert_eq!(token.price_change_24h_percent, 12.5);
+            assert_eq!(token.decimals, 6);
+
+            let snapshots = state.snapshot_repo.get_snapshots(
+                mint_pubkey,
+                Utc::now() - Duration::from_secs(60 * 60),
+                Utc::now() + Duration::from_secs(60 * 60)
+            ).await.unwrap();
+
+            assert_eq!(snapshots.len(), 1);
+            assert_eq!(snapshots[0].price, mock.price);
+            assert_eq!(snapshots[0].market_cap, mock.market_cap);
+        }
+
+        context.cleanup().await;
+    }
+
+    #[tokio::test]
+    #[ignore = "needs DATABASE_URL"]
+    async fn records_no_snapshots_when_a_later_token_fails() {
+        let context = TestContext::start().await;
+        let state = context.state.clone();
+
+        // Birdeye has no overview for the unknown mint, failing the run after Alpha was fetched
+        for mint_pubkey in ["MockMintAlpha", "UnknownMint"] {
+            state.token_repo.create_token(stale_token(mint_pubkey)).await.unwrap();
+        }
+
+        for _ in 0..2 {
+            assert!(matches!(TokenUpdater::run_token_updater(state.clone()).await, Err(CronError::BirdeyeClientFail)));
+        }
+
+        let snapshots = state.snapshot_repo.get_snapshots(
+            "MockMintAlpha",
+            Utc::now() - Duration::from_secs(60 * 60),
+            Utc::now() + Duration::from_secs(60 * 60)
+        ).await.unwrap();
+
+        assert!(snapshots.is_empty());
+
+        context.cleanup().await;
+    }
+
+    #[tokio::test]
+    async fn runs_against_in_memory_repos() {
+        let upstream = MockUpstream::start().await;
+        let state = upstream.in_memory_state();
+
+        state.token_repo.create_token(stale_token("MockMintBeta")).await.unwrap();
+
+        TokenUpdater::run_token_updater(state.clone()).await.unwrap();
+
+        let mock = mock_token("MockMintBeta").unwrap();
+        let token = state.token_repo.get_token("MockMintBeta").await.unwrap().unwrap();
+        assert_eq!(token.volume_24h_usd, mock.volume_24h_usd);
+
+        let snapshots = state.snapshot_repo.get_snapshots(
+            "MockMintBeta",
+            Utc::now() - Duration::from_secs(60 * 60),
+            Utc::now() + Duration::from_secs(60 * 60)
+        ).await.unwrap();
+
+        assert_eq!(snapshots.len(), 1);
+        assert_eq!(snapshots[0].price, mock.price);
+    }
+
+    #[tokio::test]
+    #[ignore = "needs DATABASE_URL"]
+    async fn fails_without_tokens() {
+        let context = TestContext::start().await;
+
+        let result = TokenUpdater::run_token_updater(context.state.clone()).await;
+
+        assert!(matches!(result, Err(CronError::UpdateTokenStatusFail)));
+
+        context.cleanup().await;
+    }
 }
\ No newline at end of file
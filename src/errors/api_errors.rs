@@ -1,56 +1,208 @@
-use axum::{http::StatusCode, response::{IntoResponse, Response}};
+use std::borrow::Cow;
+
+use axum::{http::{header, StatusCode}, response::{IntoResponse, Response}};
 
 pub type Result<T> = core::result::Result<T, ApiError>;
 
 #[derive(Debug)]
+#[allow(clippy::enum_variant_names)]
 pub enum ApiError {
+    // auth errors
+    Unauthorized,
+    Forbidden,
+    AuthNonceCreateFail,
+    AuthNonceGetFail,
+    AuthSignatureInvalid,
+    AuthSessionCreateFail,
+    AuthSessionGetFail,
+    ApiKeyCreateFail,
+    ApiKeyGetFail,
+    ApiKeyUpdateFail,
+    ApiKeyNotFound,
+
     // token errors
     TokenCreateFail,
     TokenGetFail,
     TokenUpdateFail,
+    TokenNotTradable,
+    TokenSnapshotCreateFail,
+    TokenHistoryRangeInvalid,
+    SelectionCreateFail,
+    SelectionGetFail,
+    SelectionConfigInvalid,
+    SelectionConfigGetFail,
+    SelectionConfigUpdateFail,
+    TokenOverrideInvalid,
+    TokenOverrideExists,
+    TokenOverrideNotFound,
+    TokenOverrideCreateFail,
+    TokenOverrideGetFail,
+    TokenOverrideUpdateFail,
 
     // user errors
     UserCreateFail,
     UserGetFail,
+    ReferralCodeCreateFail,
+    ReferralCodeInvalid,
+    ReferralNotAllowed,
+    PointsGetFail,
 
     // position errors
     PositionCreateFail,
     PositionGetFail,
+    PositionNotFound,
+    PositionClosed,
+
+    // trade errors
+    TradeCreateFail,
+    TradeInvalidOrder,
+    TradeSlippageExceeded,
+
+    // spin errors
+    SpinCreateFail,
+    SpinGetFail,
+    SpinNotFound,
+    SpinSeedNotFound,
+    SpinSeedUsed,
+    SpinInvalidRequest,
+    SpinNotLinkable,
+    SpinQuotaExceeded { retry_after_secs: u64 },
+    SpinCooldownActive { retry_after_secs: u64 },
+
+    // telegram user errors
+    TgUserCreateFail,
+    TgUserGetFail,
+    TgUserUpdateFail,
+    TgUserNotFound,
+    TgUserAlreadyRegistered,
+    TgAccessCodeInvalid,
+    TgReferralNotAllowed,
+    TgInsufficientPoints,
+
+    // leaderboard errors
+    LeaderboardUpdateFail,
+    LeaderboardGetFail,
 
     // client errors
+    PriceUnavailable { mint: String },
+    VsTokenUnknown,
+    VsTokenMismatch,
     JupiterFetchFail,
     JupiterDeserializationFail,
     BirdeyeFetchFail,
     BirdeyeDeserializationFail,
 }
 
+impl ApiError {
+    /// Seconds a rate limited client should wait, sent back as the `Retry-After` header.
+    pub fn retry_after_secs(&self) -> Option<u64> {
+        match self {
+            ApiError::SpinQuotaExceeded { retry_after_secs } | ApiError::SpinCooldownActive { retry_after_secs } => Some(*retry_after_secs),
+            _ => None
+        }
+    }
+}
+
 impl IntoResponse for ApiError {
     fn into_response(self) -> Response {
         println!("->> {:<12} - {self:?}", "INTO_RES_ERR");
 
-        let body = match self {
+        let retry_after_secs = self.retry_after_secs();
+
+        let (status, body): (StatusCode, Cow<'static, str>) = match self {
+            // auth
+            ApiError::Unauthorized => (StatusCode::UNAUTHORIZED, "Missing or invalid credentials".into()),
+            ApiError::Forbidden => (StatusCode::FORBIDDEN, "Not allowed to access this resource".into()),
+            ApiError::AuthNonceCreateFail => (StatusCode::INTERNAL_SERVER_ERROR, "Error creating the sign in nonce".into()),
+            ApiError::AuthNonceGetFail => (StatusCode::INTERNAL_SERVER_ERROR, "Error fetching the sign in nonce".into()),
+            ApiError::AuthSignatureInvalid => (StatusCode::UNAUTHORIZED, "Invalid or expired wallet signature".into()),
+            ApiError::AuthSessionCreateFail => (StatusCode::INTERNAL_SERVER_ERROR, "Error creating the session".into()),
+            ApiError::AuthSessionGetFail => (StatusCode::INTERNAL_SERVER_ERROR, "Error fetching the session".into()),
+            ApiError::ApiKeyCreateFail => (StatusCode::INTERNAL_SERVER_ERROR, "Error creating the api key".into()),
+            ApiError::ApiKeyGetFail => (StatusCode::INTERNAL_SERVER_ERROR, "Error fetching api keys".into()),
+            ApiError::ApiKeyUpdateFail => (StatusCode::INTERNAL_SERVER_ERROR, "Error updating the api key".into()),
+            ApiError::ApiKeyNotFound => (StatusCode::NOT_FOUND, "Api key not found".into()),
+
             // positions
-            ApiError::PositionCreateFail => "Error creating the position",
-            ApiError::PositionGetFail => "Error fetching positions",
+            ApiError::PositionCreateFail => (StatusCode::INTERNAL_SERVER_ERROR, "Error creating the position".into()),
+            ApiError::PositionGetFail => (StatusCode::INTERNAL_SERVER_ERROR, "Error fetching positions".into()),
+            ApiError::PositionNotFound => (StatusCode::NOT_FOUND, "Position not found".into()),
+            ApiError::PositionClosed => (StatusCode::CONFLICT, "Position is already closed".into()),
+
+            // trades
+            ApiError::TradeCreateFail => (StatusCode::INTERNAL_SERVER_ERROR, "Error executing the trade".into()),
+            ApiError::TradeInvalidOrder => (StatusCode::BAD_REQUEST, "Invalid order amount, price or slippage".into()),
+            ApiError::TradeSlippageExceeded => (StatusCode::CONFLICT, "Price moved beyond the allowed slippage".into()),
 
             // tokens
-            ApiError::TokenCreateFail => "Error creating the token",
-            ApiError::TokenGetFail => "Error fetching tokens",
-            ApiError::TokenUpdateFail => "Error updating the token",
+            ApiError::TokenCreateFail => (StatusCode::INTERNAL_SERVER_ERROR, "Error creating the token".into()),
+            ApiError::TokenGetFail => (StatusCode::INTERNAL_SERVER_ERROR, "Error fetching tokens".into()),
+            ApiError::TokenUpdateFail => (StatusCode::INTERNAL_SERVER_ERROR, "Error updating the token".into()),
+            ApiError::TokenNotTradable => (StatusCode::BAD_REQUEST, "Token is not currently tradable".into()),
+            ApiError::TokenSnapshotCreateFail => (StatusCode::INTERNAL_SERVER_ERROR, "Error saving the token price snapshot".into()),
+            ApiError::TokenHistoryRangeInvalid => (StatusCode::BAD_REQUEST, "Invalid history range or too many candles requested".into()),
+            ApiError::SelectionCreateFail => (StatusCode::INTERNAL_SERVER_ERROR, "Error archiving token selection".into()),
+            ApiError::SelectionGetFail => (StatusCode::INTERNAL_SERVER_ERROR, "Error fetching token selections".into()),
+            ApiError::SelectionConfigInvalid => (StatusCode::BAD_REQUEST, "Invalid selection config".into()),
+            ApiError::SelectionConfigGetFail => (StatusCode::INTERNAL_SERVER_ERROR, "Error fetching selection config".into()),
+            ApiError::SelectionConfigUpdateFail => (StatusCode::INTERNAL_SERVER_ERROR, "Error updating selection config".into()),
+            ApiError::TokenOverrideInvalid => (StatusCode::BAD_REQUEST, "Token override needs a mint, a reason and an expiry in the future".into()),
+            ApiError::TokenOverrideExists => (StatusCode::CONFLICT, "Mint already has an override".into()),
+            ApiError::TokenOverrideNotFound => (StatusCode::NOT_FOUND, "Token override not found".into()),
+            ApiError::TokenOverrideCreateFail => (StatusCode::INTERNAL_SERVER_ERROR, "Error creating token override".into()),
+            ApiError::TokenOverrideGetFail => (StatusCode::INTERNAL_SERVER_ERROR, "Error fetching token overrides".into()),
+            ApiError::TokenOverrideUpdateFail => (StatusCode::INTERNAL_SERVER_ERROR, "Error updating token override".into()),
 
             // users
-            ApiError::UserCreateFail => "Error creating the user",
-            ApiError::UserGetFail => "Error fetching users",
+            ApiError::UserCreateFail => (StatusCode::INTERNAL_SERVER_ERROR, "Error creating the user".into()),
+            ApiError::UserGetFail => (StatusCode::INTERNAL_SERVER_ERROR, "Error fetching users".into()),
+            ApiError::ReferralCodeCreateFail => (StatusCode::INTERNAL_SERVER_ERROR, "Error creating referral code".into()),
+            ApiError::ReferralCodeInvalid => (StatusCode::NOT_FOUND, "Referral code not found".into()),
+            ApiError::ReferralNotAllowed => (StatusCode::CONFLICT, "Self and circular referrals are not allowed".into()),
+            ApiError::PointsGetFail => (StatusCode::INTERNAL_SERVER_ERROR, "Error fetching points".into()),
+
+            // spins
+            ApiError::SpinCreateFail => (StatusCode::INTERNAL_SERVER_ERROR, "Error creating spin".into()),
+            ApiError::SpinGetFail => (StatusCode::INTERNAL_SERVER_ERROR, "Error fetching spin".into()),
+            ApiError::SpinNotFound => (StatusCode::NOT_FOUND, "Spin not found".into()),
+            ApiError::SpinSeedNotFound => (StatusCode::NOT_FOUND, "Spin seed not found".into()),
+            ApiError::SpinSeedUsed => (StatusCode::CONFLICT, "Spin seed was already used, request a new one".into()),
+            ApiError::SpinInvalidRequest => (StatusCode::BAD_REQUEST, "Client seed must be 1 to 64 characters and nonce must not be negative".into()),
+            ApiError::SpinNotLinkable => (StatusCode::CONFLICT, "Spin belongs to another user, selected another token or already opened a position".into()),
+            ApiError::SpinQuotaExceeded { retry_after_secs } => (StatusCode::TOO_MANY_REQUESTS, format!("Daily spin quota reached, retry after {} seconds", retry_after_secs).into()),
+            ApiError::SpinCooldownActive { retry_after_secs } => (StatusCode::TOO_MANY_REQUESTS, format!("Spin cooldown active, retry after {} seconds", retry_after_secs).into()),
+
+            // telegram users
+            ApiError::TgUserCreateFail => (StatusCode::INTERNAL_SERVER_ERROR, "Error creating telegram user".into()),
+            ApiError::TgUserGetFail => (StatusCode::INTERNAL_SERVER_ERROR, "Error fetching telegram user".into()),
+            ApiError::TgUserUpdateFail => (StatusCode::INTERNAL_SERVER_ERROR, "Error updating telegram user".into()),
+            ApiError::TgUserNotFound => (StatusCode::NOT_FOUND, "Telegram user not found".into()),
+            ApiError::TgUserAlreadyRegistered => (StatusCode::CONFLICT, "Telegram user is already registered".into()),
+            ApiError::TgAccessCodeInvalid => (StatusCode::NOT_FOUND, "Access code not found".into()),
+            ApiError::TgReferralNotAllowed => (StatusCode::CONFLICT, "User was already referred, or the access code is their own or from someone they referred".into()),
+            ApiError::TgInsufficientPoints => (StatusCode::PAYMENT_REQUIRED, "Not enough points to spin".into()),
+
+            // leaderboard
+            ApiError::LeaderboardUpdateFail => (StatusCode::INTERNAL_SERVER_ERROR, "Error updating leaderboard".into()),
+            ApiError::LeaderboardGetFail => (StatusCode::INTERNAL_SERVER_ERROR, "Error fetching leaderboard".into()),
+
+            // prices
+            ApiError::PriceUnavailable { mint } => (StatusCode::SERVICE_UNAVAILABLE, format!("No price available for mint {}", mint).into()),
+            ApiError::VsTokenUnknown => (StatusCode::BAD_REQUEST, "Vs token must be SOL, USDC, USDT or a mint".into()),
+            ApiError::VsTokenMismatch => (StatusCode::BAD_REQUEST, "Vs token symbol does not match its mint".into()),
 
             // jupiter
-            ApiError::JupiterFetchFail => "Error fetching Jupiter price data",
-            ApiError::JupiterDeserializationFail => "Error deserializing Jupiter price data",
+            ApiError::JupiterFetchFail => (StatusCode::INTERNAL_SERVER_ERROR, "Error fetching Jupiter price data".into()),
+            ApiError::JupiterDeserializationFail => (StatusCode::INTERNAL_SERVER_ERROR, "Error deserializing Jupiter price data".into()),
 
             // birdeye
-            ApiError::BirdeyeFetchFail => "Error fetching data from Birdeye",
-            ApiError::BirdeyeDeserializationFail => "Error deserializing Birdeye data"
+            ApiError::BirdeyeFetchFail => (StatusCode::INTERNAL_SERVER_ERROR, "Error fetching data from Birdeye".into()),
+            ApiError::BirdeyeDeserializationFail => (StatusCode::INTERNAL_SERVER_ERROR, "Error deserializing Birdeye data".into())
         };
 
-        (StatusCode::INTERNAL_SERVER_ERROR, body).into_response()
+        match retry_after_secs {
+            Some(retry_after_secs) => (status, [(header::RETRY_AFTER, retry_after_secs.to_string())], body).into_response(),
+            None => (status, body).into_response()
+        }
     }
-}
\ No newline at end of file
+}
@@ -2,10 +2,20 @@ use std::fmt;
 
 pub type Result<T> = core::result::Result<T, CronError>;
 
+#[derive(Debug)]
+#[allow(clippy::enum_variant_names)]
 pub enum CronError {
     BirdeyeClientFail,
     FilteredTokensLengthFail,
     UpdateTokenStatusFail,
+    SnapshotCreateFail,
+    PositionsFetchFail,
+    PriceFetchFail,
+    LeaderboardUpdateFail,
+    SelectionArchiveFail,
+    SelectionConfigFetchFail,
+    TokenOverridesFetchFail,
+    PinnedTokensFetchFail,
 }
 
 impl fmt::Display for CronError {
@@ -15,8 +25,16 @@ impl fmt::Display for CronError {
             "{}",
             match self {
                 CronError::BirdeyeClientFail => "Birdeye client failed to fetch data.",
-                CronError::FilteredTokensLengthFail => "Filtered tokens length is less than 25.",
-                CronError::UpdateTokenStatusFail => "Updating token status failed."
+                CronError::FilteredTokensLengthFail => "Filtered tokens length is less than the selection size.",
+                CronError::UpdateTokenStatusFail => "Updating token status failed.",
+                CronError::SnapshotCreateFail => "Saving token price snapshot failed.",
+                CronError::PositionsFetchFail => "Fetching positions failed.",
+                CronError::PriceFetchFail => "Fetching prices failed.",
+                CronError::LeaderboardUpdateFail => "Updating leaderboard failed.",
+                CronError::SelectionArchiveFail => "Archiving token selection failed.",
+                CronError::SelectionConfigFetchFail => "Fetching selection config failed.",
+                CronError::TokenOverridesFetchFail => "Fetching token overrides failed.",
+                CronError::PinnedTokensFetchFail => "Fetching pinned tokens failed."
             }
         )
     }
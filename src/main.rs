@@ -1,6 +1,19 @@
+use std::{sync::Arc, time::Duration};
+
 use axum::{middleware, Extension, Router};
-use clients::client_birdeye::BirdeyeClient;
-use cron_jobs::token_updater::TokenUpdater;
+use clients::{client_birdeye::{BirdeyeClient, BIRDEYE_DEFAULT_BASE_URL}, client_jupiter::{JupiterClient, JUPITER_DEFAULT_BASE_URL}, client_jupiter_v2::{JupiterV2Client, JUPITER_V2_DEFAULT_BASE_URL}};
+use cron_jobs::{leaderboard_updater::LeaderboardUpdater, token_updater::TokenUpdater};
+use models::{model_api_key::ApiKey, model_selection::{SelectionConfigForUpdate, SelectionSortKey}, model_spin_quota::SpinQuotaConfig};
+use repos::{
+    repo_leaderboard::{LeaderboardRepo, PgLeaderboardRepo},
+    repo_position::{PgPositionRepo, PositionRepo},
+    repo_selection::{PgSelectionRepo, SelectionRepo},
+    repo_token::{PgTokenRepo, TokenRepo},
+    repo_token_override::{PgTokenOverrideRepo, TokenOverrideRepo},
+    repo_token_snapshot::{PgSnapshotRepo, SnapshotRepo},
+    repo_user::{PgUserRepo, UserRepo}
+};
+use services::{price_service::PriceService, price_source::{CompositePriceSource, PriceSource}};
 use sqlx::PgPool;
 use shuttle_runtime::SecretStore;
 use tokio_cron_scheduler::JobScheduler;
@@ -14,12 +27,24 @@ mod errors;
 mod clients;
 mod utils;
 mod cron_jobs;
+mod services;
+mod repos;
+#[cfg(test)]
+mod test_utils;
 
 #[derive(Clone)]
 pub struct AppState {
     db: PgPool,
-    api_key: String,
     birdeye_client: BirdeyeClient,
+    price_service: PriceService,
+    spin_quota: SpinQuotaConfig,
+    token_repo: Arc<dyn TokenRepo>,
+    position_repo: Arc<dyn PositionRepo>,
+    user_repo: Arc<dyn UserRepo>,
+    selection_repo: Arc<dyn SelectionRepo>,
+    token_override_repo: Arc<dyn TokenOverrideRepo>,
+    snapshot_repo: Arc<dyn SnapshotRepo>,
+    leaderboard_repo: Arc<dyn LeaderboardRepo>,
 }
 
 #[shuttle_runtime::main]
@@ -28,30 +53,158 @@ async fn main(
     #[shuttle_runtime::Secrets] secrets: SecretStore,
 ) -> shuttle_axum::ShuttleAxum {
 
-    let _ = sqlx::migrate!().run(&db)
-        .await.map_err(|e| format!("Migrations failed. Error: {e}"));
-
-    let api_key = secrets.get("API_KEY")
-        .expect("API key not found in secrets!");
+    // a half-migrated schema would fail at the first query, refuse to start instead
+    sqlx::migrate!().run(&db)
+        .await.expect("Migrations failed");
 
     let birdeye_api_key = secrets.get("BIRDEYE_API_KEY")
         .expect("Birdeye API key not found in secrets!");
 
-    let birdeye_client = BirdeyeClient::new(&birdeye_api_key);
+    // 1 request per second is Birdeye's free tier, raise it to match the key's plan
+    let birdeye_requests_per_sec = secrets.get("BIRDEYE_REQUESTS_PER_SEC")
+        .map(|rate| rate.parse::<f64>().ok().filter(|rate| rate.is_finite() && *rate > 0.0)
+            .expect("BIRDEYE_REQUESTS_PER_SEC must be a positive number"))
+        .unwrap_or(1.0);
+
+    // base urls are only overridden to point the clients at a mock or proxy
+    let birdeye_base_url = secrets.get("BIRDEYE_BASE_URL")
+        .unwrap_or_else(|| BIRDEYE_DEFAULT_BASE_URL.to_string());
+
+    let birdeye_client = BirdeyeClient::new(&birdeye_api_key, &birdeye_base_url, birdeye_requests_per_sec);
+
+    let jupiter_base_url = secrets.get("JUPITER_BASE_URL")
+        .unwrap_or_else(|| JUPITER_DEFAULT_BASE_URL.to_string());
+
+    let jupiter_v2_base_url = secrets.get("JUPITER_V2_BASE_URL")
+        .unwrap_or_else(|| JUPITER_V2_DEFAULT_BASE_URL.to_string());
+
+    // priority order, the deprecated v4 endpoint is left out unless listed
+    let price_sources: Vec<Arc<dyn PriceSource>> = secrets.get("PRICE_SOURCES")
+        .unwrap_or_else(|| "jupiter_v2,birdeye".to_string())
+        .split(',')
+        .map(|source| -> Arc<dyn PriceSource> {
+            match source.trim() {
+                "jupiter_v2" => Arc::new(JupiterV2Client::new(&jupiter_v2_base_url)),
+                "jupiter_v4" => Arc::new(JupiterClient::new(&jupiter_base_url)),
+                "birdeye" => Arc::new(birdeye_client.clone()),
+                _ => panic!("PRICE_SOURCES must list jupiter_v2, jupiter_v4 or birdeye, got: {}", source)
+            }
+        })
+        .collect();
+
+    // when set, every source is queried and prices further apart than this share are flagged
+    let price_divergence_threshold = secrets.get("PRICE_DIVERGENCE_THRESHOLD")
+        .map(|threshold| threshold.parse::<f64>().expect("PRICE_DIVERGENCE_THRESHOLD must be a number, 0.05 is 5%"));
+
+    let price_cache_ttl_secs = secrets.get("PRICE_CACHE_TTL_SECS")
+        .map(|ttl| ttl.parse::<u64>().expect("PRICE_CACHE_TTL_SECS must be a number of seconds"))
+        .unwrap_or(30);
+
+    let price_service = PriceService::new(Duration::from_secs(price_cache_ttl_secs), CompositePriceSource::new(price_sources, price_divergence_threshold));
     
-    let state = AppState { db, api_key, birdeye_client };
+    // 0 would reject every spin
+    let spins_per_day = secrets.get("SPINS_PER_DAY")
+        .map(|spins| spins.parse::<i32>().ok().filter(|spins| *spins > 0)
+            .expect("SPINS_PER_DAY must be a positive number"))
+        .unwrap_or(10);
+
+    let spin_cooldown_secs = secrets.get("SPIN_COOLDOWN_SECS")
+        .map(|cooldown| cooldown.parse::<u64>().expect("SPIN_COOLDOWN_SECS must be a number of seconds"))
+        .unwrap_or(30);
+
+    let spin_quota = SpinQuotaConfig {
+        spins_per_day,
+        cooldown: Duration::from_secs(spin_cooldown_secs)
+    };
+    
+    let token_repo: Arc<dyn TokenRepo> = Arc::new(PgTokenRepo::new(db.clone()));
+    let position_repo: Arc<dyn PositionRepo> = Arc::new(PgPositionRepo::new(db.clone()));
+    let user_repo: Arc<dyn UserRepo> = Arc::new(PgUserRepo::new(db.clone()));
+    let selection_repo: Arc<dyn SelectionRepo> = Arc::new(PgSelectionRepo::new(db.clone()));
+    let token_override_repo: Arc<dyn TokenOverrideRepo> = Arc::new(PgTokenOverrideRepo::new(db.clone()));
+    let snapshot_repo: Arc<dyn SnapshotRepo> = Arc::new(PgSnapshotRepo::new(db.clone()));
+    let leaderboard_repo: Arc<dyn LeaderboardRepo> = Arc::new(PgLeaderboardRepo::new(db.clone()));
+
+    let state = AppState {
+        db,
+        birdeye_client,
+        price_service,
+        spin_quota,
+        token_repo,
+        position_repo,
+        user_repo,
+        selection_repo,
+        token_override_repo,
+        snapshot_repo,
+        leaderboard_repo
+    };
+
+    let selection_defaults = SelectionConfigForUpdate::default();
+
+    let selection_defaults = SelectionConfigForUpdate {
+        min_market_cap_usd: secrets.get("SELECTION_MIN_MARKET_CAP_USD")
+            .map(|value| value.parse::<f64>().expect("SELECTION_MIN_MARKET_CAP_USD must be a number"))
+            .unwrap_or(selection_defaults.min_market_cap_usd),
+        min_liquidity_usd: secrets.get("SELECTION_MIN_LIQUIDITY_USD")
+            .map(|value| value.parse::<f64>().expect("SELECTION_MIN_LIQUIDITY_USD must be a number"))
+            .unwrap_or(selection_defaults.min_liquidity_usd),
+        min_trades_24h: secrets.get("SELECTION_MIN_TRADES_24H")
+            .map(|value| value.parse::<i32>().expect("SELECTION_MIN_TRADES_24H must be a number"))
+            .unwrap_or(selection_defaults.min_trades_24h),
+        selection_size: secrets.get("SELECTION_SIZE")
+            .map(|value| value.parse::<i32>().expect("SELECTION_SIZE must be a number"))
+            .unwrap_or(selection_defaults.selection_size),
+        page_count: secrets.get("SELECTION_PAGE_COUNT")
+            .map(|value| value.parse::<i32>().expect("SELECTION_PAGE_COUNT must be a number"))
+            .unwrap_or(selection_defaults.page_count),
+        sort_by: secrets.get("SELECTION_SORT_BY")
+            .map(|value| SelectionSortKey::parse(&value).expect("SELECTION_SORT_BY must be volume_24h_usd, market_cap or volume_24h_change_percent"))
+            .unwrap_or(selection_defaults.sort_by),
+        max_risk_score: secrets.get("SELECTION_MAX_RISK_SCORE")
+            .map(|value| value.parse::<i32>().expect("SELECTION_MAX_RISK_SCORE must be a number"))
+            .unwrap_or(selection_defaults.max_risk_score),
+        max_top10_holder_share: secrets.get("SELECTION_MAX_TOP10_HOLDER_SHARE")
+            .map(|value| value.parse::<f64>().expect("SELECTION_MAX_TOP10_HOLDER_SHARE must be a number"))
+            .unwrap_or(selection_defaults.max_top10_holder_share),
+        max_creator_share: secrets.get("SELECTION_MAX_CREATOR_SHARE")
+            .map(|value| value.parse::<f64>().expect("SELECTION_MAX_CREATOR_SHARE must be a number"))
+            .unwrap_or(selection_defaults.max_creator_share)
+    };
+
+    // secrets only seed the config, later edits through /admin/selection-config take precedence
+    state.selection_repo.ensure_selection_config(selection_defaults)
+        .await.expect("Failed to store selection config");
+
+    if let Some(api_key) = secrets.get("API_KEY") {
+        ApiKey::ensure_bootstrap_key(&api_key, state.clone())
+            .await.expect("Failed to register bootstrap API key");
+    }
     
     let position_routes = web::routes_positions::routes(state.clone());
     let user_routes = web::routes_users::routes(state.clone());
     let token_routes = web::routes_tokens::routes(state.clone());
     let play_routes = web::routes_play::routes(state.clone());
+    let auth_routes = web::routes_auth::routes(state.clone());
+    let api_key_routes = web::routes_api_keys::routes(state.clone());
+    let portfolio_routes = web::routes_portfolio::routes(state.clone());
+    let leaderboard_routes = web::routes_leaderboard::routes(state.clone());
+    let tg_user_routes = web::routes_tg_users::routes(state.clone());
+    let token_override_routes = web::routes_token_overrides::routes(state.clone());
+    let upstream_routes = web::routes_upstreams::routes(state.clone());
 
     let api_router = Router::new()
         .merge(position_routes)
         .merge(user_routes)
         .merge(token_routes)
         .merge(play_routes)
+        .merge(api_key_routes)
+        .merge(portfolio_routes)
+        .merge(leaderboard_routes)
+        .merge(tg_user_routes)
+        .merge(token_override_routes)
+        .merge(upstream_routes)
         .layer(middleware::from_fn(web::mw_auth::auth_middleware))
+        .merge(auth_routes)
         .layer(Extension(state.clone()));
     
     let router = Router::new()
@@ -66,7 +219,11 @@ async fn main(
     ).await.expect("Failed to schedule job");
 
     scheduler.add(
-        TokenUpdater::init_job("0 */10 * * * *", state)
+        TokenUpdater::init_job("0 */10 * * * *", state.clone())
+    ).await.expect("Failed to schedule job");
+
+    scheduler.add(
+        LeaderboardUpdater::init_job("0 */15 * * * *", state)
     ).await.expect("Failed to schedule job");
 
     scheduler.start().await.expect("Failed to start scheduler");
@@ -1,3 +1,17 @@
 pub mod model_position;
 pub mod model_token;
-pub mod model_user;
\ No newline at end of file
+pub mod model_user;
+pub mod model_auth;
+pub mod model_api_key;
+pub mod model_trade;
+pub mod model_portfolio;
+pub mod model_token_snapshot;
+pub mod model_leaderboard;
+pub mod model_spin;
+pub mod model_spin_mode;
+pub mod model_spin_quota;
+pub mod model_tg_user;
+pub mod model_referral;
+pub mod model_points;
+pub mod model_selection;
+pub mod model_token_override;
\ No newline at end of file
@@ -0,0 +1,207 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+use crate::{errors::api_errors::{ApiError, Result}, utils, AppState};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Scope {
+    #[serde(rename = "positions:read")]
+    PositionsRead,
+    #[serde(rename = "positions:write")]
+    PositionsWrite,
+    #[serde(rename = "users:read")]
+    UsersRead,
+    #[serde(rename = "users:write")]
+    UsersWrite,
+    #[serde(rename = "tokens:read")]
+    TokensRead,
+    #[serde(rename = "play")]
+    Play,
+    #[serde(rename = "admin")]
+    Admin,
+}
+
+impl Scope {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Scope::PositionsRead => "positions:read",
+            Scope::PositionsWrite => "positions:write",
+            Scope::UsersRead => "users:read",
+            Scope::UsersWrite => "users:write",
+            Scope::TokensRead => "tokens:read",
+            Scope::Play => "play",
+            Scope::Admin => "admin",
+        }
+    }
+
+    pub fn parse(scope: &str) -> Option<Self> {
+        match scope {
+            "positions:read" => Some(Scope::PositionsRead),
+            "positions:write" => Some(Scope::PositionsWrite),
+            "users:read" => Some(Scope::UsersRead),
+            "users:write" => Some(Scope::UsersWrite),
+            "tokens:read" => Some(Scope::TokensRead),
+            "play" => Some(Scope::Play),
+            "admin" => Some(Scope::Admin),
+            _ => None
+        }
+    }
+}
+
+#[derive(Debug, sqlx::FromRow, Serialize)]
+pub struct ApiKey {
+    pub id: Uuid,
+    pub name: String,
+    pub scopes: Vec<String>,
+    pub expires_at: Option<DateTime<Utc>>,
+    pub revoked_at: Option<DateTime<Utc>>,
+    pub created_at: DateTime<Utc>
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ApiKeyForCreate {
+    pub name: String,
+    pub scopes: Vec<Scope>,
+    pub expires_at: Option<DateTime<Utc>>
+}
+
+#[derive(Debug, Serialize)]
+pub struct ApiKeyWithSecret {
+    /// Plaintext key. Only returned once, at creation time.
+    pub api_key: String,
+    #[serde(flatten)]
+    pub key: ApiKey
+}
+
+impl ApiKey {
+    pub fn parsed_scopes(&self) -> Vec<Scope> {
+        self.scopes.iter()
+            .filter_map(|scope| Scope::parse(scope))
+            .collect()
+    }
+}
+
+// CRUD implementation for ApiKey
+
+impl ApiKey {
+    pub async fn create_api_key(
+        api_key: ApiKeyForCreate,
+        state: AppState
+    ) -> Result<ApiKeyWithSecret> {
+        println!("->> {:<12} - create_api_key", "CONTROLLER");
+
+        let secret = format!("sk_{}", utils::generate_random_token());
+
+        let scopes: Vec<&str> = api_key.scopes.iter().map(|scope| scope.as_str()).collect();
+
+        let result = sqlx::query_as::<_, ApiKey>(
+                "INSERT INTO api_keys (name, key_hash, scopes, expires_at) VALUES ($1, $2, $3, $4) RETURNING *"
+            )
+            .bind(api_key.name)
+            .bind(utils::hash_token(&secret))
+            .bind(scopes)
+            .bind(api_key.expires_at)
+            .fetch_one(&state.db)
+            .await;
+
+        match result {
+            Ok(key) => Ok(ApiKeyWithSecret { api_key: secret, key }),
+            Err(e) => {
+                println!("Error creating api key. Error: {}", e);
+                Err(ApiError::ApiKeyCreateFail)
+            }
+        }
+    }
+
+    /// Registers the `API_KEY` secret as an admin key so existing deployments keep working.
+    /// A revoked bootstrap key stays revoked across restarts.
+    pub async fn ensure_bootstrap_key(
+        secret: &str,
+        state: AppState
+    ) -> Result<()> {
+        println!("->> {:<12} - ensure_bootstrap_key", "CONTROLLER");
+
+        let result = sqlx::query(
+                "INSERT INTO api_keys (name, key_hash, scopes) VALUES ($1, $2, $3) ON CONFLICT (key_hash) DO NOTHING"
+            )
+            .bind("bootstrap")
+            .bind(utils::hash_token(secret))
+            .bind(vec![Scope::Admin.as_str()])
+            .execute(&state.db)
+            .await;
+
+        match result {
+            Ok(_) => Ok(()),
+            Err(e) => {
+                println!("Error creating bootstrap api key. Error: {}", e);
+                Err(ApiError::ApiKeyCreateFail)
+            }
+        }
+    }
+
+    pub async fn get_api_keys(state: AppState) -> Result<Vec<Self>> {
+        println!("->> {:<12} - get_api_keys", "CONTROLLER");
+
+        let result = sqlx::query_as::<_, ApiKey>(
+                "SELECT * FROM api_keys ORDER BY created_at DESC"
+            )
+            .fetch_all(&state.db)
+            .await;
+
+        match result {
+            Ok(keys) => Ok(keys),
+            Err(e) => {
+                println!("Error fetching api keys. Error: {}", e);
+                Err(ApiError::ApiKeyGetFail)
+            }
+        }
+    }
+
+    /// Looks a plaintext key up by its hash, ignoring revoked and expired keys.
+    pub async fn get_active_api_key(
+        secret: &str,
+        state: AppState
+    ) -> Result<Option<Self>> {
+        println!("->> {:<12} - get_active_api_key", "CONTROLLER");
+
+        let result = sqlx::query_as::<_, ApiKey>(
+                r#"SELECT * FROM api_keys
+                WHERE key_hash = $1
+                    AND revoked_at IS NULL
+                    AND (expires_at IS NULL OR expires_at > NOW())"#
+            )
+            .bind(utils::hash_token(secret))
+            .fetch_optional(&state.db)
+            .await;
+
+        match result {
+            Ok(key) => Ok(key),
+            Err(e) => {
+                println!("Error fetching api key. Error: {}", e);
+                Err(ApiError::ApiKeyGetFail)
+            }
+        }
+    }
+
+    pub async fn revoke_api_key(
+        key_id: &Uuid,
+        state: AppState
+    ) -> Result<Option<Self>> {
+        println!("->> {:<12} - revoke_api_key", "CONTROLLER");
+
+        let result = sqlx::query_as::<_, ApiKey>(
+                "UPDATE api_keys SET revoked_at = COALESCE(revoked_at, NOW()) WHERE id = $1 RETURNING *"
+            )
+            .bind(key_id)
+            .fetch_optional(&state.db)
+            .await;
+
+        match result {
+            Ok(key) => Ok(key),
+            Err(e) => {
+                println!("Error revoking api key with id: {}. Error: {}", key_id, e);
+                Err(ApiError::ApiKeyUpdateFail)
+            }
+        }
+    }
+}
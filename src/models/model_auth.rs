@@ -0,0 +1,230 @@
+use std::time::Duration;
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use crate::{errors::api_errors::{ApiError, Result}, utils, AppState};
+
+const NONCE_TTL: Duration = Duration::from_secs(5 * 60);
+const SESSION_TTL: Duration = Duration::from_secs(24 * 60 * 60);
+
+#[derive(Debug, sqlx::FromRow)]
+pub struct AuthNonce {
+    pub user_pubkey: String,
+    pub nonce: String,
+    pub expires_at: DateTime<Utc>
+}
+
+#[derive(Debug, sqlx::FromRow)]
+pub struct AuthSession {
+    pub user_pubkey: String,
+    pub expires_at: DateTime<Utc>
+}
+
+#[derive(Debug, Deserialize)]
+pub struct NonceForCreate {
+    pub user_pubkey: String
+}
+
+#[derive(Debug, Serialize)]
+pub struct NonceChallenge {
+    pub user_pubkey: String,
+    pub message: String,
+    pub expires_at: DateTime<Utc>
+}
+
+#[derive(Debug, Deserialize)]
+pub struct SessionForCreate {
+    pub user_pubkey: String,
+    pub signature: String
+}
+
+#[derive(Debug, Serialize)]
+pub struct SessionToken {
+    pub token: String,
+    pub user_pubkey: String,
+    pub expires_at: DateTime<Utc>
+}
+
+impl AuthNonce {
+    /// The exact text the wallet has to sign to prove ownership of the pubkey.
+    pub fn sign_in_message(&self) -> String {
+        format!(
+            "Sign in to SolSpinner\nWallet: {}\nNonce: {}",
+            self.user_pubkey,
+            self.nonce
+        )
+    }
+}
+
+// CRUD implementation for AuthNonce
+
+impl AuthNonce {
+    /// Returns the wallet's pending nonce, or a new one once it expired.
+    /// Anyone may request a challenge for any wallet, so a pending one is never replaced,
+    /// which would break a sign in the owner has in flight.
+    pub async fn create_nonce(
+        user_pubkey: &str,
+        state: AppState
+    ) -> Result<Self> {
+        println!("->> {:<12} - create_nonce", "CONTROLLER");
+
+        let result = sqlx::query_as::<_, AuthNonce>(
+                r#"INSERT INTO auth_nonces (user_pubkey, nonce, expires_at)
+                VALUES ($1, $2, $3)
+                ON CONFLICT (user_pubkey) DO UPDATE
+                SET
+                    nonce = CASE WHEN auth_nonces.expires_at > NOW() THEN auth_nonces.nonce ELSE EXCLUDED.nonce END,
+                    created_at = CASE WHEN auth_nonces.expires_at > NOW() THEN auth_nonces.created_at ELSE CURRENT_TIMESTAMP END,
+                    expires_at = CASE WHEN auth_nonces.expires_at > NOW() THEN auth_nonces.expires_at ELSE EXCLUDED.expires_at END
+                RETURNING *"#
+            )
+            .bind(user_pubkey)
+            .bind(utils::generate_random_token())
+            .bind(Utc::now() + NONCE_TTL)
+            .fetch_one(&state.db)
+            .await;
+
+        match result {
+            Ok(nonce) => Ok(nonce),
+            Err(e) => {
+                println!("Error creating nonce for user: {}. Error: {}", user_pubkey, e);
+                Err(ApiError::AuthNonceCreateFail)
+            }
+        }
+    }
+
+    pub async fn get_active_nonce(
+        user_pubkey: &str,
+        state: AppState
+    ) -> Result<Option<Self>> {
+        println!("->> {:<12} - get_active_nonce", "CONTROLLER");
+
+        let result = sqlx::query_as::<_, AuthNonce>(
+                "SELECT * FROM auth_nonces WHERE user_pubkey = $1 AND expires_at > NOW()"
+            )
+            .bind(user_pubkey)
+            .fetch_optional(&state.db)
+            .await;
+
+        match result {
+            Ok(nonce) => Ok(nonce),
+            Err(e) => {
+                println!("Error fetching nonce for user: {}. Error: {}", user_pubkey, e);
+                Err(ApiError::AuthNonceGetFail)
+            }
+        }
+    }
+
+    /// Deletes the nonce once its signature checked out, so it signs in a single session.
+    /// False when a concurrent sign in already used it.
+    pub async fn consume_nonce(
+        &self,
+        state: AppState
+    ) -> Result<bool> {
+        println!("->> {:<12} - consume_nonce", "CONTROLLER");
+
+        let result = sqlx::query(
+                "DELETE FROM auth_nonces WHERE user_pubkey = $1 AND nonce = $2 AND expires_at > NOW()"
+            )
+            .bind(&self.user_pubkey)
+            .bind(&self.nonce)
+            .execute(&state.db)
+            .await;
+
+        match result {
+            Ok(deleted) => Ok(deleted.rows_affected() == 1),
+            Err(e) => {
+                println!("Error deleting nonce for user: {}. Error: {}", self.user_pubkey, e);
+                Err(ApiError::AuthNonceGetFail)
+            }
+        }
+    }
+}
+
+// CRUD implementation for AuthSession
+
+impl AuthSession {
+    /// Returns the plaintext token alongside the session. Only its hash is stored.
+    pub async fn create_session(
+        user_pubkey: &str,
+        state: AppState
+    ) -> Result<(String, Self)> {
+        println!("->> {:<12} - create_session", "CONTROLLER");
+
+        let token = utils::generate_random_token();
+
+        let result = sqlx::query_as::<_, AuthSession>(
+                "INSERT INTO auth_sessions (token_hash, user_pubkey, expires_at) VALUES ($1, $2, $3) RETURNING *"
+            )
+            .bind(utils::hash_token(&token))
+            .bind(user_pubkey)
+            .bind(Utc::now() + SESSION_TTL)
+            .fetch_one(&state.db)
+            .await;
+
+        match result {
+            Ok(session) => Ok((token, session)),
+            Err(e) => {
+                println!("Error creating session for user: {}. Error: {}", user_pubkey, e);
+                Err(ApiError::AuthSessionCreateFail)
+            }
+        }
+    }
+
+    pub async fn get_active_session(
+        token: &str,
+        state: AppState
+    ) -> Result<Option<Self>> {
+        println!("->> {:<12} - get_active_session", "CONTROLLER");
+
+        let result = sqlx::query_as::<_, AuthSession>(
+                "SELECT * FROM auth_sessions WHERE token_hash = $1 AND expires_at > NOW()"
+            )
+            .bind(utils::hash_token(token))
+            .fetch_optional(&state.db)
+            .await;
+
+        match result {
+            Ok(session) => Ok(session),
+            Err(e) => {
+                println!("Error fetching session. Error: {}", e);
+                Err(ApiError::AuthSessionGetFail)
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::TestContext;
+
+    #[tokio::test]
+    #[ignore = "needs DATABASE_URL"]
+    async fn keeps_pending_nonces_and_rejects_expired_ones() {
+        let context = TestContext::start().await;
+        let state = context.state.clone();
+
+        let nonce = AuthNonce::create_nonce("Wallet", state.clone()).await.unwrap();
+
+        // a second challenge request, possibly from someone else, gets the same nonce
+        let repeated = AuthNonce::create_nonce("Wallet", state.clone()).await.unwrap();
+        assert_eq!(repeated.nonce, nonce.nonce);
+
+        sqlx::query("UPDATE auth_nonces SET expires_at = NOW() - INTERVAL '1 second' WHERE user_pubkey = $1")
+            .bind("Wallet")
+            .execute(&state.db)
+            .await.unwrap();
+
+        assert!(AuthNonce::get_active_nonce("Wallet", state.clone()).await.unwrap().is_none());
+        assert!(!nonce.consume_nonce(state.clone()).await.unwrap());
+
+        let renewed = AuthNonce::create_nonce("Wallet", state.clone()).await.unwrap();
+        assert_ne!(renewed.nonce, nonce.nonce);
+
+        assert!(renewed.consume_nonce(state.clone()).await.unwrap());
+        assert!(!renewed.consume_nonce(state.clone()).await.unwrap());
+
+        context.cleanup().await;
+    }
+}
@@ -0,0 +1,255 @@
+use std::{collections::HashMap, time::Duration};
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+use super::model_position::Position;
+
+const DEFAULT_LEADERBOARD_LIMIT: i64 = 50;
+const MAX_LEADERBOARD_LIMIT: i64 = 500;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Default)]
+pub enum LeaderboardWindow {
+    #[serde(rename = "24h")]
+    Day,
+    #[serde(rename = "7d")]
+    Week,
+    #[default]
+    #[serde(rename = "all")]
+    All,
+}
+
+impl LeaderboardWindow {
+    pub const ALL_WINDOWS: [LeaderboardWindow; 3] = [
+        LeaderboardWindow::Day,
+        LeaderboardWindow::Week,
+        LeaderboardWindow::All,
+    ];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            LeaderboardWindow::Day => "24h",
+            LeaderboardWindow::Week => "7d",
+            LeaderboardWindow::All => "all",
+        }
+    }
+
+    /// Positions opened at or after this instant count towards the window.
+    pub fn starts_at(&self, now: DateTime<Utc>) -> Option<DateTime<Utc>> {
+        match self {
+            LeaderboardWindow::Day => Some(now - Duration::from_secs(24 * 60 * 60)),
+            LeaderboardWindow::Week => Some(now - Duration::from_secs(7 * 24 * 60 * 60)),
+            LeaderboardWindow::All => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Deserialize, Default)]
+pub enum LeaderboardMetric {
+    #[default]
+    #[serde(rename = "pnl_pct")]
+    PnlPct,
+    #[serde(rename = "pnl_abs")]
+    PnlAbs,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct LeaderboardParams {
+    #[serde(default)]
+    pub window: LeaderboardWindow,
+    #[serde(default)]
+    pub metric: LeaderboardMetric,
+    pub limit: Option<i64>
+}
+
+impl LeaderboardParams {
+    /// Entries to return, defaulted and clamped.
+    pub fn limit(&self) -> i64 {
+        self.limit
+            .unwrap_or(DEFAULT_LEADERBOARD_LIMIT)
+            .clamp(1, MAX_LEADERBOARD_LIMIT)
+    }
+}
+
+#[derive(Debug, sqlx::FromRow, Serialize, Clone)]
+pub struct LeaderboardEntry {
+    pub user_pubkey: String,
+    pub positions_count: i32,
+    pub cost_basis_usd: f64,
+    pub pnl_abs_usd: f64,
+    pub pnl_pct: f64,
+    pub computed_at: DateTime<Utc>
+}
+
+#[derive(Debug, Serialize)]
+pub struct RankedLeaderboardEntry {
+    pub rank: usize,
+    #[serde(flatten)]
+    pub entry: LeaderboardEntry
+}
+
+#[derive(Debug, Serialize)]
+pub struct Leaderboard {
+    pub window: LeaderboardWindow,
+    pub entries: Vec<RankedLeaderboardEntry>
+}
+
+#[derive(Debug, Default)]
+pub struct UserPerformance {
+    pub positions_count: i32,
+    pub cost_basis_usd: f64,
+    pub pnl_abs_usd: f64
+}
+
+impl UserPerformance {
+    pub fn pnl_pct(&self) -> f64 {
+        if self.cost_basis_usd > 0.0 {
+            self.pnl_abs_usd / self.cost_basis_usd * 100.0
+        } else {
+            0.0
+        }
+    }
+}
+
+/// Realized plus unrealized PnL per user, in USD.
+/// `token_prices` is keyed by (token_pubkey, vs_token_symbol) and `usd_prices` by vs_token_pubkey.
+/// Positions that can't be converted to USD, or open positions without a current price, are skipped.
+pub fn compute_user_performance(
+    positions: &[Position],
+    token_prices: &HashMap<(String, String), f64>,
+    usd_prices: &HashMap<String, f64>
+) -> HashMap<String, UserPerformance> {
+    let mut performance: HashMap<String, UserPerformance> = HashMap::new();
+
+    for position in positions {
+        let Some(usd_price) = usd_prices.get(&position.vs_token_pubkey) else {
+            continue
+        };
+
+        let unrealized_pnl = if position.current_quantity > 0.0 {
+            match token_prices.get(&(position.token_pubkey.clone(), position.vs_token_symbol.clone())) {
+                Some(current_price) => (current_price - position.purchase_price) * position.current_quantity,
+                None => continue
+            }
+        } else {
+            0.0
+        };
+
+        let user_performance = performance.entry(position.user_pubkey.clone()).or_default();
+
+        user_performance.positions_count += 1;
+        user_performance.cost_basis_usd += position.initial_quantity * position.purchase_price * usd_price;
+        user_performance.pnl_abs_usd += (position.realized_pnl + unrealized_pnl) * usd_price;
+    }
+
+    performance
+}
+
+#[cfg(test)]
+mod tests {
+    use uuid::Uuid;
+
+    use super::*;
+    use crate::models::model_position::PositionStatus;
+
+    const SOL: (&str, &str) = ("So11111111111111111111111111111111111111112", "SOL");
+    const USDC: (&str, &str) = ("EPjFWdd5AufqSSqeM2qN1xzybapC8G4wEGGkZwyTDt1v", "USDC");
+
+    fn position(user_pubkey: &str, token_pubkey: &str, vs_token: (&str, &str), quantity: f64, purchase_price: f64) -> Position {
+        Position {
+            id: Uuid::new_v4(),
+            user_pubkey: user_pubkey.to_string(),
+            token_pubkey: token_pubkey.to_string(),
+            token_symbol: token_pubkey.to_uppercase(),
+            token_logo_url: String::new(),
+            vs_token_pubkey: vs_token.0.to_string(),
+            vs_token_symbol: vs_token.1.to_string(),
+            vs_token_logo_url: String::new(),
+            initial_quantity: quantity,
+            current_quantity: quantity,
+            purchase_price,
+            created_at: Utc::now(),
+            status: PositionStatus::Open.as_str().to_string(),
+            realized_pnl: 0.0,
+            closed_at: None
+        }
+    }
+
+    fn closed(mut position: Position, realized_pnl: f64) -> Position {
+        position.current_quantity = 0.0;
+        position.realized_pnl = realized_pnl;
+        position.status = PositionStatus::Closed.as_str().to_string();
+        position
+    }
+
+    fn prices() -> (HashMap<(String, String), f64>, HashMap<String, f64>) {
+        let token_prices = HashMap::from([
+            (("a".to_string(), "USDC".to_string()), 2.0),
+            (("a".to_string(), "SOL".to_string()), 0.02)
+        ]);
+        let usd_prices = HashMap::from([
+            (USDC.0.to_string(), 1.0),
+            (SOL.0.to_string(), 150.0)
+        ]);
+
+        (token_prices, usd_prices)
+    }
+
+    fn assert_close(actual: f64, expected: f64) {
+        assert!((actual - expected).abs() < 1e-9, "expected {expected}, got {actual}");
+    }
+
+    #[test]
+    fn realized_and_unrealized_pnl_add_up_in_usd() {
+        let (token_prices, usd_prices) = prices();
+        let positions = vec![
+            closed(position("alice", "a", USDC, 10.0, 1.0), 5.0),
+            position("alice", "a", USDC, 10.0, 1.0),
+            position("alice", "a", SOL, 10.0, 0.01)
+        ];
+
+        let performance = compute_user_performance(&positions, &token_prices, &usd_prices);
+        let alice = &performance["alice"];
+
+        assert_eq!(alice.positions_count, 3);
+        assert_close(alice.cost_basis_usd, 10.0 + 10.0 + 15.0);
+        // 5 realized, 10 unrealized in USDC and 0.1 SOL unrealized
+        assert_close(alice.pnl_abs_usd, 5.0 + 10.0 + 15.0);
+        assert_close(alice.pnl_pct(), 30.0 / 35.0 * 100.0);
+    }
+
+    #[test]
+    fn positions_without_a_price_are_skipped() {
+        let (token_prices, usd_prices) = prices();
+        let unknown_vs_token = ("UnknownVsTokenMint", "UNK");
+        let positions = vec![
+            position("alice", "a", USDC, 10.0, 1.0),
+            position("alice", "b", USDC, 10.0, 1.0),
+            position("alice", "a", unknown_vs_token, 10.0, 1.0),
+            // a closed position needs no current price, only a USD one
+            closed(position("bob", "b", USDC, 10.0, 1.0), -2.0)
+        ];
+
+        let performance = compute_user_performance(&positions, &token_prices, &usd_prices);
+
+        let alice = &performance["alice"];
+        assert_eq!(alice.positions_count, 1);
+        assert_close(alice.cost_basis_usd, 10.0);
+        assert_close(alice.pnl_abs_usd, 10.0);
+
+        let bob = &performance["bob"];
+        assert_eq!(bob.positions_count, 1);
+        assert_close(bob.pnl_pct(), -20.0);
+    }
+
+    #[test]
+    fn users_without_priced_positions_are_left_out() {
+        let (token_prices, usd_prices) = prices();
+        let positions = vec![position("carol", "b", USDC, 10.0, 1.0)];
+
+        let performance = compute_user_performance(&positions, &token_prices, &usd_prices);
+
+        assert!(performance.is_empty());
+        assert_eq!(UserPerformance::default().pnl_pct(), 0.0);
+    }
+}
@@ -0,0 +1,349 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::PgConnection;
+use uuid::Uuid;
+use crate::{errors::api_errors::{ApiError, Result}, AppState};
+
+/// Source of every point handed out, its balance goes negative as rewards are issued.
+pub const SYSTEM_REWARDS_ACCOUNT: &str = "system:rewards";
+/// Collects the points users spend on spins.
+pub const SYSTEM_SPINS_ACCOUNT: &str = "system:spins";
+
+/// Daily streak bonus per consecutive day, capped at `MAX_STREAK_BONUS_DAYS`.
+pub const DAILY_STREAK_POINTS: i64 = 1;
+pub const MAX_STREAK_BONUS_DAYS: i32 = 7;
+/// (positions opened, points) paid when a user opens their nth position.
+pub const TRADING_MILESTONES: [(i64, i64); 4] = [(1, 5), (10, 10), (50, 25), (100, 50)];
+
+const DEFAULT_ENTRIES_LIMIT: i64 = 20;
+const MAX_ENTRIES_LIMIT: i64 = 100;
+
+#[derive(Debug, Clone, Copy)]
+pub enum PointsEntryKind {
+    SignupBonus,
+    Spin,
+    Referral,
+    DailyStreak,
+    TradingMilestone,
+}
+
+impl PointsEntryKind {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            PointsEntryKind::SignupBonus => "signup_bonus",
+            PointsEntryKind::Spin => "spin",
+            PointsEntryKind::Referral => "referral",
+            PointsEntryKind::DailyStreak => "daily_streak",
+            PointsEntryKind::TradingMilestone => "trading_milestone",
+        }
+    }
+}
+
+pub fn daily_streak_points(streak_days: i32) -> i64 {
+    DAILY_STREAK_POINTS * streak_days.clamp(1, MAX_STREAK_BONUS_DAYS) as i64
+}
+
+pub fn trading_milestone_points(positions_opened: i64) -> Option<i64> {
+    TRADING_MILESTONES.iter()
+        .find(|(milestone, _)| *milestone == positions_opened)
+        .map(|(_, points)| *points)
+}
+
+/// One side of a points transaction. Credits are positive, debits negative.
+#[derive(Debug, sqlx::FromRow, Serialize)]
+pub struct PointsEntry {
+    pub id: i64,
+    pub transaction_id: Uuid,
+    pub account: String,
+    pub amount: i64,
+    pub kind: String,
+    pub reference: Option<String>,
+    pub created_at: DateTime<Utc>
+}
+
+#[derive(Debug, Deserialize)]
+pub struct PointsParams {
+    pub limit: Option<i64>
+}
+
+#[derive(Debug, Serialize)]
+pub struct PointsBalance {
+    pub account: String,
+    pub balance: i64,
+    pub recent_entries: Vec<PointsEntry>
+}
+
+pub struct PointsLedger;
+
+// CRUD implementation for PointsLedger
+
+impl PointsLedger {
+    /// Moves `amount` points from one account to another inside the caller's transaction.
+    /// The two entries share a transaction id, and the database rejects the commit if they don't balance.
+    pub async fn transfer(
+        from_account: &str,
+        to_account: &str,
+        amount: i64,
+        kind: PointsEntryKind,
+        reference: Option<&str>,
+        conn: &mut PgConnection
+    ) -> sqlx::Result<Uuid> {
+        println!("->> {:<12} - transfer {} {} -> {}", "CONTROLLER", kind.as_str(), from_account, to_account);
+
+        let transaction_id = Uuid::new_v4();
+
+        sqlx::query(
+                r#"INSERT INTO points_ledger (transaction_id, account, amount, kind, reference)
+                VALUES ($1, $2, $3, $5, $6), ($1, $4, $7, $5, $6)"#
+            )
+            .bind(transaction_id)
+            .bind(from_account)
+            .bind(-amount)
+            .bind(to_account)
+            .bind(kind.as_str())
+            .bind(reference)
+            .bind(amount)
+            .execute(&mut *conn)
+            .await?;
+
+        Ok(transaction_id)
+    }
+
+    /// Credits `amount` from the system rewards account.
+    pub async fn reward(
+        account: &str,
+        amount: i64,
+        kind: PointsEntryKind,
+        reference: Option<&str>,
+        conn: &mut PgConnection
+    ) -> sqlx::Result<Uuid> {
+        Self::transfer(SYSTEM_REWARDS_ACCOUNT, account, amount, kind, reference, conn).await
+    }
+
+    /// Pays the milestone bonus when the user's latest position is one of `TRADING_MILESTONES`.
+    /// Runs in the transaction that opens the position, so the count includes it.
+    /// The user row is locked first, so concurrent buys count each other's positions in turn.
+    pub async fn credit_trading_milestone(
+        user_pubkey: &str,
+        conn: &mut PgConnection
+    ) -> sqlx::Result<()> {
+        // the position insert already holds a key share lock on the user through its foreign key,
+        // FOR UPDATE would conflict with the other buys' key share locks and deadlock
+        sqlx::query("SELECT user_pubkey FROM users WHERE user_pubkey = $1 FOR NO KEY UPDATE")
+            .bind(user_pubkey)
+            .execute(&mut *conn)
+            .await?;
+
+        let positions_opened = sqlx::query_scalar::<_, i64>(
+                "SELECT COUNT(*) FROM positions WHERE user_pubkey = $1"
+            )
+            .bind(user_pubkey)
+            .fetch_one(&mut *conn)
+            .await?;
+
+        if let Some(points) = trading_milestone_points(positions_opened) {
+            Self::reward_once(
+                user_pubkey,
+                points,
+                PointsEntryKind::TradingMilestone,
+                &format!("positions:{}", positions_opened),
+                conn
+            ).await?;
+        }
+
+        Ok(())
+    }
+
+    /// Credits `amount` from the system rewards account unless `account` already has a credit
+    /// of this kind under `reference`. Only kinds with a unique index on the credit side are deduplicated.
+    /// Returns the transaction id, or `None` when it was already paid.
+    pub async fn reward_once(
+        account: &str,
+        amount: i64,
+        kind: PointsEntryKind,
+        reference: &str,
+        conn: &mut PgConnection
+    ) -> sqlx::Result<Option<Uuid>> {
+        println!("->> {:<12} - reward_once {} {}", "CONTROLLER", kind.as_str(), account);
+
+        let transaction_id = sqlx::query_scalar::<_, Uuid>(
+                r#"INSERT INTO points_ledger (transaction_id, account, amount, kind, reference)
+                VALUES ($1, $2, $3, $4, $5)
+                ON CONFLICT DO NOTHING
+                RETURNING transaction_id"#
+            )
+            .bind(Uuid::new_v4())
+            .bind(account)
+            .bind(amount)
+            .bind(kind.as_str())
+            .bind(reference)
+            .fetch_optional(&mut *conn)
+            .await?;
+
+        if let Some(transaction_id) = transaction_id {
+            sqlx::query(
+                    r#"INSERT INTO points_ledger (transaction_id, account, amount, kind, reference)
+                    VALUES ($1, $2, $3, $4, $5)"#
+                )
+                .bind(transaction_id)
+                .bind(SYSTEM_REWARDS_ACCOUNT)
+                .bind(-amount)
+                .bind(kind.as_str())
+                .bind(reference)
+                .execute(&mut *conn)
+                .await?;
+        }
+
+        Ok(transaction_id)
+    }
+
+    pub async fn get_balance(
+        account: &str,
+        conn: &mut PgConnection
+    ) -> sqlx::Result<i64> {
+        sqlx::query_scalar::<_, i64>(
+                "SELECT COALESCE(SUM(amount), 0)::BIGINT FROM points_ledger WHERE account = $1"
+            )
+            .bind(account)
+            .fetch_one(&mut *conn)
+            .await
+    }
+
+    pub async fn get_points(
+        account: &str,
+        params: PointsParams,
+        state: AppState
+    ) -> Result<PointsBalance> {
+        println!("->> {:<12} - get_points", "CONTROLLER");
+
+        let limit = params.limit
+            .unwrap_or(DEFAULT_ENTRIES_LIMIT)
+            .clamp(1, MAX_ENTRIES_LIMIT);
+
+        let result: sqlx::Result<PointsBalance> = async {
+            let mut conn = state.db.acquire().await?;
+
+            let balance = Self::get_balance(account, &mut conn).await?;
+
+            let recent_entries = sqlx::query_as::<_, PointsEntry>(
+                    r#"SELECT id, transaction_id, account, amount, kind, reference, created_at
+                    FROM points_ledger
+                    WHERE account = $1
+                    ORDER BY created_at DESC, id DESC
+                    LIMIT $2"#
+                )
+                .bind(account)
+                .bind(limit)
+                .fetch_all(&mut *conn)
+                .await?;
+
+            Ok(PointsBalance {
+                account: account.to_string(),
+                balance,
+                recent_entries
+            })
+        }.await;
+
+        match result {
+            Ok(points) => Ok(points),
+            Err(e) => {
+                println!("Error fetching points for account: {}. Error: {}", account, e);
+                Err(ApiError::PointsGetFail)
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        models::{model_token::TokenForCreate, model_trade::{BuyOrder, Trade}, model_user::UserForCreate},
+        test_utils::TestContext
+    };
+
+    use super::*;
+
+    fn buy_order(user_pubkey: &str) -> BuyOrder {
+        BuyOrder {
+            user_pubkey: user_pubkey.to_string(),
+            token_pubkey: "MockMintAlpha".to_string(),
+            vs_token_pubkey: "So11111111111111111111111111111111111111112".to_string(),
+            vs_token_symbol: "SOL".to_string(),
+            vs_token_logo_url: String::new(),
+            amount: 1.0,
+            expected_price: 2.0,
+            max_slippage_bps: 100,
+            spin_id: None
+        }
+    }
+
+    fn token() -> TokenForCreate {
+        TokenForCreate {
+            mint_pubkey: "MockMintAlpha".to_string(),
+            symbol: "ALPHA".to_string(),
+            name: "Alpha".to_string(),
+            logo_url: String::new(),
+            price_change_24h_percent: 0.0,
+            volume_24h_usd: 0.0,
+            discord_url: None,
+            twitter_url: None,
+            website_url: None,
+            telegram_url: None,
+            decimals: 6,
+            is_active: true,
+            risk_score: None
+        }
+    }
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 4)]
+    #[ignore = "needs DATABASE_URL"]
+    async fn pays_each_trading_milestone_once_under_concurrent_buys() {
+        let context = TestContext::start().await;
+        let state = context.state.clone();
+        let user_pubkey = "MilestoneUser";
+
+        state.user_repo.create_user(UserForCreate { user_pubkey: user_pubkey.to_string(), referral_code: None })
+            .await.unwrap();
+        let token = state.token_repo.create_token(token()).await.unwrap();
+
+        let buys = (0..10).map(|_| tokio::spawn(Trade::execute_buy(buy_order(user_pubkey), token.clone(), 2.0, "test", state.clone())));
+        for receipt in futures::future::join_all(buys).await {
+            receipt.unwrap().unwrap();
+        }
+
+        let mut conn = state.db.acquire().await.unwrap();
+        let milestones = sqlx::query_scalar::<_, String>(
+                "SELECT reference FROM points_ledger WHERE account = $1 AND kind = $2 ORDER BY reference"
+            )
+            .bind(user_pubkey)
+            .bind(PointsEntryKind::TradingMilestone.as_str())
+            .fetch_all(&mut *conn)
+            .await.unwrap();
+
+        assert_eq!(milestones, vec!["positions:1", "positions:10"]);
+        assert_eq!(PointsLedger::get_balance(user_pubkey, &mut conn).await.unwrap(), 15);
+
+        drop(conn);
+        context.cleanup().await;
+    }
+
+    #[tokio::test]
+    #[ignore = "needs DATABASE_URL"]
+    async fn reward_once_skips_repeated_milestones() {
+        let context = TestContext::start().await;
+        let mut tx = context.state.db.begin().await.unwrap();
+
+        let first = PointsLedger::reward_once("tg:1", 5, PointsEntryKind::TradingMilestone, "positions:1", &mut tx).await.unwrap();
+        let second = PointsLedger::reward_once("tg:1", 5, PointsEntryKind::TradingMilestone, "positions:1", &mut tx).await.unwrap();
+        let other_user = PointsLedger::reward_once("tg:2", 5, PointsEntryKind::TradingMilestone, "positions:1", &mut tx).await.unwrap();
+
+        assert!(first.is_some());
+        assert!(second.is_none());
+        assert!(other_user.is_some());
+        assert_eq!(PointsLedger::get_balance("tg:1", &mut tx).await.unwrap(), 5);
+        assert_eq!(PointsLedger::get_balance(SYSTEM_REWARDS_ACCOUNT, &mut tx).await.unwrap(), -10);
+
+        tx.commit().await.unwrap();
+        context.cleanup().await;
+    }
+}
@@ -0,0 +1,333 @@
+use std::collections::HashMap;
+
+use serde::Serialize;
+
+use super::model_position::{Position, PositionStatus, PriceStatus, UniquePositionsData};
+
+#[derive(Debug, Serialize)]
+pub struct Portfolio {
+    pub user_pubkey: String,
+    pub holdings: Vec<PortfolioHolding>,
+    pub vs_token_totals: Vec<PortfolioVsTokenTotal>,
+    pub usd_totals: PortfolioUsdTotals,
+    /// Token mints without a price in their vs token. Their holdings are left out of the value and unrealized totals.
+    pub missing_token_prices: Vec<String>,
+    /// Vs token mints without a USD price. Their totals are left out of `usd_totals`.
+    pub missing_vs_token_prices: Vec<String>
+}
+
+/// Aggregate of every position a user holds in one token, priced in one vs token.
+#[derive(Debug, Serialize)]
+pub struct PortfolioHolding {
+    pub token_pubkey: String,
+    pub token_symbol: String,
+    pub token_logo_url: String,
+    pub vs_token_pubkey: String,
+    pub vs_token_symbol: String,
+    pub open_positions: usize,
+    pub closed_positions: usize,
+    pub open_quantity: f64,
+    pub current_price: Option<f64>,
+    pub price_status: PriceStatus,
+    pub cost_basis: f64,
+    pub current_value: Option<f64>,
+    pub unrealized_pnl: Option<f64>,
+    pub realized_pnl: f64
+}
+
+#[derive(Debug, Serialize)]
+pub struct PortfolioVsTokenTotal {
+    pub vs_token_pubkey: String,
+    pub vs_token_symbol: String,
+    pub usd_price: Option<f64>,
+    pub cost_basis: f64,
+    pub current_value: f64,
+    pub unrealized_pnl: f64,
+    pub realized_pnl: f64
+}
+
+#[derive(Debug, Serialize, Default)]
+pub struct PortfolioUsdTotals {
+    pub cost_basis: f64,
+    pub current_value: f64,
+    pub unrealized_pnl: f64,
+    pub realized_pnl: f64,
+    pub total_pnl: f64
+}
+
+impl Portfolio {
+    /// Builds the summary from already fetched prices.
+    /// `token_prices` is keyed by (token_pubkey, vs_token_symbol), `usd_prices` by vs_token_symbol.
+    pub fn build(
+        user_pubkey: &str,
+        unique_tokens_and_vs_tokens: Vec<UniquePositionsData>,
+        positions: Vec<Position>,
+        token_prices: &HashMap<(String, String), f64>,
+        usd_prices: &HashMap<String, f64>
+    ) -> Self {
+        let mut holdings: Vec<PortfolioHolding> = Vec::with_capacity(unique_tokens_and_vs_tokens.len());
+        let mut missing_token_prices: Vec<String> = Vec::new();
+
+        for token_and_vs_token in unique_tokens_and_vs_tokens {
+            let current_price = token_prices
+                .get(&(token_and_vs_token.token_pubkey.clone(), token_and_vs_token.vs_token_symbol.clone()))
+                .copied();
+
+            let matching_positions: Vec<&Position> = positions.iter()
+                .filter(|position| position.token_pubkey == token_and_vs_token.token_pubkey && position.vs_token_symbol == token_and_vs_token.vs_token_symbol)
+                .collect();
+
+            let Some(first_position) = matching_positions.first() else {
+                continue
+            };
+
+            let mut holding = PortfolioHolding {
+                token_pubkey: first_position.token_pubkey.clone(),
+                token_symbol: first_position.token_symbol.clone(),
+                token_logo_url: first_position.token_logo_url.clone(),
+                vs_token_pubkey: first_position.vs_token_pubkey.clone(),
+                vs_token_symbol: first_position.vs_token_symbol.clone(),
+                open_positions: 0,
+                closed_positions: 0,
+                open_quantity: 0.0,
+                current_price,
+                price_status: if current_price.is_some() { PriceStatus::Available } else { PriceStatus::Unavailable },
+                cost_basis: 0.0,
+                current_value: None,
+                unrealized_pnl: None,
+                realized_pnl: 0.0
+            };
+
+            for position in matching_positions {
+                if position.status == PositionStatus::Open.as_str() {
+                    holding.open_positions += 1;
+                } else {
+                    holding.closed_positions += 1;
+                }
+
+                holding.open_quantity += position.current_quantity;
+                holding.cost_basis += position.current_quantity * position.purchase_price;
+                holding.realized_pnl += position.realized_pnl;
+            }
+
+            match current_price {
+                Some(current_price) => {
+                    let current_value = holding.open_quantity * current_price;
+
+                    holding.current_value = Some(current_value);
+                    holding.unrealized_pnl = Some(current_value - holding.cost_basis);
+                },
+                None => missing_token_prices.push(holding.token_pubkey.clone())
+            }
+
+            holdings.push(holding);
+        }
+
+        let mut vs_token_totals: Vec<PortfolioVsTokenTotal> = Vec::new();
+
+        for holding in &holdings {
+            let total = match vs_token_totals.iter_mut().find(|total| total.vs_token_symbol == holding.vs_token_symbol) {
+                Some(total) => total,
+                None => {
+                    vs_token_totals.push(PortfolioVsTokenTotal {
+                        vs_token_pubkey: holding.vs_token_pubkey.clone(),
+                        vs_token_symbol: holding.vs_token_symbol.clone(),
+                        usd_price: usd_prices.get(&holding.vs_token_symbol).copied(),
+                        cost_basis: 0.0,
+                        current_value: 0.0,
+                        unrealized_pnl: 0.0,
+                        realized_pnl: 0.0
+                    });
+                    vs_token_totals.last_mut().expect("total was just pushed")
+                }
+            };
+
+            total.realized_pnl += holding.realized_pnl;
+
+            if let (Some(current_value), Some(unrealized_pnl)) = (holding.current_value, holding.unrealized_pnl) {
+                total.cost_basis += holding.cost_basis;
+                total.current_value += current_value;
+                total.unrealized_pnl += unrealized_pnl;
+            }
+        }
+
+        let mut usd_totals = PortfolioUsdTotals::default();
+        let mut missing_vs_token_prices: Vec<String> = Vec::new();
+
+        for total in &vs_token_totals {
+            let Some(usd_price) = total.usd_price else {
+                missing_vs_token_prices.push(total.vs_token_pubkey.clone());
+                continue
+            };
+
+            usd_totals.cost_basis += total.cost_basis * usd_price;
+            usd_totals.current_value += total.current_value * usd_price;
+            usd_totals.unrealized_pnl += total.unrealized_pnl * usd_price;
+            usd_totals.realized_pnl += total.realized_pnl * usd_price;
+        }
+
+        usd_totals.total_pnl = usd_totals.unrealized_pnl + usd_totals.realized_pnl;
+
+        Portfolio {
+            user_pubkey: user_pubkey.to_string(),
+            holdings,
+            vs_token_totals,
+            usd_totals,
+            missing_token_prices,
+            missing_vs_token_prices
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use uuid::Uuid;
+
+    use super::*;
+
+    const SOL: (&str, &str) = ("So11111111111111111111111111111111111111112", "SOL");
+    const USDC: (&str, &str) = ("EPjFWdd5AufqSSqeM2qN1xzybapC8G4wEGGkZwyTDt1v", "USDC");
+
+    fn position(token_pubkey: &str, vs_token: (&str, &str), quantity: f64, purchase_price: f64) -> Position {
+        Position {
+            id: Uuid::new_v4(),
+            user_pubkey: "user".to_string(),
+            token_pubkey: token_pubkey.to_string(),
+            token_symbol: token_pubkey.to_uppercase(),
+            token_logo_url: String::new(),
+            vs_token_pubkey: vs_token.0.to_string(),
+            vs_token_symbol: vs_token.1.to_string(),
+            vs_token_logo_url: String::new(),
+            initial_quantity: quantity,
+            current_quantity: quantity,
+            purchase_price,
+            created_at: chrono::Utc::now(),
+            status: PositionStatus::Open.as_str().to_string(),
+            realized_pnl: 0.0,
+            closed_at: None
+        }
+    }
+
+    fn closed(mut position: Position, realized_pnl: f64) -> Position {
+        position.current_quantity = 0.0;
+        position.realized_pnl = realized_pnl;
+        position.status = PositionStatus::Closed.as_str().to_string();
+        position
+    }
+
+    /// One entry per (token, vs token) pair, the way the repo returns them.
+    fn unique_pairs(positions: &[Position]) -> Vec<UniquePositionsData> {
+        let mut pairs: Vec<UniquePositionsData> = Vec::new();
+
+        for position in positions {
+            if !pairs.iter().any(|pair| pair.token_pubkey == position.token_pubkey && pair.vs_token_symbol == position.vs_token_symbol) {
+                pairs.push(UniquePositionsData {
+                    token_pubkey: position.token_pubkey.clone(),
+                    vs_token_pubkey: position.vs_token_pubkey.clone(),
+                    vs_token_symbol: position.vs_token_symbol.clone()
+                });
+            }
+        }
+
+        pairs
+    }
+
+    fn build(positions: Vec<Position>, token_prices: &[(&str, &str, f64)], usd_prices: &[(&str, f64)]) -> Portfolio {
+        let token_prices: HashMap<(String, String), f64> = token_prices.iter()
+            .map(|(token_pubkey, vs_token_symbol, price)| ((token_pubkey.to_string(), vs_token_symbol.to_string()), *price))
+            .collect();
+        let usd_prices: HashMap<String, f64> = usd_prices.iter()
+            .map(|(vs_token_symbol, price)| (vs_token_symbol.to_string(), *price))
+            .collect();
+
+        Portfolio::build("user", unique_pairs(&positions), positions, &token_prices, &usd_prices)
+    }
+
+    fn assert_close(actual: f64, expected: f64) {
+        assert!((actual - expected).abs() < 1e-9, "expected {expected}, got {actual}");
+    }
+
+    #[test]
+    fn unpriced_tokens_are_left_out_of_the_value() {
+        let portfolio = build(
+            vec![position("a", USDC, 10.0, 1.0), position("a", USDC, 10.0, 2.0), position("b", USDC, 5.0, 4.0)],
+            &[("a", "USDC", 3.0)],
+            &[("USDC", 1.0)]
+        );
+
+        assert_eq!(portfolio.missing_token_prices, vec!["b"]);
+        assert!(portfolio.missing_vs_token_prices.is_empty());
+
+        let a = &portfolio.holdings[0];
+        assert_eq!((a.open_positions, a.price_status), (2, PriceStatus::Available));
+        assert_close(a.cost_basis, 30.0);
+        assert_close(a.current_value.unwrap(), 60.0);
+        assert_close(a.unrealized_pnl.unwrap(), 30.0);
+
+        let b = &portfolio.holdings[1];
+        assert_eq!((b.current_value, b.price_status), (None, PriceStatus::Unavailable));
+
+        // b's 20 USDC cost basis has no value to compare against, so it stays out of the totals
+        assert_close(portfolio.vs_token_totals[0].cost_basis, 30.0);
+        assert_close(portfolio.usd_totals.current_value, 60.0);
+        assert_close(portfolio.usd_totals.total_pnl, 30.0);
+    }
+
+    #[test]
+    fn totals_are_kept_per_vs_token_and_converted_to_usd() {
+        let portfolio = build(
+            vec![position("a", SOL, 10.0, 0.01), position("a", USDC, 10.0, 1.0), position("b", USDC, 4.0, 1.0)],
+            &[("a", "SOL", 0.02), ("a", "USDC", 1.5), ("b", "USDC", 0.5)],
+            &[("SOL", 150.0), ("USDC", 1.0)]
+        );
+
+        assert_eq!(portfolio.holdings.len(), 3);
+        assert!(portfolio.missing_token_prices.is_empty());
+
+        let symbols: Vec<&str> = portfolio.vs_token_totals.iter().map(|total| total.vs_token_symbol.as_str()).collect();
+        assert_eq!(symbols, vec!["SOL", "USDC"]);
+
+        let sol = &portfolio.vs_token_totals[0];
+        assert_close(sol.unrealized_pnl, 0.1);
+
+        let usdc = &portfolio.vs_token_totals[1];
+        assert_close(usdc.cost_basis, 14.0);
+        assert_close(usdc.current_value, 17.0);
+
+        assert_close(portfolio.usd_totals.cost_basis, 15.0 + 14.0);
+        assert_close(portfolio.usd_totals.unrealized_pnl, 15.0 + 3.0);
+    }
+
+    #[test]
+    fn vs_tokens_without_a_usd_price_are_reported_separately() {
+        let portfolio = build(
+            vec![position("a", SOL, 10.0, 0.01), position("a", USDC, 10.0, 1.0)],
+            &[("a", "SOL", 0.02), ("a", "USDC", 1.5)],
+            &[("USDC", 1.0)]
+        );
+
+        assert!(portfolio.missing_token_prices.is_empty());
+        assert_eq!(portfolio.missing_vs_token_prices, vec![SOL.0]);
+        assert_eq!(portfolio.vs_token_totals[0].usd_price, None);
+        assert_close(portfolio.usd_totals.unrealized_pnl, 5.0);
+    }
+
+    #[test]
+    fn closed_positions_only_add_realized_pnl() {
+        let portfolio = build(
+            vec![closed(position("a", USDC, 10.0, 1.0), 4.0), position("a", USDC, 2.0, 1.0)],
+            &[("a", "USDC", 2.0)],
+            &[("USDC", 1.0)]
+        );
+
+        let holding = &portfolio.holdings[0];
+        assert_eq!((holding.open_positions, holding.closed_positions), (1, 1));
+        assert_close(holding.open_quantity, 2.0);
+        assert_close(holding.cost_basis, 2.0);
+        assert_close(holding.realized_pnl, 4.0);
+        assert_close(holding.unrealized_pnl.unwrap(), 2.0);
+
+        assert_close(portfolio.usd_totals.realized_pnl, 4.0);
+        assert_close(portfolio.usd_totals.total_pnl, 6.0);
+    }
+}
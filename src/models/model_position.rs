@@ -1,6 +1,22 @@
 use serde::{Deserialize, Serialize};
+use sqlx::PgExecutor;
 use uuid::Uuid;
-use crate::{errors::api_errors::{ApiError, Result}, AppState};
+use crate::{errors::api_errors::{ApiError, Result}, utils, AppState};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PositionStatus {
+    Open,
+    Closed,
+}
+
+impl PositionStatus {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            PositionStatus::Open => "open",
+            PositionStatus::Closed => "closed",
+        }
+    }
+}
 
 #[derive(sqlx::FromRow, Debug, Serialize, Clone)]
 pub struct Position {
@@ -16,6 +32,29 @@ pub struct Position {
     pub current_quantity: f64,
     pub purchase_price: f64,
     pub created_at: chrono::DateTime<chrono::Utc>,
+    pub status: String,
+    pub realized_pnl: f64,
+    pub closed_at: Option<chrono::DateTime<chrono::Utc>>,
+}
+
+#[derive(sqlx::FromRow, Debug, Serialize, Clone)]
+pub struct PositionFill {
+    pub id: Uuid,
+    pub position_id: Uuid,
+    pub trade_id: Option<Uuid>,
+    pub quantity: f64,
+    pub price: f64,
+    pub realized_pnl: f64,
+    pub created_at: chrono::DateTime<chrono::Utc>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PriceStatus {
+    Available,
+    /// Priced, but the price sources disagreed by more than the configured threshold.
+    Divergent,
+    Unavailable,
 }
 
 #[derive(Debug, Serialize)]
@@ -32,18 +71,36 @@ pub struct PositionWithProfit {
     pub current_quantity: f64,
     pub purchase_price: f64,
     pub created_at: chrono::DateTime<chrono::Utc>,
-    pub current_price: f64,
-    pub percentage_change: f64,
-    pub price_change: f64
+    pub status: String,
+    pub closed_at: Option<chrono::DateTime<chrono::Utc>>,
+    /// `None` when no price source could price the token, see `price_status`.
+    pub current_price: Option<f64>,
+    pub price_status: PriceStatus,
+    /// Source that served `current_price`.
+    pub price_source: Option<String>,
+    /// Largest relative gap between `current_price` and another source's price, when cross-checked.
+    pub price_divergence: Option<f64>,
+    pub percentage_change: Option<f64>,
+    pub price_change: Option<f64>,
+    /// Profit from fills that already happened, in the vs token.
+    pub realized_pnl: f64,
+    /// Mark to market profit of the quantity still held, in the vs token.
+    pub unrealized_pnl: Option<f64>
 }
 
 impl PositionWithProfit {
     pub fn new(
-        position: Position, 
-        current_price: f64,
-        percentage_change: f64, 
-        price_change: f64
+        position: Position,
+        current_price: Option<f64>
     ) -> Self {
+        let changes = current_price
+            .map(|current_price| utils::calculate_price_change(current_price, position.purchase_price));
+
+        let price_status = match current_price {
+            Some(_) => PriceStatus::Available,
+            None => PriceStatus::Unavailable
+        };
+
         PositionWithProfit {
             id: position.id,
             user_pubkey: position.user_pubkey,
@@ -57,10 +114,29 @@ impl PositionWithProfit {
             current_quantity: position.current_quantity,
             purchase_price: position.purchase_price,
             created_at: position.created_at,
+            status: position.status,
+            closed_at: position.closed_at,
             current_price,
-            percentage_change,
-            price_change
+            price_status,
+            price_source: None,
+            price_divergence: None,
+            percentage_change: changes.map(|(_, percentage_change)| percentage_change),
+            price_change: changes.map(|(price_change, _)| price_change),
+            realized_pnl: position.realized_pnl,
+            unrealized_pnl: changes.map(|(price_change, _)| price_change * position.current_quantity)
+        }
+    }
+
+    /// Records which source priced the position, marking the price divergent when the sources disagreed.
+    pub fn with_price_source(mut self, source: &str, divergence: Option<f64>, divergent: bool) -> Self {
+        self.price_source = Some(source.to_string());
+        self.price_divergence = divergence;
+
+        if divergent {
+            self.price_status = PriceStatus::Divergent;
         }
+
+        self
     }
 }
 
@@ -80,6 +156,7 @@ pub struct PositionForCreate {
 #[derive(Deserialize, Debug, sqlx::FromRow)]
 pub struct UniquePositionsData {
     pub token_pubkey: String,
+    pub vs_token_pubkey: String,
     pub vs_token_symbol: String
 }
 
@@ -92,13 +169,12 @@ pub struct UpdatePositionData {
 // CRUD implementation for Position
 
 impl Position {
-    pub async fn create_position(
-        position: PositionForCreate, 
-        state: AppState
-    ) -> Result<Self> {
-        println!("->> {:<12} - create_position", "CONTROLLER");
-
-        let result = sqlx::query_as::<_, Position>(
+    /// Insert shared by `PgPositionRepo::create_position` and the trade flows, which run it inside a transaction.
+    pub async fn insert_position<'e>(
+        position: PositionForCreate,
+        executor: impl PgExecutor<'e>
+    ) -> sqlx::Result<Self> {
+        sqlx::query_as::<_, Position>(
                 "INSERT INTO positions (user_pubkey, token_pubkey, token_symbol, token_logo_url, vs_token_pubkey, vs_token_symbol, vs_token_logo_url, initial_quantity, current_quantity, purchase_price) VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10) RETURNING *;"
             )
             .bind(position.user_pubkey)
@@ -111,159 +187,33 @@ impl Position {
             .bind(position.quantity)
             .bind(position.quantity)
             .bind(position.purchase_price)
-            .fetch_one(&state.db)
-            .await;
-
-        match result {
-            Ok(position) => Ok(position),
-            Err(e) => {
-                println!("Error creating position. Error: {}", e);
-                Err(ApiError::PositionCreateFail)
-            }
-        }
-    }
-
-    pub async fn update_position_quantity(
-        update_data: UpdatePositionData,
-        state: AppState
-    ) -> Result<Position> {
-        println!("->> {:<12} - update_position", "CONTROLLER");
-
-        let result = sqlx::query_as::<_, Position>(
-                "UPDATE positions SET current_quantity = $1 WHERE id = $2 RETURNING *"
-            )
-            .bind(update_data.new_quantity)
-            .bind(&update_data.position_id)
-            .fetch_one(&state.db)
-            .await;
-
-        match result {
-            Ok(position) => Ok(position),
-            Err(e) => {
-                println!("Error updating position with id: {}. Error: {}",update_data.position_id, e);
-                Err(ApiError::PositionGetFail)
-            }
-        }
+            .fetch_one(executor)
+            .await
     }
+}
 
-    pub async fn get_positions(state: AppState) -> Result<Vec<Self>> {
-        println!("->> {:<12} - get_positions", "CONTROLLER");
-
-        let result = sqlx::query_as::<_, Position>(
-                "SELECT * FROM positions;"
-            )
-            .fetch_all(&state.db)
-            .await;
-
-        match result {
-            Ok(positions) => Ok(positions),
-            Err(e) => {
-                println!("Error fetching positions. Error: {}", e);
-                Err(ApiError::PositionGetFail)
-            }
-        }
-    }
-
-    pub async fn get_user_positions(
-        user_pubkey: &str, 
-        state: AppState
-    ) -> Result<Vec<Position>> {
-        println!("->> {:<12} - get_user_positions", "CONTROLLER");
-
-        let result = sqlx::query_as::<_, Position>(
-                "SELECT * FROM positions WHERE user_pubkey = $1;"
-            )
-            .bind(&user_pubkey)
-            .fetch_all(&state.db)
-            .await;
-
-        match result {
-            Ok(positions) => Ok(positions),
-            Err(e) => {
-                println!("Error fetching positions for user: {}. Error: {}", user_pubkey, e);
-                Err(ApiError::PositionGetFail)
-            }
-        }
-    }
-
-    pub async fn get_user_positions_by_token(
-        user_pubkey: &str,
-        token_pubkey: &str,
-        state: AppState
-    ) -> Result<Vec<Position>> {
-        println!("->> {:<12} - get_user_positions_by_token", "CONTROLLER");
-
-        let result = sqlx::query_as::<_, Position>(
-                "SELECT * FROM positions WHERE user_pubkey = $1 AND token_pubkey = $2;"
-            )
-            .bind(&user_pubkey)
-            .bind(&token_pubkey)
-            .fetch_all(&state.db)
-            .await;
-
-        match result {
-            Ok(positions) => Ok(positions),
-            Err(e) => {
-                println!(
-                    "Error fetching positions for user: {}, mint: {}. Error: {}",
-                    user_pubkey,
-                    token_pubkey,
-                    e
-                );
-                Err(ApiError::PositionGetFail)
-            }
-        }
-    }
-
-    pub async fn get_token_positions(
-        token_pubkey: &str,
-        state: AppState
-    ) -> Result<Vec<Position>> {
-        println!("->> {:<12} - get_token_positions", "CONTROLLER");
-
-        let result = sqlx::query_as::<_, Position>(
-                "SELECT * FROM positions WHERE token_pubkey = $1;"
-            )
-            .bind(&token_pubkey)
-            .fetch_all(&state.db)
-            .await;
-
-        match result {
-            Ok(positions) => Ok(positions),
-            Err(e) => {
-                println!(
-                    "Error fetching positions for mint: {}. Error: {}",
-                    token_pubkey,
-                    e
-                );
-                Err(ApiError::PositionGetFail)
-            }
-        }
-    }
+// CRUD implementation for PositionFill
 
-    pub async fn get_user_unique_tokens_and_vs_tokens(
-        user_pubkey: &str,
+impl PositionFill {
+    pub async fn get_position_fills(
+        position_id: &Uuid,
         state: AppState
-    ) -> Result<Vec<UniquePositionsData>> {
-        println!("->> {:<12} - get_unique_positions_pubkey_and_vs_token", "CONTROLLER");
+    ) -> Result<Vec<Self>> {
+        println!("->> {:<12} - get_position_fills", "CONTROLLER");
 
-        let result = sqlx::query_as::<_, UniquePositionsData>(
-                "SELECT DISTINCT ON (token_pubkey, vs_token_symbol) token_pubkey, vs_token_symbol FROM positions WHERE user_pubkey = $1 ORDER BY token_pubkey, vs_token_symbol, created_at;"
+        let result = sqlx::query_as::<_, PositionFill>(
+                "SELECT * FROM position_fills WHERE position_id = $1 ORDER BY created_at;"
             )
-            .bind(user_pubkey)
+            .bind(position_id)
             .fetch_all(&state.db)
             .await;
 
         match result {
-            Ok(unique_positions) => Ok(unique_positions),
+            Ok(fills) => Ok(fills),
             Err(e) => {
-                println!(
-                    "Error fetching unique positions for user: {}. Error: {}",
-                    user_pubkey,
-                    e
-                );
+                println!("Error fetching fills for position: {}. Error: {}", position_id, e);
                 Err(ApiError::PositionGetFail)
             }
         }
     }
-}
\ No newline at end of file
+}
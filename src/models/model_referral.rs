@@ -0,0 +1,229 @@
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use sqlx::PgConnection;
+use crate::{errors::api_errors::{ApiError, Result}, utils, AppState};
+
+use super::model_points::{PointsEntryKind, PointsLedger};
+
+/// Points credited to the referrer once the referee opens their first position.
+pub const REFERRAL_REWARD_POINTS: i64 = 5;
+
+#[derive(Debug, sqlx::FromRow, Serialize)]
+pub struct ReferralCode {
+    pub code: String,
+    pub user_pubkey: String,
+    pub created_at: DateTime<Utc>
+}
+
+// CRUD implementation for ReferralCode
+
+impl ReferralCode {
+    /// Returns the user's code, creating it on first use.
+    pub async fn get_or_create_referral_code(
+        user_pubkey: &str,
+        state: AppState
+    ) -> Result<Self> {
+        println!("->> {:<12} - get_or_create_referral_code", "CONTROLLER");
+
+        let result: sqlx::Result<Option<ReferralCode>> = async {
+            let mut attempts = 0;
+
+            // DO NOTHING covers both unique keys, a skipped insert is either an existing code or a taken one
+            loop {
+                let inserted = sqlx::query(
+                        "INSERT INTO referral_codes (code, user_pubkey) VALUES ($1, $2) ON CONFLICT DO NOTHING"
+                    )
+                    .bind(utils::generate_access_code())
+                    .bind(user_pubkey)
+                    .execute(&state.db)
+                    .await?;
+
+                let referral_code = sqlx::query_as::<_, ReferralCode>(
+                        "SELECT code, user_pubkey, created_at FROM referral_codes WHERE user_pubkey = $1"
+                    )
+                    .bind(user_pubkey)
+                    .fetch_optional(&state.db)
+                    .await?;
+
+                if inserted.rows_affected() == 1 || referral_code.is_some() {
+                    return Ok(referral_code)
+                }
+
+                attempts += 1;
+
+                if attempts == utils::ACCESS_CODE_ATTEMPTS {
+                    return Ok(None)
+                }
+            }
+        }.await;
+
+        match result {
+            Ok(Some(referral_code)) => Ok(referral_code),
+            Ok(None) => {
+                println!("Error creating referral code for user: {}. No free code after {} attempts", user_pubkey, utils::ACCESS_CODE_ATTEMPTS);
+                Err(ApiError::ReferralCodeCreateFail)
+            },
+            Err(e) => {
+                println!("Error creating referral code for user: {}. Error: {}", user_pubkey, e);
+                Err(ApiError::ReferralCodeCreateFail)
+            }
+        }
+    }
+
+    /// Marks `user_pubkey` as referred by the owner of `code`, inside the caller's transaction.
+    /// Rejects unknown codes, self referral, users who were already referred, and codes whose
+    /// owner is somewhere down the user's own referral chain.
+    /// The outer error is a database failure, the inner one a rejected claim.
+    pub async fn claim_referral(
+        user_pubkey: &str,
+        code: &str,
+        conn: &mut PgConnection
+    ) -> sqlx::Result<Result<()>> {
+        println!("->> {:<12} - claim_referral", "CONTROLLER");
+
+        let referrer = sqlx::query_scalar::<_, String>(
+                "SELECT user_pubkey FROM referral_codes WHERE code = $1"
+            )
+            .bind(code)
+            .fetch_optional(&mut *conn)
+            .await?;
+
+        let Some(referrer) = referrer else {
+            return Ok(Err(ApiError::ReferralCodeInvalid))
+        };
+
+        if referrer == user_pubkey {
+            return Ok(Err(ApiError::ReferralNotAllowed))
+        }
+
+        let referred_by = sqlx::query_scalar::<_, Option<String>>(
+                "SELECT referred_by FROM users WHERE user_pubkey = $1 FOR UPDATE"
+            )
+            .bind(user_pubkey)
+            .fetch_one(&mut *conn)
+            .await?;
+
+        if referred_by.is_some() {
+            return Ok(Err(ApiError::ReferralNotAllowed))
+        }
+
+        // UNION rather than UNION ALL, so a cycle that slipped into the data still terminates
+        let is_circular = sqlx::query_scalar::<_, bool>(
+                r#"WITH RECURSIVE referral_chain AS (
+                    SELECT user_pubkey, referred_by FROM users WHERE user_pubkey = $1
+                    UNION
+                    SELECT users.user_pubkey, users.referred_by FROM users
+                    JOIN referral_chain ON users.user_pubkey = referral_chain.referred_by
+                )
+                SELECT EXISTS (SELECT 1 FROM referral_chain WHERE user_pubkey = $2)"#
+            )
+            .bind(&referrer)
+            .bind(user_pubkey)
+            .fetch_one(&mut *conn)
+            .await?;
+
+        if is_circular {
+            return Ok(Err(ApiError::ReferralNotAllowed))
+        }
+
+        sqlx::query("UPDATE users SET referred_by = $2 WHERE user_pubkey = $1")
+            .bind(user_pubkey)
+            .bind(&referrer)
+            .execute(&mut *conn)
+            .await?;
+
+        Ok(Ok(()))
+    }
+
+    /// Credits the referrer of `user_pubkey` the first time it is called for a referred user,
+    /// inside the caller's transaction. Later calls and unreferred users are no-ops.
+    pub async fn credit_referral_reward(
+        user_pubkey: &str,
+        conn: &mut PgConnection
+    ) -> sqlx::Result<()> {
+        let referrer = sqlx::query_scalar::<_, String>(
+                r#"UPDATE users SET referral_rewarded_at = CURRENT_TIMESTAMP
+                WHERE user_pubkey = $1 AND referred_by IS NOT NULL AND referral_rewarded_at IS NULL
+                RETURNING referred_by"#
+            )
+            .bind(user_pubkey)
+            .fetch_optional(&mut *conn)
+            .await?;
+
+        if let Some(referrer) = referrer {
+            println!("->> {:<12} - credit_referral_reward {} -> {}", "CONTROLLER", user_pubkey, referrer);
+
+            PointsLedger::reward(
+                &referrer,
+                REFERRAL_REWARD_POINTS,
+                PointsEntryKind::Referral,
+                Some(user_pubkey),
+                &mut *conn
+            ).await?;
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{models::model_user::UserForCreate, test_utils::TestContext};
+
+    fn user(user_pubkey: &str, referral_code: Option<&str>) -> UserForCreate {
+        UserForCreate {
+            user_pubkey: user_pubkey.to_string(),
+            referral_code: referral_code.map(|code| code.to_string())
+        }
+    }
+
+    #[tokio::test]
+    #[ignore = "needs DATABASE_URL"]
+    async fn reuses_the_users_code() {
+        let context = TestContext::start().await;
+        let state = context.state.clone();
+
+        state.user_repo.create_user(user("alice", None)).await.unwrap();
+
+        let first = ReferralCode::get_or_create_referral_code("alice", state.clone()).await.unwrap();
+        let second = ReferralCode::get_or_create_referral_code("alice", state.clone()).await.unwrap();
+
+        assert_eq!(first.code, second.code);
+
+        context.cleanup().await;
+    }
+
+    #[tokio::test]
+    #[ignore = "needs DATABASE_URL"]
+    async fn rejects_invalid_self_and_circular_referrals() {
+        let context = TestContext::start().await;
+        let state = context.state.clone();
+
+        state.user_repo.create_user(user("alice", None)).await.unwrap();
+        let alice_code = ReferralCode::get_or_create_referral_code("alice", state.clone()).await.unwrap().code;
+
+        let bob = state.user_repo.create_user(user("bob", Some(&alice_code))).await.unwrap();
+        assert_eq!(bob.referred_by.as_deref(), Some("alice"));
+        let bob_code = ReferralCode::get_or_create_referral_code("bob", state.clone()).await.unwrap().code;
+
+        // an unknown code rolls the whole registration back
+        let invalid = state.user_repo.create_user(user("carol", Some("NOTACODE"))).await;
+        assert!(matches!(invalid, Err(ApiError::ReferralCodeInvalid)));
+        assert!(state.user_repo.get_user("carol").await.unwrap().is_none());
+
+        let mut tx = state.db.begin().await.unwrap();
+
+        let self_referral = ReferralCode::claim_referral("alice", &alice_code, &mut tx).await.unwrap();
+        assert!(matches!(self_referral, Err(ApiError::ReferralNotAllowed)));
+
+        let circular = ReferralCode::claim_referral("alice", &bob_code, &mut tx).await.unwrap();
+        assert!(matches!(circular, Err(ApiError::ReferralNotAllowed)));
+
+        let already_referred = ReferralCode::claim_referral("bob", &alice_code, &mut tx).await.unwrap();
+        assert!(matches!(already_referred, Err(ApiError::ReferralNotAllowed)));
+
+        tx.rollback().await.unwrap();
+        context.cleanup().await;
+    }
+}
@@ -0,0 +1,174 @@
+use chrono::{DateTime, NaiveDate, Utc};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+use crate::errors::api_errors::{ApiError, Result};
+
+/// Risk scores run from 0, nothing flagged, to this.
+pub const MAX_RISK_SCORE: i32 = 100;
+const MAX_PAGE_COUNT: i32 = 10;
+/// Birdeye returns 50 tokens per list page.
+const TOKENS_PER_PAGE: i32 = 50;
+
+/// Birdeye token list ordering the selector pages through.
+#[derive(Debug, Clone, Copy, Deserialize, Serialize, Default)]
+pub enum SelectionSortKey {
+    #[default]
+    #[serde(rename = "volume_24h_usd")]
+    Volume24hUsd,
+    #[serde(rename = "market_cap")]
+    MarketCap,
+    #[serde(rename = "volume_24h_change_percent")]
+    Volume24hChangePercent,
+}
+
+impl SelectionSortKey {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            SelectionSortKey::Volume24hUsd => "volume_24h_usd",
+            SelectionSortKey::MarketCap => "market_cap",
+            SelectionSortKey::Volume24hChangePercent => "volume_24h_change_percent",
+        }
+    }
+
+    pub fn parse(sort_by: &str) -> Option<Self> {
+        match sort_by {
+            "volume_24h_usd" => Some(SelectionSortKey::Volume24hUsd),
+            "market_cap" => Some(SelectionSortKey::MarketCap),
+            "volume_24h_change_percent" => Some(SelectionSortKey::Volume24hChangePercent),
+            _ => None
+        }
+    }
+
+    /// Value of Birdeye's `sort_by` query parameter.
+    pub fn birdeye_sort_by(&self) -> &'static str {
+        match self {
+            SelectionSortKey::Volume24hUsd => "v24hUSD",
+            SelectionSortKey::MarketCap => "mc",
+            SelectionSortKey::Volume24hChangePercent => "v24hChangePercent",
+        }
+    }
+}
+
+/// Criteria the coin selector filters Birdeye's token list with.
+#[derive(Debug, sqlx::FromRow, Serialize, Clone)]
+pub struct SelectionConfig {
+    pub min_market_cap_usd: f64,
+    pub min_liquidity_usd: f64,
+    pub min_trades_24h: i32,
+    /// Number of coins made spinnable per run.
+    pub selection_size: i32,
+    /// Birdeye list pages fetched per run.
+    pub page_count: i32,
+    pub sort_by: String,
+    /// Highest risk score a token may have and still be selected.
+    pub max_risk_score: i32,
+    /// Supply share above which the top 10 holders add to the risk score, 0.5 is 50%.
+    pub max_top10_holder_share: f64,
+    /// Supply share above which a creator still holding tokens adds to the risk score.
+    pub max_creator_share: f64,
+    pub updated_at: DateTime<Utc>
+}
+
+#[derive(Debug, Deserialize, Clone)]
+pub struct SelectionConfigForUpdate {
+    pub min_market_cap_usd: f64,
+    pub min_liquidity_usd: f64,
+    pub min_trades_24h: i32,
+    pub selection_size: i32,
+    pub page_count: i32,
+    pub sort_by: SelectionSortKey,
+    pub max_risk_score: i32,
+    pub max_top10_holder_share: f64,
+    pub max_creator_share: f64
+}
+
+impl Default for SelectionConfigForUpdate {
+    fn default() -> Self {
+        Self {
+            min_market_cap_usd: 500_000.0,
+            min_liquidity_usd: 100_000.0,
+            min_trades_24h: 500,
+            selection_size: 25,
+            page_count: 2,
+            sort_by: SelectionSortKey::default(),
+            max_risk_score: 20,
+            max_top10_holder_share: 0.5,
+            max_creator_share: 0.1
+        }
+    }
+}
+
+impl SelectionConfigForUpdate {
+    pub fn validate(&self) -> Result<()> {
+        let valid = self.min_market_cap_usd >= 0.0
+            && self.min_liquidity_usd >= 0.0
+            && self.min_trades_24h >= 0
+            && self.selection_size > 0
+            && (1..=MAX_PAGE_COUNT).contains(&self.page_count)
+            && self.selection_size <= self.page_count * TOKENS_PER_PAGE
+            && (0..=MAX_RISK_SCORE).contains(&self.max_risk_score)
+            && (0.0..=1.0).contains(&self.max_top10_holder_share)
+            && (0.0..=1.0).contains(&self.max_creator_share);
+
+        if valid {
+            Ok(())
+        } else {
+            Err(ApiError::SelectionConfigInvalid)
+        }
+    }
+}
+
+impl SelectionConfig {
+    /// Falls back to the default ordering if the stored key is unknown.
+    pub fn sort_key(&self) -> SelectionSortKey {
+        SelectionSortKey::parse(&self.sort_by).unwrap_or_default()
+    }
+}
+
+/// One execution of the coin selector. Filter values are `None` for runs archived before they were recorded.
+#[derive(Debug, sqlx::FromRow, Serialize, Clone)]
+pub struct SelectionRun {
+    pub id: Uuid,
+    pub run_date: NaiveDate,
+    pub min_market_cap_usd: Option<f64>,
+    pub min_liquidity_usd: Option<f64>,
+    pub min_trades_24h: Option<i32>,
+    pub selection_size: Option<i32>,
+    pub page_count: Option<i32>,
+    pub sort_by: Option<String>,
+    pub max_risk_score: Option<i32>,
+    pub max_top10_holder_share: Option<f64>,
+    pub max_creator_share: Option<f64>,
+    pub created_at: DateTime<Utc>
+}
+
+#[derive(Debug, sqlx::FromRow, Serialize, Clone)]
+pub struct SelectedToken {
+    pub mint_pubkey: String,
+    pub symbol: String,
+    pub name: String,
+    pub logo_url: String,
+    pub price_change_24h_percent: f64,
+    pub volume_24h_usd: f64,
+    pub risk_score: Option<i32>
+}
+
+#[derive(Debug, Serialize, Clone)]
+pub struct SelectionRunWithTokens {
+    #[serde(flatten)]
+    pub run: SelectionRun,
+    pub tokens: Vec<SelectedToken>
+}
+
+#[derive(Debug)]
+pub struct SelectionRunForCreate {
+    /// Config the run filtered with.
+    pub config: SelectionConfig,
+    pub tokens: Vec<SelectedToken>
+}
+
+#[derive(Debug, Deserialize)]
+pub struct SelectionParams {
+    /// UTC day to look up, today when omitted.
+    pub date: Option<NaiveDate>
+}
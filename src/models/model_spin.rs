@@ -0,0 +1,525 @@
+use chrono::{DateTime, Utc};
+use hmac::{Hmac, Mac};
+use serde::{Deserialize, Serialize};
+use sha2::Sha256;
+use sqlx::PgConnection;
+use uuid::Uuid;
+use crate::{errors::api_errors::{ApiError, Result}, utils, AppState};
+
+use super::{model_points::{daily_streak_points, PointsEntryKind, PointsLedger}, model_spin_mode::SpinMode, model_spin_quota::{SpinQuota, SpinQuotaConfig}, model_token::Token};
+
+const MAX_CLIENT_SEED_LENGTH: usize = 64;
+const DEFAULT_HISTORY_LIMIT: i64 = 20;
+const MAX_HISTORY_LIMIT: i64 = 100;
+
+const SPIN_SELECT: &str = r#"SELECT spins.id, spins.user_pubkey, spins.seed_id, spin_seeds.server_seed, spin_seeds.server_seed_hash,
+    spins.client_seed, spins.nonce, spins.mode, spins.candidate_mints, spins.candidate_weights, spins.selected_mint, spins.position_id, spins.created_at
+    FROM spins
+    JOIN spin_seeds ON spin_seeds.id = spins.seed_id"#;
+
+/// HMAC-SHA256 of `{client_seed}:{nonce}:{round}`, keyed with the server seed.
+pub fn spin_digest(server_seed: &str, client_seed: &str, nonce: i64, round: u32) -> [u8; 32] {
+    let mut mac = Hmac::<Sha256>::new_from_slice(server_seed.as_bytes())
+        .expect("hmac accepts keys of any length");
+
+    mac.update(format!("{}:{}:{}", client_seed, nonce, round).as_bytes());
+
+    mac.finalize().into_bytes().into()
+}
+
+/// Maps the seeds to a value in `0..range`.
+/// The first 8 bytes of the digest are read as a big endian u64, and values from the
+/// incomplete tail of the u64 range are rejected, moving on to the next round, so that
+/// every value is equally likely.
+pub fn roll(server_seed: &str, client_seed: &str, nonce: i64, range: u64) -> u64 {
+    let limit = u64::MAX - u64::MAX % range;
+    let mut round = 0;
+
+    loop {
+        let digest = spin_digest(server_seed, client_seed, nonce, round);
+        let value = u64::from_be_bytes(digest[..8].try_into().expect("digest has 32 bytes"));
+
+        if value < limit {
+            return value % range
+        }
+
+        round += 1;
+    }
+}
+
+/// Picks a candidate index, each candidate covering as many consecutive roll values as its weight.
+/// With all weights at 1 this is a plain uniform pick.
+pub fn select_index(server_seed: &str, client_seed: &str, nonce: i64, weights: &[u64]) -> usize {
+    let total_weight: u64 = weights.iter().sum();
+    let mut remaining = roll(server_seed, client_seed, nonce, total_weight);
+
+    for (index, weight) in weights.iter().enumerate() {
+        if remaining < *weight {
+            return index
+        }
+
+        remaining -= weight;
+    }
+
+    unreachable!("roll is below the total weight")
+}
+
+/// Spins and odds order candidates by mint, so the order doesn't depend on how the database returned them.
+pub fn sort_candidates(tokens: &mut [Token]) {
+    tokens.sort_by(|a, b| a.mint_pubkey.cmp(&b.mint_pubkey));
+}
+
+#[derive(Debug, sqlx::FromRow)]
+struct SpinSeed {
+    server_seed: String,
+    server_seed_hash: String,
+    used_at: Option<DateTime<Utc>>
+}
+
+/// Commitment handed out before a spin. The server seed is revealed once the seed is spun.
+#[derive(Debug, sqlx::FromRow, Serialize)]
+pub struct SpinSeedCommitment {
+    pub seed_id: Uuid,
+    pub server_seed_hash: String
+}
+
+#[derive(Debug, Deserialize)]
+pub struct SpinForCreate {
+    pub user_pubkey: String,
+    pub seed_id: Uuid,
+    pub client_seed: String,
+    pub nonce: i64
+}
+
+impl SpinForCreate {
+    pub fn validate(&self) -> Result<()> {
+        if self.client_seed.is_empty() || self.client_seed.len() > MAX_CLIENT_SEED_LENGTH || self.nonce < 0 {
+            return Err(ApiError::SpinInvalidRequest)
+        }
+
+        Ok(())
+    }
+}
+
+#[derive(Debug, sqlx::FromRow, Serialize, Clone)]
+pub struct Spin {
+    pub id: Uuid,
+    /// Empty for spins recorded before spins were tied to users.
+    pub user_pubkey: Option<String>,
+    pub seed_id: Uuid,
+    pub server_seed: String,
+    pub server_seed_hash: String,
+    pub client_seed: String,
+    pub nonce: i64,
+    pub mode: String,
+    /// Active mints at spin time, sorted, so the selected index can be recomputed.
+    pub candidate_mints: Vec<String>,
+    /// Weights of `candidate_mints` under `mode`. Empty for spins recorded before weighted modes, which were uniform.
+    pub candidate_weights: Option<Vec<i64>>,
+    pub selected_mint: String,
+    /// Position bought from this spin, if any.
+    pub position_id: Option<Uuid>,
+    pub created_at: DateTime<Utc>
+}
+
+#[derive(Debug, Serialize)]
+pub struct SpinResult {
+    pub spin: Spin,
+    pub token: Token
+}
+
+#[derive(Debug, Deserialize)]
+pub struct SpinHistoryParams {
+    pub limit: Option<i64>,
+    pub offset: Option<i64>
+}
+
+#[derive(Debug, Serialize)]
+pub struct SpinHistory {
+    pub user_pubkey: String,
+    pub total_spins: i64,
+    /// Spins that a position was opened from.
+    pub converted_spins: i64,
+    pub limit: i64,
+    pub offset: i64,
+    pub spins: Vec<Spin>
+}
+
+#[derive(Debug, Serialize)]
+pub struct SpinVerification {
+    pub spin: Spin,
+    pub server_seed_matches_hash: bool,
+    pub recomputed_mint: String,
+    pub verified: bool
+}
+
+impl Spin {
+    /// A spin can back a buy of the selected token by the user who spun it, once.
+    pub fn can_open_position(&self, user_pubkey: &str, token_pubkey: &str) -> bool {
+        self.user_pubkey.as_deref() == Some(user_pubkey)
+            && self.selected_mint == token_pubkey
+            && self.position_id.is_none()
+    }
+
+    /// Recomputes the commitment and the selection from the stored seeds.
+    pub fn verify(self) -> SpinVerification {
+        let server_seed_matches_hash = utils::hash_token(&self.server_seed) == self.server_seed_hash;
+
+        let weights: Vec<u64> = match &self.candidate_weights {
+            Some(weights) => weights.iter().map(|weight| *weight as u64).collect(),
+            None => vec![1; self.candidate_mints.len()]
+        };
+
+        let recomputed_index = select_index(
+            &self.server_seed,
+            &self.client_seed,
+            self.nonce,
+            &weights
+        );
+        let recomputed_mint = self.candidate_mints[recomputed_index].clone();
+
+        SpinVerification {
+            verified: server_seed_matches_hash && recomputed_mint == self.selected_mint,
+            server_seed_matches_hash,
+            recomputed_mint,
+            spin: self
+        }
+    }
+}
+
+// CRUD implementation for Spin
+
+impl Spin {
+    pub async fn create_seed(
+        state: AppState
+    ) -> Result<SpinSeedCommitment> {
+        println!("->> {:<12} - create_seed", "CONTROLLER");
+
+        let server_seed = utils::generate_random_token();
+
+        let result = sqlx::query_as::<_, SpinSeedCommitment>(
+                "INSERT INTO spin_seeds (server_seed, server_seed_hash) VALUES ($1, $2) RETURNING id AS seed_id, server_seed_hash"
+            )
+            .bind(&server_seed)
+            .bind(utils::hash_token(&server_seed))
+            .fetch_one(&state.db)
+            .await;
+
+        match result {
+            Ok(commitment) => Ok(commitment),
+            Err(e) => {
+                println!("Error creating spin seed. Error: {}", e);
+                Err(ApiError::SpinCreateFail)
+            }
+        }
+    }
+
+    /// Consumes the seed and records the spin over `candidates`, weighted by `mode`.
+    pub async fn create_spin(
+        spin: SpinForCreate,
+        mode: SpinMode,
+        candidates: Vec<Token>,
+        state: AppState
+    ) -> Result<SpinResult> {
+        println!("->> {:<12} - create_spin", "CONTROLLER");
+
+        let seed_id = spin.seed_id;
+
+        let result: sqlx::Result<Result<SpinResult>> = async {
+            let mut tx = state.db.begin().await?;
+
+            let spin_result = Self::insert_spin(spin, mode, candidates, state.spin_quota, &mut tx).await?;
+
+            // a rejected spin is dropped without commit, so neither the quota nor the seed is used up
+            if spin_result.is_ok() {
+                tx.commit().await?;
+            }
+
+            Ok(spin_result)
+        }.await;
+
+        match result {
+            Ok(spin_result) => spin_result,
+            Err(e) => {
+                println!("Error creating spin for seed: {}. Error: {}", seed_id, e);
+                Err(ApiError::SpinCreateFail)
+            }
+        }
+    }
+
+    /// Runs a spin inside the caller's transaction, so other writes can commit or roll back with it.
+    /// The outer error is a database failure, the inner one a rejected spin.
+    pub async fn insert_spin(
+        spin: SpinForCreate,
+        mode: SpinMode,
+        mut candidates: Vec<Token>,
+        quota: SpinQuotaConfig,
+        conn: &mut PgConnection
+    ) -> sqlx::Result<Result<SpinResult>> {
+        sort_candidates(&mut candidates);
+
+        let weights = mode.weights(&candidates);
+
+        let new_streak = match SpinQuota::consume(&spin.user_pubkey, quota, &mut *conn).await? {
+            Ok(new_streak) => new_streak,
+            Err(e) => return Ok(Err(e))
+        };
+
+        let seed = sqlx::query_as::<_, SpinSeed>(
+                "SELECT server_seed, server_seed_hash, used_at FROM spin_seeds WHERE id = $1 FOR UPDATE"
+            )
+            .bind(spin.seed_id)
+            .fetch_optional(&mut *conn)
+            .await?;
+
+        let seed = match seed {
+            Some(seed) if seed.used_at.is_none() => seed,
+            Some(_) => return Ok(Err(ApiError::SpinSeedUsed)),
+            None => return Ok(Err(ApiError::SpinSeedNotFound))
+        };
+
+        let selected_index = select_index(&seed.server_seed, &spin.client_seed, spin.nonce, &weights);
+        let token = candidates[selected_index].clone();
+        let candidate_mints: Vec<String> = candidates.into_iter()
+            .map(|candidate| candidate.mint_pubkey)
+            .collect();
+        let candidate_weights: Vec<i64> = weights.iter()
+            .map(|weight| *weight as i64)
+            .collect();
+
+        sqlx::query("UPDATE spin_seeds SET used_at = CURRENT_TIMESTAMP WHERE id = $1")
+            .bind(spin.seed_id)
+            .execute(&mut *conn)
+            .await?;
+
+        let (id, created_at) = sqlx::query_as::<_, (Uuid, DateTime<Utc>)>(
+                r#"INSERT INTO spins (user_pubkey, seed_id, client_seed, nonce, mode, candidate_mints, candidate_weights, selected_mint)
+                VALUES ($1, $2, $3, $4, $5, $6, $7, $8)
+                RETURNING id, created_at"#
+            )
+            .bind(&spin.user_pubkey)
+            .bind(spin.seed_id)
+            .bind(&spin.client_seed)
+            .bind(spin.nonce)
+            .bind(mode.as_str())
+            .bind(&candidate_mints)
+            .bind(&candidate_weights)
+            .bind(&token.mint_pubkey)
+            .fetch_one(&mut *conn)
+            .await?;
+
+        if let Some(streak_days) = new_streak {
+            PointsLedger::reward(
+                &spin.user_pubkey,
+                daily_streak_points(streak_days),
+                PointsEntryKind::DailyStreak,
+                Some(&created_at.date_naive().to_string()),
+                &mut *conn
+            ).await?;
+        }
+
+        Ok(Ok(SpinResult {
+            spin: Spin {
+                id,
+                user_pubkey: Some(spin.user_pubkey),
+                seed_id: spin.seed_id,
+                server_seed: seed.server_seed,
+                server_seed_hash: seed.server_seed_hash,
+                client_seed: spin.client_seed,
+                nonce: spin.nonce,
+                mode: mode.as_str().to_string(),
+                candidate_mints,
+                candidate_weights: Some(candidate_weights),
+                selected_mint: token.mint_pubkey.clone(),
+                position_id: None,
+                created_at
+            },
+            token
+        }))
+    }
+
+    pub async fn get_spin(
+        spin_id: &Uuid,
+        state: AppState
+    ) -> Result<Option<Self>> {
+        println!("->> {:<12} - get_spin", "CONTROLLER");
+
+        let result = sqlx::query_as::<_, Spin>(
+                &format!("{} WHERE spins.id = $1", SPIN_SELECT)
+            )
+            .bind(spin_id)
+            .fetch_optional(&state.db)
+            .await;
+
+        match result {
+            Ok(spin) => Ok(spin),
+            Err(e) => {
+                println!("Error fetching spin: {}. Error: {}", spin_id, e);
+                Err(ApiError::SpinGetFail)
+            }
+        }
+    }
+
+    pub async fn get_user_spin_history(
+        user_pubkey: &str,
+        params: SpinHistoryParams,
+        state: AppState
+    ) -> Result<SpinHistory> {
+        println!("->> {:<12} - get_user_spin_history", "CONTROLLER");
+
+        let limit = params.limit
+            .unwrap_or(DEFAULT_HISTORY_LIMIT)
+            .clamp(1, MAX_HISTORY_LIMIT);
+        let offset = params.offset.unwrap_or(0).max(0);
+
+        let result: sqlx::Result<SpinHistory> = async {
+            let (total_spins, converted_spins) = sqlx::query_as::<_, (i64, i64)>(
+                    "SELECT COUNT(*), COUNT(position_id) FROM spins WHERE user_pubkey = $1"
+                )
+                .bind(user_pubkey)
+                .fetch_one(&state.db)
+                .await?;
+
+            let spins = sqlx::query_as::<_, Spin>(
+                    &format!("{} WHERE spins.user_pubkey = $1 ORDER BY spins.created_at DESC, spins.id LIMIT $2 OFFSET $3", SPIN_SELECT)
+                )
+                .bind(user_pubkey)
+                .bind(limit)
+                .bind(offset)
+                .fetch_all(&state.db)
+                .await?;
+
+            Ok(SpinHistory {
+                user_pubkey: user_pubkey.to_string(),
+                total_spins,
+                converted_spins,
+                limit,
+                offset,
+                spins
+            })
+        }.await;
+
+        match result {
+            Ok(history) => Ok(history),
+            Err(e) => {
+                println!("Error fetching spin history for user: {}. Error: {}", user_pubkey, e);
+                Err(ApiError::SpinGetFail)
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SERVER_SEED: &str = "server-seed";
+    const CLIENT_SEED: &str = "client-seed";
+    /// Just over half the u64 range, so almost half of all first rounds are rejected.
+    const WIDE_RANGE: u64 = (1 << 63) + 1;
+
+    fn spin(candidate_mints: &[&str], candidate_weights: Option<Vec<i64>>, nonce: i64) -> Spin {
+        let weights: Vec<u64> = match &candidate_weights {
+            Some(weights) => weights.iter().map(|weight| *weight as u64).collect(),
+            None => vec![1; candidate_mints.len()]
+        };
+
+        Spin {
+            id: Uuid::new_v4(),
+            user_pubkey: Some("user".to_string()),
+            seed_id: Uuid::new_v4(),
+            server_seed: SERVER_SEED.to_string(),
+            server_seed_hash: utils::hash_token(SERVER_SEED),
+            client_seed: CLIENT_SEED.to_string(),
+            nonce,
+            mode: SpinMode::Uniform.as_str().to_string(),
+            candidate_mints: candidate_mints.iter().map(|mint| mint.to_string()).collect(),
+            selected_mint: candidate_mints[select_index(SERVER_SEED, CLIENT_SEED, nonce, &weights)].to_string(),
+            candidate_weights,
+            position_id: None,
+            created_at: Utc::now()
+        }
+    }
+
+    #[test]
+    fn digest_matches_known_answer() {
+        assert_eq!(
+            hex::encode(spin_digest(SERVER_SEED, CLIENT_SEED, 0, 0)),
+            "74935949b359c7ca0a38f0d2d6f710fc0434dccdeb3963035382b3f970c2c6df"
+        );
+        assert_ne!(spin_digest(SERVER_SEED, CLIENT_SEED, 0, 0), spin_digest(SERVER_SEED, CLIENT_SEED, 0, 1));
+        assert_ne!(spin_digest(SERVER_SEED, CLIENT_SEED, 0, 0), spin_digest(SERVER_SEED, CLIENT_SEED, 1, 0));
+    }
+
+    #[test]
+    fn roll_matches_known_answers() {
+        assert_eq!(roll(SERVER_SEED, CLIENT_SEED, 0, 10), 4);
+        assert_eq!(roll(SERVER_SEED, CLIENT_SEED, 2, 10), 0);
+        assert_eq!(roll(SERVER_SEED, CLIENT_SEED, 0, WIDE_RANGE), 8_400_155_903_052_597_194);
+    }
+
+    #[test]
+    fn roll_rejects_the_incomplete_tail() {
+        // round 0 of nonce 1 reads 12259763175496301004, past the last full multiple of the range
+        let first_round = u64::from_be_bytes(spin_digest(SERVER_SEED, CLIENT_SEED, 1, 0)[..8].try_into().unwrap());
+        assert!(first_round >= WIDE_RANGE);
+
+        assert_eq!(roll(SERVER_SEED, CLIENT_SEED, 1, WIDE_RANGE), 2_927_342_915_074_270_805);
+    }
+
+    #[test]
+    fn select_index_uses_the_rejection_sampled_roll() {
+        // a plain modulo of round 0 would land on 3036391138641525195, in the second candidate
+        let weights = [3_000_000_000_000_000_000, WIDE_RANGE - 3_000_000_000_000_000_000];
+
+        assert_eq!(select_index(SERVER_SEED, CLIENT_SEED, 1, &weights), 0);
+    }
+
+    #[test]
+    fn roll_stays_in_range_and_covers_it() {
+        let mut seen = [false; 7];
+
+        for nonce in 0..500 {
+            let value = roll(SERVER_SEED, CLIENT_SEED, nonce, 7);
+            assert!(value < 7);
+            seen[value as usize] = true;
+        }
+
+        assert!(seen.iter().all(|seen| *seen));
+        assert_eq!(roll(SERVER_SEED, CLIENT_SEED, 0, 1), 0);
+    }
+
+    #[test]
+    fn select_index_never_picks_zero_weights() {
+        for nonce in 0..200 {
+            assert_eq!(select_index(SERVER_SEED, CLIENT_SEED, nonce, &[0, 3, 0]), 1);
+        }
+    }
+
+    #[test]
+    fn verify_round_trips_weighted_and_legacy_spins() {
+        for nonce in 0..20 {
+            let weighted = spin(&["a", "b", "c"], Some(vec![5, 1, 2]), nonce).verify();
+            assert!(weighted.verified);
+            assert!(weighted.server_seed_matches_hash);
+            assert_eq!(weighted.recomputed_mint, weighted.spin.selected_mint);
+
+            assert!(spin(&["a", "b", "c"], None, nonce).verify().verified);
+        }
+    }
+
+    #[test]
+    fn verify_flags_tampered_spins() {
+        let mut wrong_mint = spin(&["a", "b"], None, 0);
+        wrong_mint.selected_mint = if wrong_mint.selected_mint == "a" { "b" } else { "a" }.to_string();
+        let verification = wrong_mint.verify();
+        assert!(verification.server_seed_matches_hash);
+        assert!(!verification.verified);
+
+        let mut wrong_seed = spin(&["a", "b"], None, 0);
+        wrong_seed.server_seed_hash = utils::hash_token("another-seed");
+        let verification = wrong_seed.verify();
+        assert!(!verification.server_seed_matches_hash);
+        assert!(!verification.verified);
+    }
+}
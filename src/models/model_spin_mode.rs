@@ -0,0 +1,227 @@
+use serde::{Deserialize, Serialize};
+
+use super::model_token::Token;
+
+/// Non uniform weights are normalized so the heaviest candidate gets this many slots.
+const WEIGHT_SCALE: f64 = 1_000_000.0;
+/// Price changes are clamped here before inverting, so a token that lost everything
+/// doesn't end up with an unbounded contrarian weight.
+const MIN_PRICE_CHANGE_PERCENT: f64 = -99.0;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum SpinMode {
+    #[default]
+    Uniform,
+    VolumeWeighted,
+    Momentum,
+    Contrarian,
+}
+
+impl SpinMode {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            SpinMode::Uniform => "uniform",
+            SpinMode::VolumeWeighted => "volume_weighted",
+            SpinMode::Momentum => "momentum",
+            SpinMode::Contrarian => "contrarian",
+        }
+    }
+
+    /// Relative weight of a token, before normalization.
+    /// - uniform: every token weighs the same
+    /// - volume_weighted: 24h volume in USD
+    /// - momentum: 1 + 24h change, so +50% weighs 1.5 and -100% weighs nothing
+    /// - contrarian: the inverse of momentum, so -50% weighs 2 and +100% weighs 0.5
+    pub fn weight(&self, token: &Token) -> f64 {
+        match self {
+            SpinMode::Uniform => 1.0,
+            SpinMode::VolumeWeighted => token.volume_24h_usd.max(0.0),
+            SpinMode::Momentum => (1.0 + token.price_change_24h_percent / 100.0).max(0.0),
+            SpinMode::Contrarian => 1.0 / (1.0 + token.price_change_24h_percent.max(MIN_PRICE_CHANGE_PERCENT) / 100.0),
+        }
+    }
+
+    /// Integer weights used by the spin, in the order of `tokens`.
+    /// Every token keeps at least one slot so it stays reachable, and uniform spins
+    /// use one slot per token.
+    pub fn weights(&self, tokens: &[Token]) -> Vec<u64> {
+        if *self == SpinMode::Uniform {
+            return vec![1; tokens.len()]
+        }
+
+        let raw_weights: Vec<f64> = tokens.iter()
+            .map(|token| self.weight(token))
+            .map(|weight| if weight.is_finite() { weight } else { 0.0 })
+            .collect();
+
+        let max_weight = raw_weights.iter().cloned().fold(0.0, f64::max);
+
+        if max_weight <= 0.0 {
+            return vec![1; tokens.len()]
+        }
+
+        raw_weights.into_iter()
+            .map(|weight| ((weight / max_weight) * WEIGHT_SCALE).round().max(1.0) as u64)
+            .collect()
+    }
+}
+
+#[derive(Debug, Deserialize, Default)]
+pub struct SpinModeParams {
+    #[serde(default)]
+    pub mode: SpinMode
+}
+
+#[derive(Debug, Serialize)]
+pub struct TokenOdds {
+    pub mint_pubkey: String,
+    pub symbol: String,
+    pub weight: u64,
+    pub probability: f64
+}
+
+#[derive(Debug, Serialize)]
+pub struct SpinOdds {
+    pub mode: SpinMode,
+    pub odds: Vec<TokenOdds>
+}
+
+impl SpinOdds {
+    pub fn new(mode: SpinMode, tokens: &[Token]) -> Self {
+        let weights = mode.weights(tokens);
+        let total_weight: u64 = weights.iter().sum();
+
+        let odds = tokens.iter()
+            .zip(weights)
+            .map(|(token, weight)| TokenOdds {
+                mint_pubkey: token.mint_pubkey.clone(),
+                symbol: token.symbol.clone(),
+                weight,
+                probability: weight as f64 / total_weight as f64
+            })
+            .collect();
+
+        SpinOdds { mode, odds }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn token(mint_pubkey: &str, volume_24h_usd: f64, price_change_24h_percent: f64) -> Token {
+        Token {
+            mint_pubkey: mint_pubkey.to_string(),
+            symbol: mint_pubkey.to_uppercase(),
+            name: mint_pubkey.to_string(),
+            logo_url: String::new(),
+            price_change_24h_percent,
+            volume_24h_usd,
+            discord_url: None,
+            twitter_url: None,
+            website_url: None,
+            telegram_url: None,
+            decimals: 6,
+            is_active: true,
+            created_at: chrono::Utc::now(),
+            risk_score: None
+        }
+    }
+
+    fn probabilities(mode: SpinMode, tokens: &[Token]) -> Vec<f64> {
+        SpinOdds::new(mode, tokens).odds.into_iter()
+            .map(|odds| odds.probability)
+            .collect()
+    }
+
+    fn assert_close(actual: f64, expected: f64) {
+        assert!((actual - expected).abs() < 1e-6, "expected {expected}, got {actual}");
+    }
+
+    #[test]
+    fn uniform_gives_every_token_one_slot() {
+        let tokens = vec![token("a", 10.0, 50.0), token("b", 1_000.0, -20.0), token("c", 0.0, 0.0)];
+
+        assert_eq!(SpinMode::Uniform.weights(&tokens), vec![1, 1, 1]);
+
+        for probability in probabilities(SpinMode::Uniform, &tokens) {
+            assert_close(probability, 1.0 / 3.0);
+        }
+    }
+
+    #[test]
+    fn volume_weighted_is_proportional_to_volume() {
+        let tokens = vec![token("a", 100.0, 0.0), token("b", 300.0, 0.0)];
+
+        let probabilities = probabilities(SpinMode::VolumeWeighted, &tokens);
+
+        assert_close(probabilities[0], 0.25);
+        assert_close(probabilities[1], 0.75);
+    }
+
+    #[test]
+    fn volume_weighted_keeps_zero_volume_tokens_reachable() {
+        let tokens = vec![token("a", 0.0, 0.0), token("b", 1_000_000.0, 0.0)];
+
+        assert_eq!(SpinMode::VolumeWeighted.weights(&tokens), vec![1, 1_000_000]);
+    }
+
+    #[test]
+    fn volume_weighted_falls_back_to_uniform_without_volume() {
+        let tokens = vec![token("a", 0.0, 0.0), token("b", -5.0, 0.0)];
+
+        assert_eq!(SpinMode::VolumeWeighted.weights(&tokens), vec![1, 1]);
+    }
+
+    #[test]
+    fn momentum_favors_gainers() {
+        let tokens = vec![token("a", 0.0, 50.0), token("b", 0.0, -50.0), token("c", 0.0, 0.0)];
+
+        let probabilities = probabilities(SpinMode::Momentum, &tokens);
+
+        // weights 1.5, 0.5 and 1.0
+        assert_close(probabilities[0], 0.5);
+        assert_close(probabilities[1], 1.0 / 6.0);
+        assert_close(probabilities[2], 1.0 / 3.0);
+    }
+
+    #[test]
+    fn momentum_floors_collapsed_tokens() {
+        let tokens = vec![token("a", 0.0, -100.0), token("b", 0.0, -250.0), token("c", 0.0, 0.0)];
+
+        assert_eq!(SpinMode::Momentum.weights(&tokens), vec![1, 1, 1_000_000]);
+    }
+
+    #[test]
+    fn contrarian_favors_losers() {
+        let tokens = vec![token("a", 0.0, 100.0), token("b", 0.0, -50.0), token("c", 0.0, 0.0)];
+
+        let probabilities = probabilities(SpinMode::Contrarian, &tokens);
+
+        // weights 0.5, 2.0 and 1.0
+        assert_close(probabilities[0], 0.5 / 3.5);
+        assert_close(probabilities[1], 2.0 / 3.5);
+        assert_close(probabilities[2], 1.0 / 3.5);
+    }
+
+    #[test]
+    fn contrarian_caps_weight_of_collapsed_tokens() {
+        let tokens = vec![token("a", 0.0, -100.0), token("b", 0.0, -99.0)];
+
+        let weights = SpinMode::Contrarian.weights(&tokens);
+
+        assert_eq!(weights[0], weights[1]);
+    }
+
+    #[test]
+    fn probabilities_sum_to_one() {
+        let tokens = vec![token("a", 12.5, 3.0), token("b", 9_000.0, -70.0), token("c", 0.0, 400.0), token("d", 42.0, -100.0)];
+
+        for mode in [SpinMode::Uniform, SpinMode::VolumeWeighted, SpinMode::Momentum, SpinMode::Contrarian] {
+            let total: f64 = probabilities(mode, &tokens).iter().sum();
+
+            assert_close(total, 1.0);
+        }
+    }
+}
@@ -0,0 +1,275 @@
+use std::time::Duration;
+
+use chrono::{DateTime, NaiveDate, Utc};
+use serde::Serialize;
+use sqlx::PgConnection;
+use crate::{errors::api_errors::{ApiError, Result}, AppState};
+
+/// Limits applied to every wallet, read from secrets at startup.
+#[derive(Debug, Clone, Copy)]
+pub struct SpinQuotaConfig {
+    pub spins_per_day: i32,
+    pub cooldown: Duration
+}
+
+/// Spins a wallet made on `quota_day` (UTC). A row from an earlier day counts as zero spins.
+#[derive(Debug, sqlx::FromRow, Clone)]
+pub struct SpinQuota {
+    pub quota_day: NaiveDate,
+    pub spins_today: i32,
+    pub last_spin_at: Option<DateTime<Utc>>,
+    /// Consecutive UTC days, up to `quota_day`, with at least one spin.
+    pub streak_days: i32
+}
+
+#[derive(Debug, Serialize)]
+pub struct SpinQuotaStatus {
+    pub user_pubkey: String,
+    pub spins_per_day: i32,
+    pub spins_used: i32,
+    pub spins_remaining: i32,
+    pub cooldown_secs: u64,
+    /// Earliest time the next spin is accepted.
+    pub next_spin_at: DateTime<Utc>
+}
+
+impl SpinQuotaConfig {
+    fn spins_used(&self, quota: Option<&SpinQuota>, now: DateTime<Utc>) -> i32 {
+        match quota {
+            Some(quota) if quota.quota_day == now.date_naive() => quota.spins_today,
+            _ => 0
+        }
+    }
+
+    /// Earliest time the next spin is accepted, `now` if it is accepted right away.
+    fn next_spin_at(&self, quota: Option<&SpinQuota>, now: DateTime<Utc>) -> DateTime<Utc> {
+        if self.spins_used(quota, now) >= self.spins_per_day {
+            return next_utc_midnight(now)
+        }
+
+        match quota.and_then(|quota| quota.last_spin_at) {
+            Some(last_spin_at) if last_spin_at + self.cooldown > now => last_spin_at + self.cooldown,
+            _ => now
+        }
+    }
+
+    /// Rejects the spin when the daily quota is used up or the cooldown hasn't elapsed.
+    pub fn check(&self, quota: Option<&SpinQuota>, now: DateTime<Utc>) -> Result<()> {
+        let next_spin_at = self.next_spin_at(quota, now);
+
+        if next_spin_at <= now {
+            return Ok(())
+        }
+
+        // rounded up so clients retrying at retry_after are never early
+        let retry_after_secs = (next_spin_at - now).num_milliseconds().max(0) as u64 / 1000 + 1;
+
+        if self.spins_used(quota, now) >= self.spins_per_day {
+            Err(ApiError::SpinQuotaExceeded { retry_after_secs })
+        } else {
+            Err(ApiError::SpinCooldownActive { retry_after_secs })
+        }
+    }
+
+    pub fn status(&self, user_pubkey: &str, quota: Option<&SpinQuota>, now: DateTime<Utc>) -> SpinQuotaStatus {
+        let spins_used = self.spins_used(quota, now);
+
+        SpinQuotaStatus {
+            user_pubkey: user_pubkey.to_string(),
+            spins_per_day: self.spins_per_day,
+            spins_used,
+            spins_remaining: (self.spins_per_day - spins_used).max(0),
+            cooldown_secs: self.cooldown.as_secs(),
+            next_spin_at: self.next_spin_at(quota, now)
+        }
+    }
+}
+
+impl SpinQuota {
+    /// Streak reached by a spin at `now`, `None` unless it is the first spin of the day.
+    fn streak_after_spin(&self, now: DateTime<Utc>) -> Option<i32> {
+        let today = now.date_naive();
+
+        if self.quota_day == today && self.spins_today > 0 {
+            return None
+        }
+
+        let spun_yesterday = self.spins_today > 0 && today.pred_opt() == Some(self.quota_day);
+
+        Some(if spun_yesterday { self.streak_days + 1 } else { 1 })
+    }
+}
+
+fn next_utc_midnight(now: DateTime<Utc>) -> DateTime<Utc> {
+    now.date_naive()
+        .succ_opt()
+        .expect("date is not the last representable day")
+        .and_hms_opt(0, 0, 0)
+        .expect("midnight is a valid time")
+        .and_utc()
+}
+
+// CRUD implementation for SpinQuota
+
+impl SpinQuota {
+    /// Counts a spin against the wallet's quota, inside the caller's spin transaction.
+    /// The row is locked so concurrent spins from the same wallet are checked one at a time.
+    /// The outer error is a database failure, the inner one a rejected spin.
+    /// Returns the wallet's new daily streak when this is its first spin of the day.
+    pub async fn consume(
+        user_pubkey: &str,
+        config: SpinQuotaConfig,
+        conn: &mut PgConnection
+    ) -> sqlx::Result<Result<Option<i32>>> {
+        println!("->> {:<12} - consume", "CONTROLLER");
+
+        let now = Utc::now();
+        let today = now.date_naive();
+
+        sqlx::query(
+                "INSERT INTO spin_quotas (user_pubkey, quota_day, spins_today) VALUES ($1, $2, 0) ON CONFLICT (user_pubkey) DO NOTHING"
+            )
+            .bind(user_pubkey)
+            .bind(today)
+            .execute(&mut *conn)
+            .await?;
+
+        let quota = sqlx::query_as::<_, SpinQuota>(
+                "SELECT quota_day, spins_today, last_spin_at, streak_days FROM spin_quotas WHERE user_pubkey = $1 FOR UPDATE"
+            )
+            .bind(user_pubkey)
+            .fetch_one(&mut *conn)
+            .await?;
+
+        if let Err(e) = config.check(Some(&quota), now) {
+            return Ok(Err(e))
+        }
+
+        let new_streak = quota.streak_after_spin(now);
+
+        sqlx::query(
+                "UPDATE spin_quotas SET quota_day = $2, spins_today = $3, last_spin_at = $4, streak_days = $5 WHERE user_pubkey = $1"
+            )
+            .bind(user_pubkey)
+            .bind(today)
+            .bind(config.spins_used(Some(&quota), now) + 1)
+            .bind(now)
+            .bind(new_streak.unwrap_or(quota.streak_days))
+            .execute(&mut *conn)
+            .await?;
+
+        Ok(Ok(new_streak))
+    }
+
+    pub async fn get_spin_quota(
+        user_pubkey: &str,
+        state: AppState
+    ) -> Result<Option<Self>> {
+        println!("->> {:<12} - get_spin_quota", "CONTROLLER");
+
+        let result = sqlx::query_as::<_, SpinQuota>(
+                "SELECT quota_day, spins_today, last_spin_at, streak_days FROM spin_quotas WHERE user_pubkey = $1"
+            )
+            .bind(user_pubkey)
+            .fetch_optional(&state.db)
+            .await;
+
+        match result {
+            Ok(quota) => Ok(quota),
+            Err(e) => {
+                println!("Error fetching spin quota for user: {}. Error: {}", user_pubkey, e);
+                Err(ApiError::SpinGetFail)
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::TimeZone;
+
+    use super::*;
+
+    const CONFIG: SpinQuotaConfig = SpinQuotaConfig { spins_per_day: 3, cooldown: Duration::from_secs(30) };
+
+    fn at(hour: u32, minute: u32, second: u32) -> DateTime<Utc> {
+        Utc.with_ymd_and_hms(2024, 5, 10, hour, minute, second).unwrap()
+    }
+
+    fn quota(quota_day: NaiveDate, spins_today: i32, last_spin_at: DateTime<Utc>, streak_days: i32) -> SpinQuota {
+        SpinQuota { quota_day, spins_today, last_spin_at: Some(last_spin_at), streak_days }
+    }
+
+    fn day(day: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(2024, 5, day).unwrap()
+    }
+
+    #[test]
+    fn first_spin_is_accepted() {
+        assert!(CONFIG.check(None, at(12, 0, 0)).is_ok());
+
+        let status = CONFIG.status("user", None, at(12, 0, 0));
+        assert_eq!((status.spins_used, status.spins_remaining), (0, 3));
+        assert_eq!(status.next_spin_at, at(12, 0, 0));
+    }
+
+    #[test]
+    fn cooldown_rejects_until_it_elapses() {
+        let quota = quota(day(10), 1, at(12, 0, 0), 1);
+
+        let rejected = CONFIG.check(Some(&quota), at(12, 0, 10));
+        assert!(matches!(rejected, Err(ApiError::SpinCooldownActive { retry_after_secs: 21 })));
+        assert_eq!(CONFIG.status("user", Some(&quota), at(12, 0, 10)).next_spin_at, at(12, 0, 30));
+
+        assert!(CONFIG.check(Some(&quota), at(12, 0, 30)).is_ok());
+    }
+
+    #[test]
+    fn used_up_quota_waits_for_utc_midnight() {
+        let quota = quota(day(10), 3, at(23, 0, 0), 1);
+
+        let rejected = CONFIG.check(Some(&quota), at(23, 59, 0));
+        assert!(matches!(rejected, Err(ApiError::SpinQuotaExceeded { retry_after_secs: 61 })));
+
+        let status = CONFIG.status("user", Some(&quota), at(23, 59, 0));
+        assert_eq!((status.spins_used, status.spins_remaining), (3, 0));
+        assert_eq!(status.next_spin_at, Utc.with_ymd_and_hms(2024, 5, 11, 0, 0, 0).unwrap());
+    }
+
+    #[test]
+    fn quota_rolls_over_at_the_day_boundary() {
+        let quota = quota(day(10), 3, at(23, 59, 50), 1);
+        let after_midnight = Utc.with_ymd_and_hms(2024, 5, 11, 0, 0, 5).unwrap();
+
+        // the day's spins reset, the cooldown from the last spin still applies
+        let status = CONFIG.status("user", Some(&quota), after_midnight);
+        assert_eq!((status.spins_used, status.spins_remaining), (0, 3));
+        assert!(matches!(CONFIG.check(Some(&quota), after_midnight), Err(ApiError::SpinCooldownActive { .. })));
+
+        assert!(CONFIG.check(Some(&quota), after_midnight + Duration::from_secs(30)).is_ok());
+    }
+
+    #[test]
+    fn streak_grows_on_consecutive_days() {
+        let yesterday = quota(day(9), 2, at(12, 0, 0), 4);
+
+        assert_eq!(yesterday.streak_after_spin(at(8, 0, 0)), Some(5));
+    }
+
+    #[test]
+    fn streak_only_counts_the_first_spin_of_the_day() {
+        let today = quota(day(10), 1, at(8, 0, 0), 5);
+
+        assert_eq!(today.streak_after_spin(at(9, 0, 0)), None);
+    }
+
+    #[test]
+    fn streak_resets_after_a_skipped_day() {
+        let two_days_ago = quota(day(8), 2, at(12, 0, 0), 4);
+        assert_eq!(two_days_ago.streak_after_spin(at(8, 0, 0)), Some(1));
+
+        // a fresh row from yesterday without spins doesn't keep the streak alive
+        let unused = quota(day(9), 0, at(12, 0, 0), 4);
+        assert_eq!(unused.streak_after_spin(at(8, 0, 0)), Some(1));
+    }
+}
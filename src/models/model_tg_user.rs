@@ -0,0 +1,376 @@
+use serde::{Deserialize, Serialize};
+use crate::{errors::api_errors::{ApiError, Result}, utils, AppState};
+
+use super::{model_points::{PointsEntryKind, PointsLedger, SYSTEM_SPINS_ACCOUNT}, model_spin::{Spin, SpinForCreate, SpinResult}, model_spin_mode::SpinMode, model_token::Token};
+
+/// Points a spin costs.
+pub const SPIN_COST_POINTS: i64 = 1;
+/// Points the owner of an access code earns each time it is redeemed.
+pub const REFERRAL_BONUS_POINTS: i64 = 1;
+/// Points granted on registration.
+pub const SIGNUP_BONUS_POINTS: i64 = 3;
+
+const TG_USER_SELECT: &str = r#"SELECT tgbotusers.user_id, tgbotusers.access_code, COALESCE(points_balances.balance, 0) AS points,
+    tgbotusers.referrals, tgbotusers.referred_by
+    FROM tgbotusers
+    LEFT JOIN points_balances ON points_balances.account = 'tg:' || tgbotusers.user_id"#;
+
+#[derive(Debug, sqlx::FromRow, Serialize)]
+pub struct TgUser {
+    pub user_id: i64,
+    /// Invite code of this user, redeemed by the users they refer.
+    pub access_code: Option<String>,
+    /// Balance derived from the points ledger.
+    pub points: i64,
+    pub referrals: i32,
+    pub referred_by: Option<i64>
+}
+
+#[derive(Debug, Deserialize)]
+pub struct TgUserForCreate {
+    pub user_id: i64
+}
+
+#[derive(Debug, Deserialize)]
+pub struct AccessCodeRedeem {
+    pub access_code: String
+}
+
+#[derive(Debug, Deserialize)]
+pub struct TgSpinRequest {
+    pub seed_id: uuid::Uuid,
+    pub client_seed: String,
+    pub nonce: i64
+}
+
+#[derive(Debug, Serialize)]
+pub struct TgSpinReceipt {
+    pub points_remaining: i64,
+    #[serde(flatten)]
+    pub spin: SpinResult
+}
+
+impl TgUser {
+    /// Telegram users spin and hold points under `tg:<user_id>` in place of a wallet pubkey.
+    pub fn account(user_id: i64) -> String {
+        format!("tg:{}", user_id)
+    }
+}
+
+impl TgSpinRequest {
+    pub fn into_spin(self, user_id: i64) -> SpinForCreate {
+        SpinForCreate {
+            user_pubkey: TgUser::account(user_id),
+            seed_id: self.seed_id,
+            client_seed: self.client_seed,
+            nonce: self.nonce
+        }
+    }
+}
+
+// CRUD implementation for TgUser
+
+impl TgUser {
+    pub async fn create_tg_user(
+        user: TgUserForCreate,
+        state: AppState
+    ) -> Result<Self> {
+        println!("->> {:<12} - create_tg_user", "CONTROLLER");
+
+        let result: sqlx::Result<Result<TgUser>> = async {
+            let mut tx = state.db.begin().await?;
+
+            let mut attempts = 0;
+
+            // DO NOTHING covers both unique keys, a skipped insert is either a registered user or a taken code
+            loop {
+                let inserted = sqlx::query(
+                        "INSERT INTO tgbotusers (user_id, access_code) VALUES ($1, $2) ON CONFLICT DO NOTHING"
+                    )
+                    .bind(user.user_id)
+                    .bind(utils::generate_access_code())
+                    .execute(&mut *tx)
+                    .await?;
+
+                if inserted.rows_affected() == 1 {
+                    break
+                }
+
+                let registered = sqlx::query_scalar::<_, bool>(
+                        "SELECT EXISTS (SELECT 1 FROM tgbotusers WHERE user_id = $1)"
+                    )
+                    .bind(user.user_id)
+                    .fetch_one(&mut *tx)
+                    .await?;
+
+                if registered {
+                    return Ok(Err(ApiError::TgUserAlreadyRegistered))
+                }
+
+                attempts += 1;
+
+                if attempts == utils::ACCESS_CODE_ATTEMPTS {
+                    println!("Error creating telegram user: {}. No free access code after {} attempts", user.user_id, attempts);
+                    return Ok(Err(ApiError::TgUserCreateFail))
+                }
+            }
+
+            PointsLedger::reward(
+                &Self::account(user.user_id),
+                SIGNUP_BONUS_POINTS,
+                PointsEntryKind::SignupBonus,
+                None,
+                &mut tx
+            ).await?;
+
+            let created_user = sqlx::query_as::<_, TgUser>(
+                    &format!("{} WHERE tgbotusers.user_id = $1", TG_USER_SELECT)
+                )
+                .bind(user.user_id)
+                .fetch_one(&mut *tx)
+                .await?;
+
+            tx.commit().await?;
+
+            Ok(Ok(created_user))
+        }.await;
+
+        match result {
+            Ok(user) => user,
+            Err(e) => {
+                println!("Error creating telegram user: {}. Error: {}", user.user_id, e);
+                Err(ApiError::TgUserCreateFail)
+            }
+        }
+    }
+
+    pub async fn get_tg_user(
+        user_id: i64,
+        state: AppState
+    ) -> Result<Option<Self>> {
+        println!("->> {:<12} - get_tg_user", "CONTROLLER");
+
+        let result = sqlx::query_as::<_, TgUser>(
+                &format!("{} WHERE tgbotusers.user_id = $1", TG_USER_SELECT)
+            )
+            .bind(user_id)
+            .fetch_optional(&state.db)
+            .await;
+
+        match result {
+            Ok(user) => Ok(user),
+            Err(e) => {
+                println!("Error fetching telegram user: {}. Error: {}", user_id, e);
+                Err(ApiError::TgUserGetFail)
+            }
+        }
+    }
+
+    pub async fn get_referrals(
+        user_id: i64,
+        state: AppState
+    ) -> Result<Vec<Self>> {
+        println!("->> {:<12} - get_referrals", "CONTROLLER");
+
+        let result = sqlx::query_as::<_, TgUser>(
+                &format!("{} WHERE tgbotusers.referred_by = $1 ORDER BY tgbotusers.user_id", TG_USER_SELECT)
+            )
+            .bind(user_id)
+            .fetch_all(&state.db)
+            .await;
+
+        match result {
+            Ok(referrals) => Ok(referrals),
+            Err(e) => {
+                println!("Error fetching referrals of telegram user: {}. Error: {}", user_id, e);
+                Err(ApiError::TgUserGetFail)
+            }
+        }
+    }
+
+    /// Links the user to the owner of `access_code` and credits the owner.
+    /// A user can be referred once, and not by their own code or by anyone down their own referral chain.
+    pub async fn redeem_access_code(
+        user_id: i64,
+        access_code: &str,
+        state: AppState
+    ) -> Result<Self> {
+        println!("->> {:<12} - redeem_access_code", "CONTROLLER");
+
+        let result: sqlx::Result<Result<TgUser>> = async {
+            let mut tx = state.db.begin().await?;
+
+            let user = sqlx::query_as::<_, TgUser>(
+                    &format!("{} WHERE tgbotusers.user_id = $1 FOR UPDATE OF tgbotusers", TG_USER_SELECT)
+                )
+                .bind(user_id)
+                .fetch_optional(&mut *tx)
+                .await?;
+
+            let Some(user) = user else {
+                return Ok(Err(ApiError::TgUserNotFound))
+            };
+
+            if user.referred_by.is_some() {
+                return Ok(Err(ApiError::TgReferralNotAllowed))
+            }
+
+            let referrer = sqlx::query_as::<_, TgUser>(
+                    &format!("{} WHERE tgbotusers.access_code = $1 FOR UPDATE OF tgbotusers", TG_USER_SELECT)
+                )
+                .bind(access_code)
+                .fetch_optional(&mut *tx)
+                .await?;
+
+            let Some(referrer) = referrer else {
+                return Ok(Err(ApiError::TgAccessCodeInvalid))
+            };
+
+            if referrer.user_id == user.user_id {
+                return Ok(Err(ApiError::TgReferralNotAllowed))
+            }
+
+            // UNION rather than UNION ALL, so a cycle that slipped into the data still terminates
+            let is_circular = sqlx::query_scalar::<_, bool>(
+                    r#"WITH RECURSIVE referral_chain AS (
+                        SELECT user_id, referred_by FROM tgbotusers WHERE user_id = $1
+                        UNION
+                        SELECT tgbotusers.user_id, tgbotusers.referred_by FROM tgbotusers
+                        JOIN referral_chain ON tgbotusers.user_id = referral_chain.referred_by
+                    )
+                    SELECT EXISTS (SELECT 1 FROM referral_chain WHERE user_id = $2)"#
+                )
+                .bind(referrer.user_id)
+                .bind(user.user_id)
+                .fetch_one(&mut *tx)
+                .await?;
+
+            if is_circular {
+                return Ok(Err(ApiError::TgReferralNotAllowed))
+            }
+
+            sqlx::query("UPDATE tgbotusers SET referrals = referrals + 1 WHERE user_id = $1")
+                .bind(referrer.user_id)
+                .execute(&mut *tx)
+                .await?;
+
+            sqlx::query("UPDATE tgbotusers SET referred_by = $2 WHERE user_id = $1")
+                .bind(user.user_id)
+                .bind(referrer.user_id)
+                .execute(&mut *tx)
+                .await?;
+
+            PointsLedger::reward(
+                &Self::account(referrer.user_id),
+                REFERRAL_BONUS_POINTS,
+                PointsEntryKind::Referral,
+                Some(&Self::account(user.user_id)),
+                &mut tx
+            ).await?;
+
+            let user = sqlx::query_as::<_, TgUser>(
+                    &format!("{} WHERE tgbotusers.user_id = $1", TG_USER_SELECT)
+                )
+                .bind(user.user_id)
+                .fetch_one(&mut *tx)
+                .await?;
+
+            tx.commit().await?;
+
+            Ok(Ok(user))
+        }.await;
+
+        match result {
+            Ok(user) => user,
+            Err(e) => {
+                println!("Error redeeming access code for telegram user: {}. Error: {}", user_id, e);
+                Err(ApiError::TgUserUpdateFail)
+            }
+        }
+    }
+
+    /// Charges `SPIN_COST_POINTS` and spins in one transaction, so a rejected spin costs nothing.
+    pub async fn spin_with_points(
+        user_id: i64,
+        spin: SpinForCreate,
+        mode: SpinMode,
+        candidates: Vec<Token>,
+        state: AppState
+    ) -> Result<TgSpinReceipt> {
+        println!("->> {:<12} - spin_with_points", "CONTROLLER");
+
+        let result: sqlx::Result<Result<TgSpinReceipt>> = async {
+            let mut tx = state.db.begin().await?;
+
+            let account = Self::account(user_id);
+
+            // the user row serializes concurrent spins, since the balance itself is an aggregate
+            sqlx::query("SELECT user_id FROM tgbotusers WHERE user_id = $1 FOR UPDATE")
+                .bind(user_id)
+                .fetch_one(&mut *tx)
+                .await?;
+
+            let balance = PointsLedger::get_balance(&account, &mut tx).await?;
+
+            if balance < SPIN_COST_POINTS {
+                return Ok(Err(ApiError::TgInsufficientPoints))
+            }
+
+            let spin_result = match Spin::insert_spin(spin, mode, candidates, state.spin_quota, &mut tx).await? {
+                Ok(spin_result) => spin_result,
+                Err(e) => return Ok(Err(e))
+            };
+
+            PointsLedger::transfer(
+                &account,
+                SYSTEM_SPINS_ACCOUNT,
+                SPIN_COST_POINTS,
+                PointsEntryKind::Spin,
+                Some(&spin_result.spin.id.to_string()),
+                &mut tx
+            ).await?;
+
+            let points_remaining = PointsLedger::get_balance(&account, &mut tx).await?;
+
+            tx.commit().await?;
+
+            Ok(Ok(TgSpinReceipt { points_remaining, spin: spin_result }))
+        }.await;
+
+        match result {
+            Ok(receipt) => receipt,
+            Err(e) => {
+                println!("Error spinning for telegram user: {}. Error: {}", user_id, e);
+                Err(ApiError::SpinCreateFail)
+            }
+        }
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::TestContext;
+
+    #[tokio::test]
+    #[ignore = "needs DATABASE_URL"]
+    async fn rejects_circular_referrals() {
+        let context = TestContext::start().await;
+        let state = context.state.clone();
+
+        let alice = TgUser::create_tg_user(TgUserForCreate { user_id: 1 }, state.clone()).await.unwrap();
+        let bob = TgUser::create_tg_user(TgUserForCreate { user_id: 2 }, state.clone()).await.unwrap();
+
+        let bob = TgUser::redeem_access_code(bob.user_id, alice.access_code.as_deref().unwrap(), state.clone()).await.unwrap();
+        assert_eq!(bob.referred_by, Some(alice.user_id));
+
+        let circular = TgUser::redeem_access_code(alice.user_id, bob.access_code.as_deref().unwrap(), state.clone()).await;
+        assert!(matches!(circular, Err(ApiError::TgReferralNotAllowed)));
+
+        let duplicate = TgUser::create_tg_user(TgUserForCreate { user_id: 1 }, state.clone()).await;
+        assert!(matches!(duplicate, Err(ApiError::TgUserAlreadyRegistered)));
+
+        context.cleanup().await;
+    }
+}
@@ -0,0 +1,117 @@
+use std::collections::HashSet;
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+use crate::errors::api_errors::{ApiError, Result};
+
+const MAX_REASON_LENGTH: usize = 500;
+
+#[derive(Debug, Clone, Copy, Deserialize, Serialize, PartialEq, Eq)]
+pub enum TokenOverrideKind {
+    /// Never selected.
+    #[serde(rename = "exclude")]
+    Exclude,
+    /// Selected when Birdeye lists it, even below the market cap, liquidity and trade thresholds.
+    #[serde(rename = "force_include")]
+    ForceInclude,
+    /// Kept in every selection, listed or not. The token must already be known.
+    #[serde(rename = "pin")]
+    Pin,
+}
+
+impl TokenOverrideKind {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            TokenOverrideKind::Exclude => "exclude",
+            TokenOverrideKind::ForceInclude => "force_include",
+            TokenOverrideKind::Pin => "pin",
+        }
+    }
+}
+
+#[derive(Debug, sqlx::FromRow, Serialize, Clone)]
+pub struct TokenOverride {
+    pub id: Uuid,
+    pub mint_pubkey: String,
+    pub kind: String,
+    pub reason: String,
+    /// Ignored by the selector from then on. `None` never expires.
+    pub expires_at: Option<DateTime<Utc>>,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>
+}
+
+#[derive(Debug, Deserialize)]
+pub struct TokenOverrideForCreate {
+    pub mint_pubkey: String,
+    pub kind: TokenOverrideKind,
+    pub reason: String,
+    pub expires_at: Option<DateTime<Utc>>
+}
+
+#[derive(Debug, Deserialize)]
+pub struct TokenOverrideForUpdate {
+    pub kind: TokenOverrideKind,
+    pub reason: String,
+    pub expires_at: Option<DateTime<Utc>>
+}
+
+/// Active overrides grouped the way the coin selector applies them.
+#[derive(Debug, Default)]
+pub struct SelectionOverrides {
+    pub excluded: HashSet<String>,
+    pub forced: HashSet<String>,
+    pub pinned: Vec<String>
+}
+
+impl SelectionOverrides {
+    pub fn from_overrides(overrides: Vec<TokenOverride>) -> Self {
+        let mut selection_overrides = Self::default();
+
+        for token_override in overrides {
+            match token_override.kind.as_str() {
+                "exclude" => { selection_overrides.excluded.insert(token_override.mint_pubkey); },
+                "force_include" => { selection_overrides.forced.insert(token_override.mint_pubkey); },
+                "pin" => selection_overrides.pinned.push(token_override.mint_pubkey),
+                _ => {}
+            }
+        }
+
+        selection_overrides
+    }
+
+    /// Whether a listed token is left out of the threshold filters. Pins are added separately.
+    pub fn skips_listing(&self, mint_pubkey: &str) -> bool {
+        self.excluded.contains(mint_pubkey) || self.pinned.iter().any(|pinned| pinned == mint_pubkey)
+    }
+}
+
+fn validate_override(reason: &str, expires_at: Option<DateTime<Utc>>) -> Result<()> {
+    let reason = reason.trim();
+
+    if reason.is_empty() || reason.len() > MAX_REASON_LENGTH {
+        return Err(ApiError::TokenOverrideInvalid)
+    }
+
+    match expires_at {
+        Some(expires_at) if expires_at <= Utc::now() => Err(ApiError::TokenOverrideInvalid),
+        _ => Ok(())
+    }
+}
+
+impl TokenOverrideForCreate {
+    pub fn validate(&self) -> Result<()> {
+        if self.mint_pubkey.trim().is_empty() {
+            return Err(ApiError::TokenOverrideInvalid)
+        }
+
+        validate_override(&self.reason, self.expires_at)
+    }
+}
+
+impl TokenOverrideForUpdate {
+    pub fn validate(&self) -> Result<()> {
+        validate_override(&self.reason, self.expires_at)
+    }
+}
@@ -0,0 +1,220 @@
+use std::time::Duration;
+
+use chrono::{DateTime, TimeZone, Utc};
+use serde::{Deserialize, Serialize};
+use crate::errors::api_errors::{ApiError, Result};
+
+/// Upper bound on candles per response, so a tiny interval over a long range can't blow up.
+const MAX_CANDLES: i64 = 2_000;
+const DEFAULT_HISTORY_RANGE: Duration = Duration::from_secs(24 * 60 * 60);
+
+#[derive(Debug, sqlx::FromRow, Serialize, Clone)]
+pub struct TokenPriceSnapshot {
+    pub mint_pubkey: String,
+    pub price: f64,
+    pub volume_24h_usd: f64,
+    pub liquidity: f64,
+    pub market_cap: f64,
+    pub created_at: DateTime<Utc>
+}
+
+#[derive(Debug)]
+pub struct SnapshotForCreate {
+    pub mint_pubkey: String,
+    pub price: f64,
+    pub volume_24h_usd: f64,
+    pub liquidity: f64,
+    pub market_cap: f64
+}
+
+#[derive(Debug, Clone, Copy, Deserialize, Default)]
+pub enum HistoryInterval {
+    #[serde(rename = "5m")]
+    FiveMinutes,
+    #[serde(rename = "15m")]
+    FifteenMinutes,
+    #[default]
+    #[serde(rename = "1h")]
+    OneHour,
+    #[serde(rename = "4h")]
+    FourHours,
+    #[serde(rename = "1d")]
+    OneDay,
+}
+
+impl HistoryInterval {
+    pub fn seconds(&self) -> i64 {
+        match self {
+            HistoryInterval::FiveMinutes => 5 * 60,
+            HistoryInterval::FifteenMinutes => 15 * 60,
+            HistoryInterval::OneHour => 60 * 60,
+            HistoryInterval::FourHours => 4 * 60 * 60,
+            HistoryInterval::OneDay => 24 * 60 * 60,
+        }
+    }
+}
+
+#[derive(Debug, Deserialize)]
+pub struct HistoryParams {
+    pub from: Option<DateTime<Utc>>,
+    pub to: Option<DateTime<Utc>>,
+    #[serde(default)]
+    pub interval: HistoryInterval
+}
+
+impl HistoryParams {
+    /// Defaults to the last 24 hours and rejects empty or oversized ranges.
+    pub fn resolve_range(&self) -> Result<(DateTime<Utc>, DateTime<Utc>)> {
+        let to = self.to.unwrap_or_else(Utc::now);
+        let from = self.from.unwrap_or(to - DEFAULT_HISTORY_RANGE);
+
+        let range_seconds = (to - from).num_seconds();
+
+        if range_seconds <= 0 || range_seconds / self.interval.seconds() > MAX_CANDLES {
+            return Err(ApiError::TokenHistoryRangeInvalid)
+        }
+
+        Ok((from, to))
+    }
+}
+
+/// OHLC bucket built from the snapshots that fall inside it.
+/// Volume, liquidity and market cap are the last observed values in the bucket.
+#[derive(Debug, Serialize)]
+pub struct PriceCandle {
+    pub bucket_start: DateTime<Utc>,
+    pub open: f64,
+    pub high: f64,
+    pub low: f64,
+    pub close: f64,
+    pub volume_24h_usd: f64,
+    pub liquidity: f64,
+    pub market_cap: f64,
+    pub samples: usize
+}
+
+/// Groups snapshots ordered by `created_at` into fixed width buckets aligned to the unix epoch.
+/// Buckets without snapshots are omitted.
+pub fn build_candles(snapshots: &[TokenPriceSnapshot], interval: HistoryInterval) -> Vec<PriceCandle> {
+    let interval_seconds = interval.seconds();
+    let mut candles: Vec<PriceCandle> = Vec::new();
+
+    for snapshot in snapshots {
+        let timestamp = snapshot.created_at.timestamp();
+        let bucket_timestamp = timestamp - timestamp.rem_euclid(interval_seconds);
+        let bucket_start = Utc.timestamp_opt(bucket_timestamp, 0)
+            .single()
+            .expect("bucket start is a valid timestamp");
+
+        match candles.last_mut() {
+            Some(candle) if candle.bucket_start == bucket_start => {
+                candle.high = candle.high.max(snapshot.price);
+                candle.low = candle.low.min(snapshot.price);
+                candle.close = snapshot.price;
+                candle.volume_24h_usd = snapshot.volume_24h_usd;
+                candle.liquidity = snapshot.liquidity;
+                candle.market_cap = snapshot.market_cap;
+                candle.samples += 1;
+            },
+            _ => candles.push(PriceCandle {
+                bucket_start,
+                open: snapshot.price,
+                high: snapshot.price,
+                low: snapshot.price,
+                close: snapshot.price,
+                volume_24h_usd: snapshot.volume_24h_usd,
+                liquidity: snapshot.liquidity,
+                market_cap: snapshot.market_cap,
+                samples: 1
+            })
+        }
+    }
+
+    candles
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn at(timestamp: i64) -> DateTime<Utc> {
+        Utc.timestamp_opt(timestamp, 0).single().unwrap()
+    }
+
+    fn snapshot(timestamp: i64, price: f64, volume_24h_usd: f64) -> TokenPriceSnapshot {
+        TokenPriceSnapshot {
+            mint_pubkey: "mint".to_string(),
+            price,
+            volume_24h_usd,
+            liquidity: volume_24h_usd / 2.0,
+            market_cap: volume_24h_usd * 10.0,
+            created_at: at(timestamp)
+        }
+    }
+
+    fn history_params(from: Option<i64>, to: Option<i64>, interval: HistoryInterval) -> HistoryParams {
+        HistoryParams { from: from.map(at), to: to.map(at), interval }
+    }
+
+    #[test]
+    fn buckets_align_to_the_interval() {
+        let snapshots = [
+            snapshot(3_600, 1.0, 100.0),
+            snapshot(7_199, 2.0, 200.0),
+            snapshot(7_200, 3.0, 300.0),
+            snapshot(14_500, 4.0, 400.0)
+        ];
+
+        let candles = build_candles(&snapshots, HistoryInterval::OneHour);
+
+        let bucket_starts: Vec<DateTime<Utc>> = candles.iter().map(|candle| candle.bucket_start).collect();
+        assert_eq!(bucket_starts, vec![at(3_600), at(7_200), at(14_400)]);
+
+        let samples: Vec<usize> = candles.iter().map(|candle| candle.samples).collect();
+        assert_eq!(samples, vec![2, 1, 1]);
+    }
+
+    #[test]
+    fn candles_open_on_the_first_and_close_on_the_last_snapshot() {
+        let snapshots = [
+            snapshot(0, 2.0, 100.0),
+            snapshot(60, 5.0, 150.0),
+            snapshot(120, 1.0, 120.0),
+            snapshot(180, 3.0, 110.0)
+        ];
+
+        let candles = build_candles(&snapshots, HistoryInterval::FiveMinutes);
+
+        assert_eq!(candles.len(), 1);
+        let candle = &candles[0];
+        assert_eq!((candle.open, candle.high, candle.low, candle.close), (2.0, 5.0, 1.0, 3.0));
+        // volume is the last observation, not a sum of the samples
+        assert_eq!(candle.volume_24h_usd, 110.0);
+        assert_eq!(candle.liquidity, 55.0);
+        assert_eq!(candle.market_cap, 1_100.0);
+    }
+
+    #[test]
+    fn no_snapshots_build_no_candles() {
+        assert!(build_candles(&[], HistoryInterval::OneDay).is_empty());
+    }
+
+    #[test]
+    fn resolves_explicit_and_default_ranges() {
+        let params = history_params(Some(0), Some(86_400), HistoryInterval::OneHour);
+        assert_eq!(params.resolve_range().unwrap(), (at(0), at(86_400)));
+
+        let params = history_params(None, Some(100_000), HistoryInterval::OneHour);
+        assert_eq!(params.resolve_range().unwrap(), (at(100_000 - 86_400), at(100_000)));
+    }
+
+    #[test]
+    fn rejects_reversed_empty_and_oversized_ranges() {
+        assert!(history_params(Some(86_400), Some(0), HistoryInterval::OneHour).resolve_range().is_err());
+        assert!(history_params(Some(3_600), Some(3_600), HistoryInterval::OneHour).resolve_range().is_err());
+
+        let max_range = MAX_CANDLES * HistoryInterval::FiveMinutes.seconds();
+        assert!(history_params(Some(0), Some(max_range), HistoryInterval::FiveMinutes).resolve_range().is_ok());
+        assert!(history_params(Some(0), Some(max_range + HistoryInterval::FiveMinutes.seconds()), HistoryInterval::FiveMinutes).resolve_range().is_err());
+    }
+}
@@ -0,0 +1,353 @@
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+use crate::{errors::api_errors::{ApiError, Result}, services::price_source::VsToken, AppState};
+
+use super::{model_points::PointsLedger, model_position::{Position, PositionFill, PositionForCreate, PositionStatus}, model_referral::ReferralCode, model_token::Token};
+
+pub const MAX_SLIPPAGE_BPS: u32 = 10_000;
+
+/// Remaining quantities below this are treated as a fully closed position.
+const QUANTITY_EPSILON: f64 = 1e-9;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TradeSide {
+    Buy,
+    Sell,
+}
+
+impl TradeSide {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            TradeSide::Buy => "buy",
+            TradeSide::Sell => "sell",
+        }
+    }
+}
+
+#[derive(Debug, sqlx::FromRow, Serialize, Clone)]
+pub struct Trade {
+    pub id: Uuid,
+    pub position_id: Uuid,
+    pub user_pubkey: String,
+    pub token_pubkey: String,
+    pub vs_token_symbol: String,
+    pub side: String,
+    pub quantity: f64,
+    pub vs_token_amount: f64,
+    pub expected_price: f64,
+    pub execution_price: f64,
+    pub slippage_bps: f64,
+    pub max_slippage_bps: i32,
+    pub price_source: String,
+    pub created_at: chrono::DateTime<chrono::Utc>
+}
+
+#[derive(Debug, Deserialize)]
+pub struct QuoteParams {
+    pub token_pubkey: String,
+    pub vs_token_symbol: String,
+    pub amount: f64
+}
+
+#[derive(Debug, Serialize)]
+pub struct Quote {
+    pub token_pubkey: String,
+    pub vs_token_symbol: String,
+    pub amount: f64,
+    pub price: f64,
+    pub quantity: f64,
+    pub quoted_at: chrono::DateTime<chrono::Utc>
+}
+
+#[derive(Debug, Deserialize)]
+pub struct BuyOrder {
+    pub user_pubkey: String,
+    pub token_pubkey: String,
+    pub vs_token_pubkey: String,
+    pub vs_token_symbol: String,
+    pub vs_token_logo_url: String,
+    /// Amount of the vs token to spend.
+    pub amount: f64,
+    /// Price the client was quoted, used as the reference for the slippage check.
+    pub expected_price: f64,
+    pub max_slippage_bps: u32,
+    /// Spin the token was picked from. The spin is marked as converted into the new position.
+    pub spin_id: Option<Uuid>
+}
+
+#[derive(Debug, Deserialize)]
+pub struct SellOrder {
+    pub position_id: Uuid,
+    /// Quantity of the token to sell. Closes the whole position when omitted.
+    pub quantity: Option<f64>,
+    pub expected_price: f64,
+    pub max_slippage_bps: u32
+}
+
+#[derive(Debug, Serialize)]
+pub struct TradeReceipt {
+    pub position: Position,
+    pub trade: Trade
+}
+
+#[derive(Debug, Serialize)]
+pub struct SellReceipt {
+    pub position: Position,
+    pub trade: Trade,
+    pub fill: PositionFill
+}
+
+impl BuyOrder {
+    pub fn validate(&self) -> Result<()> {
+        // the symbol keys the position's prices, so it has to be the mint's own
+        if !VsToken::is_consistent(&self.vs_token_pubkey, &self.vs_token_symbol) {
+            return Err(ApiError::VsTokenMismatch)
+        }
+
+        if self.amount.is_finite() && self.amount > 0.0
+            && self.expected_price.is_finite() && self.expected_price > 0.0
+            && self.max_slippage_bps <= MAX_SLIPPAGE_BPS {
+            Ok(())
+        } else {
+            Err(ApiError::TradeInvalidOrder)
+        }
+    }
+}
+
+impl QuoteParams {
+    pub fn validate(&self) -> Result<()> {
+        if self.amount.is_finite() && self.amount > 0.0 {
+            Ok(())
+        } else {
+            Err(ApiError::TradeInvalidOrder)
+        }
+    }
+}
+
+impl SellOrder {
+    /// Resolves the quantity to sell against what the position still holds.
+    pub fn validate(&self, position: &Position) -> Result<f64> {
+        if position.status != PositionStatus::Open.as_str() {
+            return Err(ApiError::PositionClosed)
+        }
+
+        let quantity = self.quantity.unwrap_or(position.current_quantity);
+
+        if quantity.is_finite() && quantity > 0.0
+            && quantity <= position.current_quantity + QUANTITY_EPSILON
+            && self.expected_price.is_finite() && self.expected_price > 0.0
+            && self.max_slippage_bps <= MAX_SLIPPAGE_BPS {
+            Ok(quantity.min(position.current_quantity))
+        } else {
+            Err(ApiError::TradeInvalidOrder)
+        }
+    }
+}
+
+/// Signed slippage in basis points. Positive means the fill is worse than expected,
+/// so a higher price for buys and a lower price for sells.
+pub fn calculate_slippage_bps(side: TradeSide, expected_price: f64, execution_price: f64) -> f64 {
+    let adverse_move = match side {
+        TradeSide::Buy => execution_price - expected_price,
+        TradeSide::Sell => expected_price - execution_price,
+    };
+
+    adverse_move / expected_price * 10_000.0
+}
+
+// CRUD implementation for Trade
+
+impl Trade {
+    /// Opens the position and records the fill that produced it in a single transaction.
+    pub async fn execute_buy(
+        order: BuyOrder,
+        token: Token,
+        execution_price: f64,
+        price_source: &str,
+        state: AppState
+    ) -> Result<TradeReceipt> {
+        println!("->> {:<12} - execute_buy", "CONTROLLER");
+
+        // the price sources already drop these, a zero price would open an infinite position
+        if !(execution_price.is_finite() && execution_price > 0.0) {
+            return Err(ApiError::PriceUnavailable { mint: token.mint_pubkey })
+        }
+
+        let user_pubkey = order.user_pubkey.clone();
+        let quantity = order.amount / execution_price;
+        let slippage_bps = calculate_slippage_bps(TradeSide::Buy, order.expected_price, execution_price);
+
+        let result: sqlx::Result<Result<TradeReceipt>> = async {
+            let mut tx = state.db.begin().await?;
+
+            let position = Position::insert_position(
+                PositionForCreate {
+                    user_pubkey: order.user_pubkey,
+                    token_pubkey: token.mint_pubkey,
+                    token_symbol: token.symbol,
+                    token_logo_url: token.logo_url,
+                    vs_token_pubkey: order.vs_token_pubkey,
+                    vs_token_symbol: order.vs_token_symbol,
+                    vs_token_logo_url: order.vs_token_logo_url,
+                    quantity,
+                    purchase_price: execution_price
+                },
+                &mut *tx
+            ).await?;
+
+            let trade = sqlx::query_as::<_, Trade>(
+                    r#"INSERT INTO trades
+                    (position_id, user_pubkey, token_pubkey, vs_token_symbol, side, quantity, vs_token_amount, expected_price, execution_price, slippage_bps, max_slippage_bps, price_source)
+                    VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12)
+                    RETURNING *"#
+                )
+                .bind(position.id)
+                .bind(&position.user_pubkey)
+                .bind(&position.token_pubkey)
+                .bind(&position.vs_token_symbol)
+                .bind(TradeSide::Buy.as_str())
+                .bind(quantity)
+                .bind(order.amount)
+                .bind(order.expected_price)
+                .bind(execution_price)
+                .bind(slippage_bps)
+                .bind(order.max_slippage_bps as i32)
+                .bind(price_source)
+                .fetch_one(&mut *tx)
+                .await?;
+
+            ReferralCode::credit_referral_reward(&position.user_pubkey, &mut tx).await?;
+            PointsLedger::credit_trading_milestone(&position.user_pubkey, &mut tx).await?;
+
+            if let Some(spin_id) = order.spin_id {
+                // guards against two buys racing for the same spin
+                let linked = sqlx::query("UPDATE spins SET position_id = $1 WHERE id = $2 AND position_id IS NULL")
+                    .bind(position.id)
+                    .bind(spin_id)
+                    .execute(&mut *tx)
+                    .await?;
+
+                if linked.rows_affected() == 0 {
+                    return Ok(Err(ApiError::SpinNotLinkable))
+                }
+            }
+
+            tx.commit().await?;
+
+            Ok(Ok(TradeReceipt { position, trade }))
+        }.await;
+
+        match result {
+            Ok(receipt) => receipt,
+            Err(e) => {
+                println!("Error executing buy for user: {}. Error: {}", user_pubkey, e);
+                Err(ApiError::TradeCreateFail)
+            }
+        }
+    }
+
+    /// Sells part or all of a position, recording the trade and the resulting fill.
+    /// The position row is locked so concurrent sells can't oversell it.
+    pub async fn execute_sell(
+        order: SellOrder,
+        quantity: f64,
+        execution_price: f64,
+        price_source: &str,
+        state: AppState
+    ) -> Result<SellReceipt> {
+        println!("->> {:<12} - execute_sell", "CONTROLLER");
+
+        let position_id = order.position_id;
+        let slippage_bps = calculate_slippage_bps(TradeSide::Sell, order.expected_price, execution_price);
+
+        let result: sqlx::Result<Option<SellReceipt>> = async {
+            let mut tx = state.db.begin().await?;
+
+            let position = sqlx::query_as::<_, Position>(
+                    "SELECT * FROM positions WHERE id = $1 FOR UPDATE;"
+                )
+                .bind(position_id)
+                .fetch_one(&mut *tx)
+                .await?;
+
+            if position.status != PositionStatus::Open.as_str()
+                || quantity > position.current_quantity + QUANTITY_EPSILON {
+                return Ok(None)
+            }
+
+            let remaining_quantity = (position.current_quantity - quantity).max(0.0);
+            let realized_pnl = (execution_price - position.purchase_price) * quantity;
+
+            let position = if remaining_quantity <= QUANTITY_EPSILON {
+                sqlx::query_as::<_, Position>(
+                        r#"UPDATE positions
+                        SET current_quantity = 0, realized_pnl = realized_pnl + $1, status = $2, closed_at = NOW()
+                        WHERE id = $3
+                        RETURNING *"#
+                    )
+                    .bind(realized_pnl)
+                    .bind(PositionStatus::Closed.as_str())
+                    .bind(position_id)
+                    .fetch_one(&mut *tx)
+                    .await?
+            } else {
+                sqlx::query_as::<_, Position>(
+                        r#"UPDATE positions
+                        SET current_quantity = $1, realized_pnl = realized_pnl + $2
+                        WHERE id = $3
+                        RETURNING *"#
+                    )
+                    .bind(remaining_quantity)
+                    .bind(realized_pnl)
+                    .bind(position_id)
+                    .fetch_one(&mut *tx)
+                    .await?
+            };
+
+            let trade = sqlx::query_as::<_, Trade>(
+                    r#"INSERT INTO trades
+                    (position_id, user_pubkey, token_pubkey, vs_token_symbol, side, quantity, vs_token_amount, expected_price, execution_price, slippage_bps, max_slippage_bps, price_source)
+                    VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12)
+                    RETURNING *"#
+                )
+                .bind(position.id)
+                .bind(&position.user_pubkey)
+                .bind(&position.token_pubkey)
+                .bind(&position.vs_token_symbol)
+                .bind(TradeSide::Sell.as_str())
+                .bind(quantity)
+                .bind(quantity * execution_price)
+                .bind(order.expected_price)
+                .bind(execution_price)
+                .bind(slippage_bps)
+                .bind(order.max_slippage_bps as i32)
+                .bind(price_source)
+                .fetch_one(&mut *tx)
+                .await?;
+
+            let fill = sqlx::query_as::<_, PositionFill>(
+                    "INSERT INTO position_fills (position_id, trade_id, quantity, price, realized_pnl) VALUES ($1, $2, $3, $4, $5) RETURNING *"
+                )
+                .bind(position.id)
+                .bind(trade.id)
+                .bind(quantity)
+                .bind(execution_price)
+                .bind(realized_pnl)
+                .fetch_one(&mut *tx)
+                .await?;
+
+            tx.commit().await?;
+
+            Ok(Some(SellReceipt { position, trade, fill }))
+        }.await;
+
+        match result {
+            Ok(Some(receipt)) => Ok(receipt),
+            Ok(None) => Err(ApiError::TradeInvalidOrder),
+            Err(e) => {
+                println!("Error executing sell for position: {}. Error: {}", position_id, e);
+                Err(ApiError::TradeCreateFail)
+            }
+        }
+    }
+}
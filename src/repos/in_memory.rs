@@ -0,0 +1,778 @@
+//! In-memory repositories, so handlers and cron jobs can be tested without Postgres.
+
+use std::{collections::HashMap, sync::{Arc, RwLock}};
+
+use chrono::{DateTime, NaiveDate, Utc};
+use futures::{future::BoxFuture, FutureExt};
+use uuid::Uuid;
+use crate::{
+    errors::api_errors::{ApiError, Result},
+    models::{
+        model_leaderboard::{Leaderboard, LeaderboardEntry, LeaderboardMetric, LeaderboardParams, LeaderboardWindow, RankedLeaderboardEntry, UserPerformance},
+        model_position::{Position, PositionForCreate, PositionStatus, UniquePositionsData, UpdatePositionData},
+        model_selection::{SelectionConfig, SelectionConfigForUpdate, SelectionRun, SelectionRunForCreate, SelectionRunWithTokens},
+        model_token::{Token, TokenForCreate},
+        model_token_override::{TokenOverride, TokenOverrideForCreate, TokenOverrideForUpdate},
+        model_token_snapshot::{SnapshotForCreate, TokenPriceSnapshot},
+        model_user::{User, UserForCreate}
+    }
+};
+
+use super::{
+    repo_leaderboard::LeaderboardRepo,
+    repo_position::PositionRepo,
+    repo_selection::SelectionRepo,
+    repo_token::TokenRepo,
+    repo_token_override::TokenOverrideRepo,
+    repo_token_snapshot::SnapshotRepo,
+    repo_user::UserRepo
+};
+
+/// Rejects duplicate mints, like the primary key on `tokens.mint_pubkey`.
+#[derive(Default, Clone)]
+pub struct InMemoryTokenRepo {
+    tokens: Arc<RwLock<Vec<Token>>>
+}
+
+impl InMemoryTokenRepo {
+    fn update(&self, mint_pubkey: &str, apply: impl FnOnce(&mut Token)) {
+        let mut tokens = self.tokens.write().expect("token repo lock poisoned");
+
+        // like an UPDATE matching no row, an unknown mint is not an error
+        if let Some(token) = tokens.iter_mut().find(|token| token.mint_pubkey == mint_pubkey) {
+            apply(token);
+        }
+    }
+}
+
+impl TokenRepo for InMemoryTokenRepo {
+    fn create_token(&self, token: TokenForCreate) -> BoxFuture<'_, Result<Token>> {
+        let mut tokens = self.tokens.write().expect("token repo lock poisoned");
+
+        if tokens.iter().any(|existing| existing.mint_pubkey == token.mint_pubkey) {
+            return futures::future::ready(Err(ApiError::TokenCreateFail)).boxed()
+        }
+
+        let token = Token {
+            mint_pubkey: token.mint_pubkey,
+            symbol: token.symbol,
+            name: token.name,
+            logo_url: token.logo_url,
+            price_change_24h_percent: token.price_change_24h_percent,
+            volume_24h_usd: token.volume_24h_usd,
+            discord_url: token.discord_url,
+            twitter_url: token.twitter_url,
+            website_url: token.website_url,
+            telegram_url: token.telegram_url,
+            decimals: token.decimals,
+            is_active: token.is_active,
+            created_at: chrono::Utc::now(),
+            risk_score: token.risk_score
+        };
+
+        tokens.push(token.clone());
+
+        futures::future::ready(Ok(token)).boxed()
+    }
+
+    fn get_tokens(&self) -> BoxFuture<'_, Result<Vec<Token>>> {
+        let tokens = self.tokens.read().expect("token repo lock poisoned").clone();
+
+        futures::future::ready(Ok(tokens)).boxed()
+    }
+
+    fn get_token<'a>(&'a self, mint_pubkey: &'a str) -> BoxFuture<'a, Result<Option<Token>>> {
+        let token = self.tokens.read().expect("token repo lock poisoned")
+            .iter()
+            .find(|token| token.mint_pubkey == mint_pubkey)
+            .cloned();
+
+        futures::future::ready(Ok(token)).boxed()
+    }
+
+    fn get_all_active_tokens(&self) -> BoxFuture<'_, Result<Vec<Token>>> {
+        let tokens: Vec<Token> = self.tokens.read().expect("token repo lock poisoned")
+            .iter()
+            .filter(|token| token.is_active)
+            .cloned()
+            .collect();
+
+        futures::future::ready(Ok(tokens)).boxed()
+    }
+
+    fn get_7_active_tokens(&self) -> BoxFuture<'_, Result<Vec<Token>>> {
+        let mut tokens: Vec<Token> = self.tokens.read().expect("token repo lock poisoned")
+            .iter()
+            .filter(|token| token.is_active)
+            .cloned()
+            .collect();
+
+        tokens.sort_by(|a, b| b.volume_24h_usd.total_cmp(&a.volume_24h_usd));
+        tokens.truncate(7);
+
+        futures::future::ready(Ok(tokens)).boxed()
+    }
+
+    fn update_token_state<'a>(&'a self, mint_pubkey: &'a str, is_active: bool) -> BoxFuture<'a, Result<()>> {
+        self.update(mint_pubkey, |token| token.is_active = is_active);
+
+        futures::future::ready(Ok(())).boxed()
+    }
+
+    fn update_token_risk_score<'a>(&'a self, mint_pubkey: &'a str, risk_score: i32) -> BoxFuture<'a, Result<()>> {
+        self.update(mint_pubkey, |token| token.risk_score = Some(risk_score));
+
+        futures::future::ready(Ok(())).boxed()
+    }
+
+    fn update_token_financial_data<'a>(
+        &'a self,
+        mint_pubkey: &'a str,
+        price_change_24h_percent: f64,
+        volume_24h_usd: f64,
+        decimals: i32
+    ) -> BoxFuture<'a, Result<()>> {
+        self.update(mint_pubkey, |token| {
+            token.price_change_24h_percent = price_change_24h_percent;
+            token.volume_24h_usd = volume_24h_usd;
+            token.decimals = decimals;
+        });
+
+        futures::future::ready(Ok(())).boxed()
+    }
+}
+
+/// Foreign keys are not enforced, so positions can reference users and tokens that were never created.
+#[derive(Default, Clone)]
+pub struct InMemoryPositionRepo {
+    positions: Arc<RwLock<Vec<Position>>>
+}
+
+impl InMemoryPositionRepo {
+    fn filter(&self, predicate: impl Fn(&Position) -> bool) -> Vec<Position> {
+        self.positions.read().expect("position repo lock poisoned")
+            .iter()
+            .filter(|position| predicate(position))
+            .cloned()
+            .collect()
+    }
+}
+
+impl PositionRepo for InMemoryPositionRepo {
+    fn create_position(&self, position: PositionForCreate) -> BoxFuture<'_, Result<Position>> {
+        let position = Position {
+            id: Uuid::new_v4(),
+            user_pubkey: position.user_pubkey,
+            token_pubkey: position.token_pubkey,
+            token_symbol: position.token_symbol,
+            token_logo_url: position.token_logo_url,
+            vs_token_pubkey: position.vs_token_pubkey,
+            vs_token_symbol: position.vs_token_symbol,
+            vs_token_logo_url: position.vs_token_logo_url,
+            initial_quantity: position.quantity,
+            current_quantity: position.quantity,
+            purchase_price: position.purchase_price,
+            created_at: chrono::Utc::now(),
+            status: PositionStatus::Open.as_str().to_string(),
+            realized_pnl: 0.0,
+            closed_at: None
+        };
+
+        self.positions.write().expect("position repo lock poisoned").push(position.clone());
+
+        futures::future::ready(Ok(position)).boxed()
+    }
+
+    fn update_position_quantity(&self, update_data: UpdatePositionData) -> BoxFuture<'_, Result<Position>> {
+        let mut positions = self.positions.write().expect("position repo lock poisoned");
+
+        let result = match positions.iter_mut().find(|position| position.id == update_data.position_id) {
+            Some(position) => {
+                position.current_quantity = update_data.new_quantity;
+                Ok(position.clone())
+            },
+            None => Err(ApiError::PositionGetFail)
+        };
+
+        futures::future::ready(result).boxed()
+    }
+
+    fn get_positions(&self) -> BoxFuture<'_, Result<Vec<Position>>> {
+        futures::future::ready(Ok(self.filter(|_| true))).boxed()
+    }
+
+    fn get_position<'a>(&'a self, position_id: &'a Uuid) -> BoxFuture<'a, Result<Option<Position>>> {
+        let position = self.filter(|position| position.id == *position_id).pop();
+
+        futures::future::ready(Ok(position)).boxed()
+    }
+
+    fn get_user_positions<'a>(&'a self, user_pubkey: &'a str) -> BoxFuture<'a, Result<Vec<Position>>> {
+        let positions = self.filter(|position| position.user_pubkey == user_pubkey);
+
+        futures::future::ready(Ok(positions)).boxed()
+    }
+
+    fn get_user_positions_by_token<'a>(
+        &'a self,
+        user_pubkey: &'a str,
+        token_pubkey: &'a str
+    ) -> BoxFuture<'a, Result<Vec<Position>>> {
+        let positions = self.filter(|position| {
+            position.user_pubkey == user_pubkey && position.token_pubkey == token_pubkey
+        });
+
+        futures::future::ready(Ok(positions)).boxed()
+    }
+
+    fn get_token_positions<'a>(&'a self, token_pubkey: &'a str) -> BoxFuture<'a, Result<Vec<Position>>> {
+        let positions = self.filter(|position| position.token_pubkey == token_pubkey);
+
+        futures::future::ready(Ok(positions)).boxed()
+    }
+
+    fn get_user_unique_tokens_and_vs_tokens<'a>(&'a self, user_pubkey: &'a str) -> BoxFuture<'a, Result<Vec<UniquePositionsData>>> {
+        let mut positions = self.filter(|position| position.user_pubkey == user_pubkey);
+
+        // same ordering as the DISTINCT ON query, so the oldest position of each pair is kept
+        positions.sort_by(|a, b| {
+            (&a.token_pubkey, &a.vs_token_symbol, a.created_at)
+                .cmp(&(&b.token_pubkey, &b.vs_token_symbol, b.created_at))
+        });
+        positions.dedup_by(|b, a| a.token_pubkey == b.token_pubkey && a.vs_token_symbol == b.vs_token_symbol);
+
+        let unique_positions = positions
+            .into_iter()
+            .map(|position| UniquePositionsData {
+                token_pubkey: position.token_pubkey,
+                vs_token_pubkey: position.vs_token_pubkey,
+                vs_token_symbol: position.vs_token_symbol
+            })
+            .collect();
+
+        futures::future::ready(Ok(unique_positions)).boxed()
+    }
+}
+
+/// There is no points ledger or referral codes behind it: points stay at 0 and any referral code is rejected as invalid.
+#[derive(Default, Clone)]
+pub struct InMemoryUserRepo {
+    users: Arc<RwLock<Vec<User>>>
+}
+
+impl UserRepo for InMemoryUserRepo {
+    fn create_user(&self, user: UserForCreate) -> BoxFuture<'_, Result<User>> {
+        if user.referral_code.is_some() {
+            return futures::future::ready(Err(ApiError::ReferralCodeInvalid)).boxed()
+        }
+
+        let mut users = self.users.write().expect("user repo lock poisoned");
+
+        if users.iter().any(|existing| existing.user_pubkey == user.user_pubkey) {
+            return futures::future::ready(Err(ApiError::UserCreateFail)).boxed()
+        }
+
+        let user = User {
+            user_pubkey: user.user_pubkey,
+            referred_by: None,
+            points: 0,
+            created_at: chrono::Utc::now()
+        };
+
+        users.push(user.clone());
+
+        futures::future::ready(Ok(user)).boxed()
+    }
+
+    fn get_users(&self) -> BoxFuture<'_, Result<Vec<User>>> {
+        let users = self.users.read().expect("user repo lock poisoned").clone();
+
+        futures::future::ready(Ok(users)).boxed()
+    }
+
+    fn get_user<'a>(&'a self, pubkey: &'a str) -> BoxFuture<'a, Result<Option<User>>> {
+        let user = self.users.read().expect("user repo lock poisoned")
+            .iter()
+            .find(|user| user.user_pubkey == pubkey)
+            .cloned();
+
+        futures::future::ready(Ok(user)).boxed()
+    }
+}
+
+/// Holds no config until one is ensured, like an empty `selection_config` table. Runs are dated the current UTC day.
+#[derive(Default, Clone)]
+pub struct InMemorySelectionRepo {
+    config: Arc<RwLock<Option<SelectionConfig>>>,
+    runs: Arc<RwLock<Vec<SelectionRunWithTokens>>>
+}
+
+fn stored_selection_config(config: SelectionConfigForUpdate) -> SelectionConfig {
+    SelectionConfig {
+        min_market_cap_usd: config.min_market_cap_usd,
+        min_liquidity_usd: config.min_liquidity_usd,
+        min_trades_24h: config.min_trades_24h,
+        selection_size: config.selection_size,
+        page_count: config.page_count,
+        sort_by: config.sort_by.as_str().to_string(),
+        max_risk_score: config.max_risk_score,
+        max_top10_holder_share: config.max_top10_holder_share,
+        max_creator_share: config.max_creator_share,
+        updated_at: Utc::now()
+    }
+}
+
+impl InMemorySelectionRepo {
+    /// Starts with `defaults` stored, the way they are seeded at startup.
+    pub fn with_config(defaults: SelectionConfigForUpdate) -> Self {
+        Self {
+            config: Arc::new(RwLock::new(Some(stored_selection_config(defaults)))),
+            runs: Arc::default()
+        }
+    }
+}
+
+impl SelectionRepo for InMemorySelectionRepo {
+    fn ensure_selection_config(&self, defaults: SelectionConfigForUpdate) -> BoxFuture<'_, Result<()>> {
+        let result = defaults.validate().map(|_| {
+            self.config.write().expect("selection repo lock poisoned")
+                .get_or_insert_with(|| stored_selection_config(defaults));
+        });
+
+        futures::future::ready(result).boxed()
+    }
+
+    fn get_selection_config(&self) -> BoxFuture<'_, Result<SelectionConfig>> {
+        let config = self.config.read().expect("selection repo lock poisoned")
+            .clone()
+            .ok_or(ApiError::SelectionConfigGetFail);
+
+        futures::future::ready(config).boxed()
+    }
+
+    fn update_selection_config(&self, config: SelectionConfigForUpdate) -> BoxFuture<'_, Result<SelectionConfig>> {
+        if let Err(e) = config.validate() {
+            return futures::future::ready(Err(e)).boxed()
+        }
+
+        let mut stored = self.config.write().expect("selection repo lock poisoned");
+
+        // like the UPDATE, there is nothing to update before a config was ensured
+        let result = match stored.as_mut() {
+            Some(stored) => {
+                *stored = stored_selection_config(config);
+                Ok(stored.clone())
+            },
+            None => Err(ApiError::SelectionConfigUpdateFail)
+        };
+
+        futures::future::ready(result).boxed()
+    }
+
+    fn create_selection_run(&self, run: SelectionRunForCreate) -> BoxFuture<'_, Result<SelectionRun>> {
+        let created_at = Utc::now();
+
+        let selection_run = SelectionRun {
+            id: Uuid::new_v4(),
+            run_date: created_at.date_naive(),
+            min_market_cap_usd: Some(run.config.min_market_cap_usd),
+            min_liquidity_usd: Some(run.config.min_liquidity_usd),
+            min_trades_24h: Some(run.config.min_trades_24h),
+            selection_size: Some(run.config.selection_size),
+            page_count: Some(run.config.page_count),
+            sort_by: Some(run.config.sort_by),
+            max_risk_score: Some(run.config.max_risk_score),
+            max_top10_holder_share: Some(run.config.max_top10_holder_share),
+            max_creator_share: Some(run.config.max_creator_share),
+            created_at
+        };
+
+        self.runs.write().expect("selection repo lock poisoned")
+            .push(SelectionRunWithTokens { run: selection_run.clone(), tokens: run.tokens });
+
+        futures::future::ready(Ok(selection_run)).boxed()
+    }
+
+    fn get_selections(&self, date: NaiveDate) -> BoxFuture<'_, Result<Vec<SelectionRunWithTokens>>> {
+        let runs = self.runs.read().expect("selection repo lock poisoned");
+
+        let mut selections: Vec<SelectionRunWithTokens> = runs.iter()
+            .filter(|selection| selection.run.run_date == date)
+            .cloned()
+            .collect();
+
+        if selections.is_empty() {
+            selections.extend(
+                runs.iter()
+                    .filter(|selection| selection.run.run_date < date)
+                    .max_by_key(|selection| (selection.run.run_date, selection.run.created_at))
+                    .cloned()
+            );
+        }
+
+        // same ordering as the queries, latest run first and its tokens by volume
+        selections.sort_by_key(|selection| std::cmp::Reverse(selection.run.created_at));
+
+        for selection in &mut selections {
+            selection.tokens.sort_by(|a, b| b.volume_24h_usd.total_cmp(&a.volume_24h_usd));
+        }
+
+        futures::future::ready(Ok(selections)).boxed()
+    }
+}
+
+/// Allows one override per mint, like the unique constraint on `token_overrides.mint_pubkey`.
+#[derive(Default, Clone)]
+pub struct InMemoryTokenOverrideRepo {
+    overrides: Arc<RwLock<Vec<TokenOverride>>>
+}
+
+impl TokenOverrideRepo for InMemoryTokenOverrideRepo {
+    fn create_token_override(&self, token_override: TokenOverrideForCreate) -> BoxFuture<'_, Result<TokenOverride>> {
+        if let Err(e) = token_override.validate() {
+            return futures::future::ready(Err(e)).boxed()
+        }
+
+        let mut overrides = self.overrides.write().expect("token override repo lock poisoned");
+
+        let mint_pubkey = token_override.mint_pubkey.trim();
+
+        if overrides.iter().any(|existing| existing.mint_pubkey == mint_pubkey) {
+            return futures::future::ready(Err(ApiError::TokenOverrideExists)).boxed()
+        }
+
+        let token_override = TokenOverride {
+            id: Uuid::new_v4(),
+            mint_pubkey: mint_pubkey.to_string(),
+            kind: token_override.kind.as_str().to_string(),
+            reason: token_override.reason.trim().to_string(),
+            expires_at: token_override.expires_at,
+            created_at: Utc::now(),
+            updated_at: Utc::now()
+        };
+
+        overrides.push(token_override.clone());
+
+        futures::future::ready(Ok(token_override)).boxed()
+    }
+
+    fn get_token_overrides(&self) -> BoxFuture<'_, Result<Vec<TokenOverride>>> {
+        let mut overrides = self.overrides.read().expect("token override repo lock poisoned").clone();
+
+        overrides.sort_by(|a, b| a.kind.cmp(&b.kind).then(b.created_at.cmp(&a.created_at)));
+
+        futures::future::ready(Ok(overrides)).boxed()
+    }
+
+    fn get_active_token_overrides(&self) -> BoxFuture<'_, Result<Vec<TokenOverride>>> {
+        let now = Utc::now();
+
+        let overrides: Vec<TokenOverride> = self.overrides.read().expect("token override repo lock poisoned")
+            .iter()
+            .filter(|token_override| token_override.expires_at.is_none_or(|expires_at| expires_at > now))
+            .cloned()
+            .collect();
+
+        futures::future::ready(Ok(overrides)).boxed()
+    }
+
+    fn update_token_override<'a>(
+        &'a self,
+        override_id: &'a Uuid,
+        token_override: TokenOverrideForUpdate
+    ) -> BoxFuture<'a, Result<Option<TokenOverride>>> {
+        if let Err(e) = token_override.validate() {
+            return futures::future::ready(Err(e)).boxed()
+        }
+
+        let updated = self.overrides.write().expect("token override repo lock poisoned")
+            .iter_mut()
+            .find(|existing| existing.id == *override_id)
+            .map(|existing| {
+                existing.kind = token_override.kind.as_str().to_string();
+                existing.reason = token_override.reason.trim().to_string();
+                existing.expires_at = token_override.expires_at;
+                existing.updated_at = Utc::now();
+                existing.clone()
+            });
+
+        futures::future::ready(Ok(updated)).boxed()
+    }
+
+    fn delete_token_override<'a>(&'a self, override_id: &'a Uuid) -> BoxFuture<'a, Result<Option<TokenOverride>>> {
+        let mut overrides = self.overrides.write().expect("token override repo lock poisoned");
+
+        let deleted = overrides.iter()
+            .position(|existing| existing.id == *override_id)
+            .map(|index| overrides.remove(index));
+
+        futures::future::ready(Ok(deleted)).boxed()
+    }
+}
+
+/// Snapshots are stamped with the time they are stored, like the column default.
+#[derive(Default, Clone)]
+pub struct InMemorySnapshotRepo {
+    snapshots: Arc<RwLock<Vec<TokenPriceSnapshot>>>
+}
+
+impl SnapshotRepo for InMemorySnapshotRepo {
+    fn create_snapshots(&self, snapshots: Vec<SnapshotForCreate>) -> BoxFuture<'_, Result<()>> {
+        let created_at = Utc::now();
+
+        self.snapshots.write().expect("snapshot repo lock poisoned")
+            .extend(snapshots.into_iter().map(|snapshot| TokenPriceSnapshot {
+                mint_pubkey: snapshot.mint_pubkey,
+                price: snapshot.price,
+                volume_24h_usd: snapshot.volume_24h_usd,
+                liquidity: snapshot.liquidity,
+                market_cap: snapshot.market_cap,
+                created_at
+            }));
+
+        futures::future::ready(Ok(())).boxed()
+    }
+
+    fn get_snapshots<'a>(
+        &'a self,
+        mint_pubkey: &'a str,
+        from: DateTime<Utc>,
+        to: DateTime<Utc>
+    ) -> BoxFuture<'a, Result<Vec<TokenPriceSnapshot>>> {
+        let mut snapshots: Vec<TokenPriceSnapshot> = self.snapshots.read().expect("snapshot repo lock poisoned")
+            .iter()
+            .filter(|snapshot| snapshot.mint_pubkey == mint_pubkey && snapshot.created_at >= from && snapshot.created_at < to)
+            .cloned()
+            .collect();
+
+        snapshots.sort_by_key(|snapshot| snapshot.created_at);
+
+        futures::future::ready(Ok(snapshots)).boxed()
+    }
+}
+
+#[derive(Default, Clone)]
+pub struct InMemoryLeaderboardRepo {
+    entries: Arc<RwLock<Vec<(LeaderboardWindow, LeaderboardEntry)>>>
+}
+
+/// The metric ranked on, then the other one as tie-breaker.
+fn ranking_key(entry: &LeaderboardEntry, metric: LeaderboardMetric) -> (f64, f64) {
+    match metric {
+        LeaderboardMetric::PnlPct => (entry.pnl_pct, entry.pnl_abs_usd),
+        LeaderboardMetric::PnlAbs => (entry.pnl_abs_usd, entry.pnl_pct),
+    }
+}
+
+impl LeaderboardRepo for InMemoryLeaderboardRepo {
+    fn replace_window_entries(
+        &self,
+        window: LeaderboardWindow,
+        performance: HashMap<String, UserPerformance>,
+        computed_at: DateTime<Utc>
+    ) -> BoxFuture<'_, Result<()>> {
+        let mut entries = self.entries.write().expect("leaderboard repo lock poisoned");
+
+        entries.retain(|(entry_window, _)| *entry_window != window);
+        entries.extend(performance.into_iter().map(|(user_pubkey, user_performance)| {
+            (window, LeaderboardEntry {
+                user_pubkey,
+                positions_count: user_performance.positions_count,
+                cost_basis_usd: user_performance.cost_basis_usd,
+                pnl_abs_usd: user_performance.pnl_abs_usd,
+                pnl_pct: user_performance.pnl_pct(),
+                computed_at
+            })
+        }));
+
+        futures::future::ready(Ok(())).boxed()
+    }
+
+    fn get_leaderboard(&self, params: LeaderboardParams) -> BoxFuture<'_, Result<Leaderboard>> {
+        let mut entries: Vec<LeaderboardEntry> = self.entries.read().expect("leaderboard repo lock poisoned")
+            .iter()
+            .filter(|(entry_window, _)| *entry_window == params.window)
+            .map(|(_, entry)| entry.clone())
+            .collect();
+
+        entries.sort_by(|a, b| {
+            let (a_first, a_second) = ranking_key(a, params.metric);
+            let (b_first, b_second) = ranking_key(b, params.metric);
+
+            b_first.total_cmp(&a_first)
+                .then(b_second.total_cmp(&a_second))
+                .then_with(|| a.user_pubkey.cmp(&b.user_pubkey))
+        });
+        entries.truncate(params.limit() as usize);
+
+        let leaderboard = Leaderboard {
+            window: params.window,
+            entries: entries.into_iter()
+                .enumerate()
+                .map(|(index, entry)| RankedLeaderboardEntry { rank: index + 1, entry })
+                .collect()
+        };
+
+        futures::future::ready(Ok(leaderboard)).boxed()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn token(mint_pubkey: &str, volume_24h_usd: f64, is_active: bool) -> TokenForCreate {
+        TokenForCreate {
+            mint_pubkey: mint_pubkey.to_string(),
+            symbol: mint_pubkey.to_string(),
+            name: mint_pubkey.to_string(),
+            logo_url: String::new(),
+            price_change_24h_percent: 0.0,
+            volume_24h_usd,
+            discord_url: None,
+            twitter_url: None,
+            website_url: None,
+            telegram_url: None,
+            decimals: 6,
+            is_active,
+            risk_score: None
+        }
+    }
+
+    fn position(user_pubkey: &str, token_pubkey: &str, vs_token_symbol: &str, quantity: f64) -> PositionForCreate {
+        PositionForCreate {
+            user_pubkey: user_pubkey.to_string(),
+            token_pubkey: token_pubkey.to_string(),
+            token_symbol: token_pubkey.to_string(),
+            token_logo_url: String::new(),
+            vs_token_pubkey: format!("{}Mint", vs_token_symbol),
+            vs_token_symbol: vs_token_symbol.to_string(),
+            vs_token_logo_url: String::new(),
+            quantity,
+            purchase_price: 1.0
+        }
+    }
+
+    #[tokio::test]
+    async fn picks_the_7_most_traded_active_tokens() {
+        let repo = InMemoryTokenRepo::default();
+
+        for volume in 0..10 {
+            repo.create_token(token(&format!("Mint{}", volume), volume as f64, true)).await.unwrap();
+        }
+        repo.create_token(token("Inactive", 1_000.0, false)).await.unwrap();
+
+        let tokens = repo.get_7_active_tokens().await.unwrap();
+
+        assert_eq!(tokens.len(), 7);
+        assert_eq!(tokens[0].mint_pubkey, "Mint9");
+        assert_eq!(tokens[6].mint_pubkey, "Mint3");
+    }
+
+    #[tokio::test]
+    async fn rejects_duplicate_mints_and_ignores_updates_to_unknown_ones() {
+        let repo = InMemoryTokenRepo::default();
+
+        repo.create_token(token("MintA", 1.0, true)).await.unwrap();
+
+        assert!(matches!(repo.create_token(token("MintA", 1.0, true)).await, Err(ApiError::TokenCreateFail)));
+
+        repo.update_token_state("MintA", false).await.unwrap();
+        repo.update_token_state("Unknown", false).await.unwrap();
+
+        assert!(repo.get_all_active_tokens().await.unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn keeps_the_oldest_position_of_each_pair() {
+        let repo = InMemoryPositionRepo::default();
+
+        let oldest = repo.create_position(position("Wallet", "MintA", "USDC", 1.0)).await.unwrap();
+        repo.create_position(position("Wallet", "MintA", "USDC", 2.0)).await.unwrap();
+        repo.create_position(position("Wallet", "MintA", "SOL", 3.0)).await.unwrap();
+        repo.create_position(position("Other", "MintB", "USDC", 4.0)).await.unwrap();
+
+        let unique = repo.get_user_unique_tokens_and_vs_tokens("Wallet").await.unwrap();
+        let pairs: Vec<(&str, &str)> = unique.iter()
+            .map(|pair| (pair.token_pubkey.as_str(), pair.vs_token_symbol.as_str()))
+            .collect();
+
+        assert_eq!(pairs, vec![("MintA", "SOL"), ("MintA", "USDC")]);
+        assert_eq!(oldest.status, "open");
+        assert_eq!(oldest.current_quantity, oldest.initial_quantity);
+    }
+
+    #[tokio::test]
+    async fn updates_the_quantity_of_existing_positions_only() {
+        let repo = InMemoryPositionRepo::default();
+
+        let position = repo.create_position(position("Wallet", "MintA", "USDC", 5.0)).await.unwrap();
+
+        let updated = repo.update_position_quantity(UpdatePositionData { position_id: position.id, new_quantity: 2.0 }).await.unwrap();
+        assert_eq!(updated.current_quantity, 2.0);
+        assert_eq!(updated.initial_quantity, 5.0);
+
+        let missing = repo.update_position_quantity(UpdatePositionData { position_id: Uuid::new_v4(), new_quantity: 1.0 }).await;
+        assert!(matches!(missing, Err(ApiError::PositionGetFail)));
+    }
+
+    #[tokio::test]
+    async fn rejects_duplicate_users_and_referral_codes() {
+        let repo = InMemoryUserRepo::default();
+
+        let user = repo.create_user(UserForCreate { user_pubkey: "Wallet".to_string(), referral_code: None }).await.unwrap();
+        assert_eq!(user.points, 0);
+
+        let duplicate = repo.create_user(UserForCreate { user_pubkey: "Wallet".to_string(), referral_code: None }).await;
+        assert!(matches!(duplicate, Err(ApiError::UserCreateFail)));
+
+        let referred = repo.create_user(UserForCreate { user_pubkey: "Referred".to_string(), referral_code: Some("CODE".to_string()) }).await;
+        assert!(matches!(referred, Err(ApiError::ReferralCodeInvalid)));
+
+        assert_eq!(repo.get_users().await.unwrap().len(), 1);
+    }
+
+    #[tokio::test]
+    async fn falls_back_to_the_latest_earlier_selection_run() {
+        let repo = InMemorySelectionRepo::with_config(SelectionConfigForUpdate::default());
+        let config = repo.get_selection_config().await.unwrap();
+
+        let run = repo.create_selection_run(SelectionRunForCreate { config, tokens: Vec::new() }).await.unwrap();
+
+        let later = repo.get_selections(run.run_date + chrono::Days::new(1)).await.unwrap();
+        assert_eq!(later.len(), 1);
+        assert_eq!(later[0].run.id, run.id);
+
+        assert!(repo.get_selections(run.run_date - chrono::Days::new(1)).await.unwrap().is_empty());
+        assert!(matches!(InMemorySelectionRepo::default().get_selection_config().await, Err(ApiError::SelectionConfigGetFail)));
+    }
+
+    #[tokio::test]
+    async fn allows_one_override_per_mint_and_skips_expired_ones() {
+        let repo = InMemoryTokenOverrideRepo::default();
+
+        let token_override = |mint_pubkey: &str| TokenOverrideForCreate {
+            mint_pubkey: mint_pubkey.to_string(),
+            kind: crate::models::model_token_override::TokenOverrideKind::Exclude,
+            reason: "rugged".to_string(),
+            expires_at: Some(Utc::now() + std::time::Duration::from_secs(60 * 60))
+        };
+
+        let created = repo.create_token_override(token_override(" MintA ")).await.unwrap();
+        assert_eq!(created.mint_pubkey, "MintA");
+        assert!(matches!(repo.create_token_override(token_override("MintA")).await, Err(ApiError::TokenOverrideExists)));
+
+        repo.create_token_override(token_override("MintB")).await.unwrap();
+        // expiry is only validated on write, so age the stored one directly
+        repo.overrides.write().unwrap()[1].expires_at = Some(Utc::now() - std::time::Duration::from_secs(60 * 60));
+
+        let active: Vec<String> = repo.get_active_token_overrides().await.unwrap()
+            .into_iter()
+            .map(|token_override| token_override.mint_pubkey)
+            .collect();
+        assert_eq!(active, vec!["MintA"]);
+
+        assert!(repo.delete_token_override(&created.id).await.unwrap().is_some());
+        assert!(repo.delete_token_override(&created.id).await.unwrap().is_none());
+    }
+}
@@ -0,0 +1,9 @@
+pub mod repo_token;
+pub mod repo_position;
+pub mod repo_user;
+pub mod repo_selection;
+pub mod repo_token_override;
+pub mod repo_token_snapshot;
+pub mod repo_leaderboard;
+#[cfg(test)]
+pub mod in_memory;
\ No newline at end of file
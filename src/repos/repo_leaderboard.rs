@@ -0,0 +1,123 @@
+use std::collections::HashMap;
+
+use chrono::{DateTime, Utc};
+use futures::{future::BoxFuture, FutureExt};
+use sqlx::PgPool;
+use crate::{
+    errors::api_errors::{ApiError, Result},
+    models::model_leaderboard::{Leaderboard, LeaderboardEntry, LeaderboardMetric, LeaderboardParams, LeaderboardWindow, RankedLeaderboardEntry, UserPerformance}
+};
+
+/// Storage for the rankings the leaderboard updater materializes, reached through `AppState::leaderboard_repo`.
+pub trait LeaderboardRepo: Send + Sync {
+    /// Swaps the stored entries of a window for a freshly computed set.
+    fn replace_window_entries(
+        &self,
+        window: LeaderboardWindow,
+        performance: HashMap<String, UserPerformance>,
+        computed_at: DateTime<Utc>
+    ) -> BoxFuture<'_, Result<()>>;
+
+    fn get_leaderboard(&self, params: LeaderboardParams) -> BoxFuture<'_, Result<Leaderboard>>;
+}
+
+pub struct PgLeaderboardRepo {
+    db: PgPool
+}
+
+impl PgLeaderboardRepo {
+    pub fn new(db: PgPool) -> Self {
+        Self { db }
+    }
+}
+
+// CRUD implementation for LeaderboardEntry
+
+impl LeaderboardRepo for PgLeaderboardRepo {
+    fn replace_window_entries(
+        &self,
+        window: LeaderboardWindow,
+        performance: HashMap<String, UserPerformance>,
+        computed_at: DateTime<Utc>
+    ) -> BoxFuture<'_, Result<()>> {
+        async move {
+            println!("->> {:<12} - replace_window_entries {}", "CONTROLLER", window.as_str());
+
+            let result: sqlx::Result<()> = async {
+                let mut tx = self.db.begin().await?;
+
+                sqlx::query("DELETE FROM leaderboard_entries WHERE time_window = $1")
+                    .bind(window.as_str())
+                    .execute(&mut *tx)
+                    .await?;
+
+                for (user_pubkey, user_performance) in performance {
+                    sqlx::query(
+                            r#"INSERT INTO leaderboard_entries
+                            (time_window, user_pubkey, positions_count, cost_basis_usd, pnl_abs_usd, pnl_pct, computed_at)
+                            VALUES ($1, $2, $3, $4, $5, $6, $7)"#
+                        )
+                        .bind(window.as_str())
+                        .bind(user_pubkey)
+                        .bind(user_performance.positions_count)
+                        .bind(user_performance.cost_basis_usd)
+                        .bind(user_performance.pnl_abs_usd)
+                        .bind(user_performance.pnl_pct())
+                        .bind(computed_at)
+                        .execute(&mut *tx)
+                        .await?;
+                }
+
+                tx.commit().await
+            }.await;
+
+            match result {
+                Ok(_) => Ok(()),
+                Err(e) => {
+                    println!("Error replacing leaderboard entries for window: {}. Error: {}", window.as_str(), e);
+                    Err(ApiError::LeaderboardUpdateFail)
+                }
+            }
+        }.boxed()
+    }
+
+    fn get_leaderboard(&self, params: LeaderboardParams) -> BoxFuture<'_, Result<Leaderboard>> {
+        async move {
+            println!("->> {:<12} - get_leaderboard", "CONTROLLER");
+
+            let order_by = match params.metric {
+                LeaderboardMetric::PnlPct => "pnl_pct DESC, pnl_abs_usd DESC",
+                LeaderboardMetric::PnlAbs => "pnl_abs_usd DESC, pnl_pct DESC",
+            };
+
+            let result = sqlx::query_as::<_, LeaderboardEntry>(
+                    &format!(
+                        r#"SELECT user_pubkey, positions_count, cost_basis_usd, pnl_abs_usd, pnl_pct, computed_at
+                        FROM leaderboard_entries
+                        WHERE time_window = $1
+                        ORDER BY {}, user_pubkey
+                        LIMIT $2"#,
+                        order_by
+                    )
+                )
+                .bind(params.window.as_str())
+                .bind(params.limit())
+                .fetch_all(&self.db)
+                .await;
+
+            match result {
+                Ok(entries) => Ok(Leaderboard {
+                    window: params.window,
+                    entries: entries.into_iter()
+                        .enumerate()
+                        .map(|(index, entry)| RankedLeaderboardEntry { rank: index + 1, entry })
+                        .collect()
+                }),
+                Err(e) => {
+                    println!("Error fetching leaderboard. Error: {}", e);
+                    Err(ApiError::LeaderboardGetFail)
+                }
+            }
+        }.boxed()
+    }
+}
@@ -0,0 +1,227 @@
+use futures::{future::BoxFuture, FutureExt};
+use sqlx::PgPool;
+use uuid::Uuid;
+use crate::{
+    errors::api_errors::{ApiError, Result},
+    models::model_position::{Position, PositionForCreate, UniquePositionsData, UpdatePositionData}
+};
+
+/// Storage for user positions, reached through `AppState::position_repo`.
+/// Trades write positions inside their own transaction through `Position::insert_position` instead.
+pub trait PositionRepo: Send + Sync {
+    fn create_position(&self, position: PositionForCreate) -> BoxFuture<'_, Result<Position>>;
+
+    fn update_position_quantity(&self, update_data: UpdatePositionData) -> BoxFuture<'_, Result<Position>>;
+
+    fn get_positions(&self) -> BoxFuture<'_, Result<Vec<Position>>>;
+
+    fn get_position<'a>(&'a self, position_id: &'a Uuid) -> BoxFuture<'a, Result<Option<Position>>>;
+
+    fn get_user_positions<'a>(&'a self, user_pubkey: &'a str) -> BoxFuture<'a, Result<Vec<Position>>>;
+
+    fn get_user_positions_by_token<'a>(
+        &'a self,
+        user_pubkey: &'a str,
+        token_pubkey: &'a str
+    ) -> BoxFuture<'a, Result<Vec<Position>>>;
+
+    fn get_token_positions<'a>(&'a self, token_pubkey: &'a str) -> BoxFuture<'a, Result<Vec<Position>>>;
+
+    /// One row per (token, vs token) pair the user holds, taken from the pair's oldest position.
+    fn get_user_unique_tokens_and_vs_tokens<'a>(&'a self, user_pubkey: &'a str) -> BoxFuture<'a, Result<Vec<UniquePositionsData>>>;
+}
+
+pub struct PgPositionRepo {
+    db: PgPool
+}
+
+impl PgPositionRepo {
+    pub fn new(db: PgPool) -> Self {
+        Self { db }
+    }
+}
+
+// CRUD implementation for Position
+
+impl PositionRepo for PgPositionRepo {
+    fn create_position(&self, position: PositionForCreate) -> BoxFuture<'_, Result<Position>> {
+        async move {
+            println!("->> {:<12} - create_position", "CONTROLLER");
+
+            let result = Position::insert_position(position, &self.db).await;
+
+            match result {
+                Ok(position) => Ok(position),
+                Err(e) => {
+                    println!("Error creating position. Error: {}", e);
+                    Err(ApiError::PositionCreateFail)
+                }
+            }
+        }.boxed()
+    }
+
+    fn update_position_quantity(&self, update_data: UpdatePositionData) -> BoxFuture<'_, Result<Position>> {
+        async move {
+            println!("->> {:<12} - update_position", "CONTROLLER");
+
+            let result = sqlx::query_as::<_, Position>(
+                    "UPDATE positions SET current_quantity = $1 WHERE id = $2 RETURNING *"
+                )
+                .bind(update_data.new_quantity)
+                .bind(update_data.position_id)
+                .fetch_one(&self.db)
+                .await;
+
+            match result {
+                Ok(position) => Ok(position),
+                Err(e) => {
+                    println!("Error updating position with id: {}. Error: {}",update_data.position_id, e);
+                    Err(ApiError::PositionGetFail)
+                }
+            }
+        }.boxed()
+    }
+
+    fn get_positions(&self) -> BoxFuture<'_, Result<Vec<Position>>> {
+        async move {
+            println!("->> {:<12} - get_positions", "CONTROLLER");
+
+            let result = sqlx::query_as::<_, Position>(
+                    "SELECT * FROM positions;"
+                )
+                .fetch_all(&self.db)
+                .await;
+
+            match result {
+                Ok(positions) => Ok(positions),
+                Err(e) => {
+                    println!("Error fetching positions. Error: {}", e);
+                    Err(ApiError::PositionGetFail)
+                }
+            }
+        }.boxed()
+    }
+
+    fn get_position<'a>(&'a self, position_id: &'a Uuid) -> BoxFuture<'a, Result<Option<Position>>> {
+        async move {
+            println!("->> {:<12} - get_position", "CONTROLLER");
+
+            let result = sqlx::query_as::<_, Position>(
+                    "SELECT * FROM positions WHERE id = $1;"
+                )
+                .bind(position_id)
+                .fetch_optional(&self.db)
+                .await;
+
+            match result {
+                Ok(position) => Ok(position),
+                Err(e) => {
+                    println!("Error fetching position with id: {}. Error: {}", position_id, e);
+                    Err(ApiError::PositionGetFail)
+                }
+            }
+        }.boxed()
+    }
+
+    fn get_user_positions<'a>(&'a self, user_pubkey: &'a str) -> BoxFuture<'a, Result<Vec<Position>>> {
+        async move {
+            println!("->> {:<12} - get_user_positions", "CONTROLLER");
+
+            let result = sqlx::query_as::<_, Position>(
+                    "SELECT * FROM positions WHERE user_pubkey = $1;"
+                )
+                .bind(user_pubkey)
+                .fetch_all(&self.db)
+                .await;
+
+            match result {
+                Ok(positions) => Ok(positions),
+                Err(e) => {
+                    println!("Error fetching positions for user: {}. Error: {}", user_pubkey, e);
+                    Err(ApiError::PositionGetFail)
+                }
+            }
+        }.boxed()
+    }
+
+    fn get_user_positions_by_token<'a>(
+        &'a self,
+        user_pubkey: &'a str,
+        token_pubkey: &'a str
+    ) -> BoxFuture<'a, Result<Vec<Position>>> {
+        async move {
+            println!("->> {:<12} - get_user_positions_by_token", "CONTROLLER");
+
+            let result = sqlx::query_as::<_, Position>(
+                    "SELECT * FROM positions WHERE user_pubkey = $1 AND token_pubkey = $2;"
+                )
+                .bind(user_pubkey)
+                .bind(token_pubkey)
+                .fetch_all(&self.db)
+                .await;
+
+            match result {
+                Ok(positions) => Ok(positions),
+                Err(e) => {
+                    println!(
+                        "Error fetching positions for user: {}, mint: {}. Error: {}",
+                        user_pubkey,
+                        token_pubkey,
+                        e
+                    );
+                    Err(ApiError::PositionGetFail)
+                }
+            }
+        }.boxed()
+    }
+
+    fn get_token_positions<'a>(&'a self, token_pubkey: &'a str) -> BoxFuture<'a, Result<Vec<Position>>> {
+        async move {
+            println!("->> {:<12} - get_token_positions", "CONTROLLER");
+
+            let result = sqlx::query_as::<_, Position>(
+                    "SELECT * FROM positions WHERE token_pubkey = $1;"
+                )
+                .bind(token_pubkey)
+                .fetch_all(&self.db)
+                .await;
+
+            match result {
+                Ok(positions) => Ok(positions),
+                Err(e) => {
+                    println!(
+                        "Error fetching positions for mint: {}. Error: {}",
+                        token_pubkey,
+                        e
+                    );
+                    Err(ApiError::PositionGetFail)
+                }
+            }
+        }.boxed()
+    }
+
+    fn get_user_unique_tokens_and_vs_tokens<'a>(&'a self, user_pubkey: &'a str) -> BoxFuture<'a, Result<Vec<UniquePositionsData>>> {
+        async move {
+            println!("->> {:<12} - get_unique_positions_pubkey_and_vs_token", "CONTROLLER");
+
+            let result = sqlx::query_as::<_, UniquePositionsData>(
+                    "SELECT DISTINCT ON (token_pubkey, vs_token_symbol) token_pubkey, vs_token_pubkey, vs_token_symbol FROM positions WHERE user_pubkey = $1 ORDER BY token_pubkey, vs_token_symbol, created_at;"
+                )
+                .bind(user_pubkey)
+                .fetch_all(&self.db)
+                .await;
+
+            match result {
+                Ok(unique_positions) => Ok(unique_positions),
+                Err(e) => {
+                    println!(
+                        "Error fetching unique positions for user: {}. Error: {}",
+                        user_pubkey,
+                        e
+                    );
+                    Err(ApiError::PositionGetFail)
+                }
+            }
+        }.boxed()
+    }
+}
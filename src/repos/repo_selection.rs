@@ -0,0 +1,237 @@
+use chrono::NaiveDate;
+use futures::{future::BoxFuture, FutureExt};
+use sqlx::PgPool;
+use crate::{
+    errors::api_errors::{ApiError, Result},
+    models::model_selection::{SelectedToken, SelectionConfig, SelectionConfigForUpdate, SelectionRun, SelectionRunForCreate, SelectionRunWithTokens}
+};
+
+const SELECTION_CONFIG_COLUMNS: &str = r#"SELECT min_market_cap_usd, min_liquidity_usd, min_trades_24h, selection_size, page_count, sort_by,
+    max_risk_score, max_top10_holder_share, max_creator_share, updated_at"#;
+
+/// Storage for the coin selector's config and the runs it archives, reached through `AppState::selection_repo`.
+pub trait SelectionRepo: Send + Sync {
+    /// Stores `defaults` unless a config already exists, so edits made through the API survive restarts.
+    fn ensure_selection_config(&self, defaults: SelectionConfigForUpdate) -> BoxFuture<'_, Result<()>>;
+
+    fn get_selection_config(&self) -> BoxFuture<'_, Result<SelectionConfig>>;
+
+    /// Applies from the next selector run on.
+    fn update_selection_config(&self, config: SelectionConfigForUpdate) -> BoxFuture<'_, Result<SelectionConfig>>;
+
+    /// Archives the set of coins chosen by a selector run together with the config that produced it.
+    fn create_selection_run(&self, run: SelectionRunForCreate) -> BoxFuture<'_, Result<SelectionRun>>;
+
+    /// Every run on `date`, latest first. Each set stayed spinnable until the next run replaced it,
+    /// so a day without runs returns the latest earlier run, which was still in place.
+    fn get_selections(&self, date: NaiveDate) -> BoxFuture<'_, Result<Vec<SelectionRunWithTokens>>>;
+}
+
+pub struct PgSelectionRepo {
+    db: PgPool
+}
+
+impl PgSelectionRepo {
+    pub fn new(db: PgPool) -> Self {
+        Self { db }
+    }
+}
+
+// CRUD implementation for SelectionConfig and SelectionRun
+
+impl SelectionRepo for PgSelectionRepo {
+    fn ensure_selection_config(&self, defaults: SelectionConfigForUpdate) -> BoxFuture<'_, Result<()>> {
+        async move {
+            println!("->> {:<12} - ensure_selection_config", "CONTROLLER");
+
+            defaults.validate()?;
+
+            let result = sqlx::query(
+                    r#"INSERT INTO selection_config (min_market_cap_usd, min_liquidity_usd, min_trades_24h, selection_size, page_count, sort_by, max_risk_score, max_top10_holder_share, max_creator_share)
+                    VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9)
+                    ON CONFLICT (id) DO NOTHING"#
+                )
+                .bind(defaults.min_market_cap_usd)
+                .bind(defaults.min_liquidity_usd)
+                .bind(defaults.min_trades_24h)
+                .bind(defaults.selection_size)
+                .bind(defaults.page_count)
+                .bind(defaults.sort_by.as_str())
+                .bind(defaults.max_risk_score)
+                .bind(defaults.max_top10_holder_share)
+                .bind(defaults.max_creator_share)
+                .execute(&self.db)
+                .await;
+
+            match result {
+                Ok(_) => Ok(()),
+                Err(e) => {
+                    println!("Error storing default selection config. Error: {}", e);
+                    Err(ApiError::SelectionConfigUpdateFail)
+                }
+            }
+        }.boxed()
+    }
+
+    fn get_selection_config(&self) -> BoxFuture<'_, Result<SelectionConfig>> {
+        async move {
+            println!("->> {:<12} - get_selection_config", "CONTROLLER");
+
+            let result = sqlx::query_as::<_, SelectionConfig>(
+                    &format!("{} FROM selection_config", SELECTION_CONFIG_COLUMNS)
+                )
+                .fetch_one(&self.db)
+                .await;
+
+            match result {
+                Ok(config) => Ok(config),
+                Err(e) => {
+                    println!("Error fetching selection config. Error: {}", e);
+                    Err(ApiError::SelectionConfigGetFail)
+                }
+            }
+        }.boxed()
+    }
+
+    fn update_selection_config(&self, config: SelectionConfigForUpdate) -> BoxFuture<'_, Result<SelectionConfig>> {
+        async move {
+            println!("->> {:<12} - update_selection_config", "CONTROLLER");
+
+            config.validate()?;
+
+            let result = sqlx::query_as::<_, SelectionConfig>(
+                    r#"UPDATE selection_config
+                    SET min_market_cap_usd = $1, min_liquidity_usd = $2, min_trades_24h = $3, selection_size = $4, page_count = $5, sort_by = $6,
+                        max_risk_score = $7, max_top10_holder_share = $8, max_creator_share = $9, updated_at = CURRENT_TIMESTAMP
+                    RETURNING min_market_cap_usd, min_liquidity_usd, min_trades_24h, selection_size, page_count, sort_by,
+                        max_risk_score, max_top10_holder_share, max_creator_share, updated_at"#
+                )
+                .bind(config.min_market_cap_usd)
+                .bind(config.min_liquidity_usd)
+                .bind(config.min_trades_24h)
+                .bind(config.selection_size)
+                .bind(config.page_count)
+                .bind(config.sort_by.as_str())
+                .bind(config.max_risk_score)
+                .bind(config.max_top10_holder_share)
+                .bind(config.max_creator_share)
+                .fetch_one(&self.db)
+                .await;
+
+            match result {
+                Ok(config) => Ok(config),
+                Err(e) => {
+                    println!("Error updating selection config. Error: {}", e);
+                    Err(ApiError::SelectionConfigUpdateFail)
+                }
+            }
+        }.boxed()
+    }
+
+    fn create_selection_run(&self, run: SelectionRunForCreate) -> BoxFuture<'_, Result<SelectionRun>> {
+        async move {
+            println!("->> {:<12} - create_selection_run", "CONTROLLER");
+
+            let result: sqlx::Result<SelectionRun> = async {
+                let mut tx = self.db.begin().await?;
+
+                let selection_run = sqlx::query_as::<_, SelectionRun>(
+                        r#"INSERT INTO selection_runs
+                        (run_date, min_market_cap_usd, min_liquidity_usd, min_trades_24h, selection_size, page_count, sort_by, max_risk_score, max_top10_holder_share, max_creator_share)
+                        VALUES ((CURRENT_TIMESTAMP AT TIME ZONE 'UTC')::DATE, $1, $2, $3, $4, $5, $6, $7, $8, $9)
+                        RETURNING *"#
+                    )
+                    .bind(run.config.min_market_cap_usd)
+                    .bind(run.config.min_liquidity_usd)
+                    .bind(run.config.min_trades_24h)
+                    .bind(run.config.selection_size)
+                    .bind(run.config.page_count)
+                    .bind(&run.config.sort_by)
+                    .bind(run.config.max_risk_score)
+                    .bind(run.config.max_top10_holder_share)
+                    .bind(run.config.max_creator_share)
+                    .fetch_one(&mut *tx)
+                    .await?;
+
+                for token in &run.tokens {
+                    sqlx::query(
+                            r#"INSERT INTO selected_tokens (run_id, mint_pubkey, symbol, name, logo_url, price_change_24h_percent, volume_24h_usd, risk_score)
+                            VALUES ($1, $2, $3, $4, $5, $6, $7, $8)"#
+                        )
+                        .bind(selection_run.id)
+                        .bind(&token.mint_pubkey)
+                        .bind(&token.symbol)
+                        .bind(&token.name)
+                        .bind(&token.logo_url)
+                        .bind(token.price_change_24h_percent)
+                        .bind(token.volume_24h_usd)
+                        .bind(token.risk_score)
+                        .execute(&mut *tx)
+                        .await?;
+                }
+
+                tx.commit().await?;
+
+                Ok(selection_run)
+            }.await;
+
+            match result {
+                Ok(selection_run) => Ok(selection_run),
+                Err(e) => {
+                    println!("Error archiving selection run. Error: {}", e);
+                    Err(ApiError::SelectionCreateFail)
+                }
+            }
+        }.boxed()
+    }
+
+    fn get_selections(&self, date: NaiveDate) -> BoxFuture<'_, Result<Vec<SelectionRunWithTokens>>> {
+        async move {
+            println!("->> {:<12} - get_selections", "CONTROLLER");
+
+            let result: sqlx::Result<Vec<SelectionRunWithTokens>> = async {
+                let mut runs = sqlx::query_as::<_, SelectionRun>(
+                        "SELECT * FROM selection_runs WHERE run_date = $1 ORDER BY created_at DESC"
+                    )
+                    .bind(date)
+                    .fetch_all(&self.db)
+                    .await?;
+
+                if runs.is_empty() {
+                    runs = sqlx::query_as::<_, SelectionRun>(
+                            "SELECT * FROM selection_runs WHERE run_date < $1 ORDER BY run_date DESC, created_at DESC LIMIT 1"
+                        )
+                        .bind(date)
+                        .fetch_all(&self.db)
+                        .await?;
+                }
+
+                let mut selections = Vec::with_capacity(runs.len());
+
+                for run in runs {
+                    let tokens = sqlx::query_as::<_, SelectedToken>(
+                            r#"SELECT mint_pubkey, symbol, name, logo_url, price_change_24h_percent, volume_24h_usd, risk_score
+                            FROM selected_tokens
+                            WHERE run_id = $1
+                            ORDER BY volume_24h_usd DESC"#
+                        )
+                        .bind(run.id)
+                        .fetch_all(&self.db)
+                        .await?;
+
+                    selections.push(SelectionRunWithTokens { run, tokens });
+                }
+
+                Ok(selections)
+            }.await;
+
+            match result {
+                Ok(selections) => Ok(selections),
+                Err(e) => {
+                    println!("Error fetching selections for date: {}. Error: {}", date, e);
+                    Err(ApiError::SelectionGetFail)
+                }
+            }
+        }.boxed()
+    }
+}
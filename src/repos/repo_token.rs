@@ -0,0 +1,243 @@
+use futures::{future::BoxFuture, FutureExt};
+use sqlx::PgPool;
+use crate::{errors::api_errors::{ApiError, Result}, models::model_token::{Token, TokenForCreate}};
+
+/// Storage for the spinnable tokens, reached through `AppState::token_repo`.
+pub trait TokenRepo: Send + Sync {
+    fn create_token(&self, token: TokenForCreate) -> BoxFuture<'_, Result<Token>>;
+
+    fn get_tokens(&self) -> BoxFuture<'_, Result<Vec<Token>>>;
+
+    fn get_token<'a>(&'a self, mint_pubkey: &'a str) -> BoxFuture<'a, Result<Option<Token>>>;
+
+    fn get_all_active_tokens(&self) -> BoxFuture<'_, Result<Vec<Token>>>;
+
+    /// The 7 active tokens with the most 24h volume.
+    fn get_7_active_tokens(&self) -> BoxFuture<'_, Result<Vec<Token>>>;
+
+    fn update_token_state<'a>(&'a self, mint_pubkey: &'a str, is_active: bool) -> BoxFuture<'a, Result<()>>;
+
+    fn update_token_risk_score<'a>(&'a self, mint_pubkey: &'a str, risk_score: i32) -> BoxFuture<'a, Result<()>>;
+
+    fn update_token_financial_data<'a>(
+        &'a self,
+        mint_pubkey: &'a str,
+        price_change_24h_percent: f64,
+        volume_24h_usd: f64,
+        decimals: i32
+    ) -> BoxFuture<'a, Result<()>>;
+}
+
+pub struct PgTokenRepo {
+    db: PgPool
+}
+
+impl PgTokenRepo {
+    pub fn new(db: PgPool) -> Self {
+        Self { db }
+    }
+}
+
+// CRUD implementation for Token
+
+impl TokenRepo for PgTokenRepo {
+    fn create_token(&self, token: TokenForCreate) -> BoxFuture<'_, Result<Token>> {
+        async move {
+            println!("->> {:<12} - create_token", "CONTROLLER");
+
+            let result = sqlx::query_as::<_, Token>(
+                    r#"INSERT INTO tokens
+                    (mint_pubkey, symbol, name, logo_url, price_change_24h_percent, volume_24h_usd, discord_url, twitter_url, website_url, telegram_url, decimals, is_active, risk_score)
+                    VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12, $13)
+                    RETURNING *"#
+                )
+                .bind(token.mint_pubkey)
+                .bind(token.symbol)
+                .bind(token.name)
+                .bind(token.logo_url)
+                .bind(token.price_change_24h_percent)
+                .bind(token.volume_24h_usd)
+                .bind(token.discord_url)
+                .bind(token.twitter_url)
+                .bind(token.website_url)
+                .bind(token.telegram_url)
+                .bind(token.decimals)
+                .bind(token.is_active)
+                .bind(token.risk_score)
+                .fetch_one(&self.db)
+                .await;
+
+            match result {
+                Ok(token) => Ok(token),
+                Err(e) => {
+                    println!("Error creating token. Error: {}", e);
+                    Err(ApiError::TokenCreateFail)
+                }
+            }
+        }.boxed()
+    }
+
+    fn get_tokens(&self) -> BoxFuture<'_, Result<Vec<Token>>> {
+        async move {
+            println!("->> {:<12} - get_tokens", "CONTROLLER");
+
+            let result = sqlx::query_as::<_, Token>(
+                    "SELECT * FROM tokens"
+                )
+                .fetch_all(&self.db)
+                .await;
+
+            match result {
+                Ok(tokens) => Ok(tokens),
+                Err(e) => {
+                    println!("Error fetching tokens. Error: {}", e);
+                    Err(ApiError::TokenGetFail)
+                }
+            }
+        }.boxed()
+    }
+
+    fn get_token<'a>(&'a self, mint_pubkey: &'a str) -> BoxFuture<'a, Result<Option<Token>>> {
+        async move {
+            println!("->> {:<12} - get_token", "CONTROLLER");
+
+            let result = sqlx::query_as::<_, Token>(
+                    "SELECT * FROM tokens WHERE mint_pubkey = $1"
+                )
+                .bind(mint_pubkey)
+                .fetch_optional(&self.db)
+                .await;
+
+            match result {
+                Ok(result) => Ok(result),
+                Err(e) => {
+                    println!("Error fetching token. Error: {}", e);
+                    Err(ApiError::TokenGetFail)
+                }
+            }
+        }.boxed()
+    }
+
+    fn get_all_active_tokens(&self) -> BoxFuture<'_, Result<Vec<Token>>> {
+        async move {
+            println!("->> {:<12} - get_all_active_tokens", "CONTROLLER");
+
+            let result = sqlx::query_as::<_, Token>(
+                    "SELECT * FROM tokens WHERE is_active = true"
+                )
+                .fetch_all(&self.db)
+                .await;
+
+            match result {
+                Ok(tokens) => Ok(tokens),
+                Err(e) => {
+                    println!("Error fetching active tokens. Error: {}", e);
+                    Err(ApiError::TokenGetFail)
+                }
+            }
+        }.boxed()
+    }
+
+    fn get_7_active_tokens(&self) -> BoxFuture<'_, Result<Vec<Token>>> {
+        async move {
+            println!("->> {:<12} - get_7_active_tokens", "CONTROLLER");
+
+            let result = sqlx::query_as::<_, Token>(
+                    r#"SELECT *
+                    FROM tokens
+                    WHERE is_active = true
+                    ORDER BY volume_24h_usd DESC
+                    LIMIT 7"#
+                )
+                .fetch_all(&self.db)
+                .await;
+
+            match result {
+                Ok(tokens) => Ok(tokens),
+                Err(e) => {
+                    println!("Error fetching active tokens. Error: {}", e);
+                    Err(ApiError::TokenGetFail)
+                }
+            }
+        }.boxed()
+    }
+
+    fn update_token_state<'a>(&'a self, mint_pubkey: &'a str, is_active: bool) -> BoxFuture<'a, Result<()>> {
+        async move {
+            println!("->> {:<12} - update_token_state", "CONTROLLER");
+
+            let result = sqlx::query(
+                    "UPDATE tokens SET is_active = $1 WHERE mint_pubkey = $2"
+                )
+                .bind(is_active)
+                .bind(mint_pubkey)
+                .execute(&self.db)
+                .await;
+
+            match result {
+                Ok(_) => Ok(()),
+                Err(e) => {
+                    println!("Error updating token is_active column. Error: {}", e);
+                    Err(ApiError::TokenUpdateFail)
+                }
+            }
+        }.boxed()
+    }
+
+    fn update_token_risk_score<'a>(&'a self, mint_pubkey: &'a str, risk_score: i32) -> BoxFuture<'a, Result<()>> {
+        async move {
+            println!("->> {:<12} - update_token_risk_score", "CONTROLLER");
+
+            let result = sqlx::query(
+                    "UPDATE tokens SET risk_score = $1 WHERE mint_pubkey = $2"
+                )
+                .bind(risk_score)
+                .bind(mint_pubkey)
+                .execute(&self.db)
+                .await;
+
+            match result {
+                Ok(_) => Ok(()),
+                Err(e) => {
+                    println!("Error updating token risk score. Error: {}", e);
+                    Err(ApiError::TokenUpdateFail)
+                }
+            }
+        }.boxed()
+    }
+
+    fn update_token_financial_data<'a>(
+        &'a self,
+        mint_pubkey: &'a str,
+        price_change_24h_percent: f64,
+        volume_24h_usd: f64,
+        decimals: i32
+    ) -> BoxFuture<'a, Result<()>> {
+        async move {
+            println!("->> {:<12} - update_token_financial_data", "CONTROLLER");
+
+            let result = sqlx::query(
+                    r#"UPDATE tokens
+                    SET
+                        price_change_24h_percent = $1,
+                        volume_24h_usd = $2,
+                        decimals = $3
+                    WHERE mint_pubkey = $4"#
+                )
+                .bind(price_change_24h_percent)
+                .bind(volume_24h_usd)
+                .bind(decimals)
+                .bind(mint_pubkey)
+                .execute(&self.db)
+                .await;
+
+            match result {
+                Ok(_) => Ok(()),
+                Err(e) => {
+                    println!("Error updating token financial data. Error: {}", e);
+                    Err(ApiError::TokenUpdateFail)
+                }
+            }
+        }.boxed()
+    }
+}
@@ -0,0 +1,166 @@
+use futures::{future::BoxFuture, FutureExt};
+use sqlx::PgPool;
+use uuid::Uuid;
+use crate::{
+    errors::api_errors::{ApiError, Result},
+    models::model_token_override::{TokenOverride, TokenOverrideForCreate, TokenOverrideForUpdate}
+};
+
+/// Storage for the admin overrides the coin selector applies, reached through `AppState::token_override_repo`.
+pub trait TokenOverrideRepo: Send + Sync {
+    /// A mint has at most one override, a second one is rejected rather than merged.
+    fn create_token_override(&self, token_override: TokenOverrideForCreate) -> BoxFuture<'_, Result<TokenOverride>>;
+
+    /// Every override, expired ones included.
+    fn get_token_overrides(&self) -> BoxFuture<'_, Result<Vec<TokenOverride>>>;
+
+    fn get_active_token_overrides(&self) -> BoxFuture<'_, Result<Vec<TokenOverride>>>;
+
+    fn update_token_override<'a>(
+        &'a self,
+        override_id: &'a Uuid,
+        token_override: TokenOverrideForUpdate
+    ) -> BoxFuture<'a, Result<Option<TokenOverride>>>;
+
+    fn delete_token_override<'a>(&'a self, override_id: &'a Uuid) -> BoxFuture<'a, Result<Option<TokenOverride>>>;
+}
+
+pub struct PgTokenOverrideRepo {
+    db: PgPool
+}
+
+impl PgTokenOverrideRepo {
+    pub fn new(db: PgPool) -> Self {
+        Self { db }
+    }
+}
+
+// CRUD implementation for TokenOverride
+
+impl TokenOverrideRepo for PgTokenOverrideRepo {
+    fn create_token_override(&self, token_override: TokenOverrideForCreate) -> BoxFuture<'_, Result<TokenOverride>> {
+        async move {
+            println!("->> {:<12} - create_token_override", "CONTROLLER");
+
+            token_override.validate()?;
+
+            let result = sqlx::query_as::<_, TokenOverride>(
+                    r#"INSERT INTO token_overrides (mint_pubkey, kind, reason, expires_at)
+                    VALUES ($1, $2, $3, $4)
+                    ON CONFLICT (mint_pubkey) DO NOTHING
+                    RETURNING *"#
+                )
+                .bind(token_override.mint_pubkey.trim())
+                .bind(token_override.kind.as_str())
+                .bind(token_override.reason.trim())
+                .bind(token_override.expires_at)
+                .fetch_optional(&self.db)
+                .await;
+
+            match result {
+                Ok(Some(token_override)) => Ok(token_override),
+                Ok(None) => Err(ApiError::TokenOverrideExists),
+                Err(e) => {
+                    println!("Error creating token override for mint: {}. Error: {}", token_override.mint_pubkey, e);
+                    Err(ApiError::TokenOverrideCreateFail)
+                }
+            }
+        }.boxed()
+    }
+
+    fn get_token_overrides(&self) -> BoxFuture<'_, Result<Vec<TokenOverride>>> {
+        async move {
+            println!("->> {:<12} - get_token_overrides", "CONTROLLER");
+
+            let result = sqlx::query_as::<_, TokenOverride>(
+                    "SELECT * FROM token_overrides ORDER BY kind, created_at DESC"
+                )
+                .fetch_all(&self.db)
+                .await;
+
+            match result {
+                Ok(token_overrides) => Ok(token_overrides),
+                Err(e) => {
+                    println!("Error fetching token overrides. Error: {}", e);
+                    Err(ApiError::TokenOverrideGetFail)
+                }
+            }
+        }.boxed()
+    }
+
+    fn get_active_token_overrides(&self) -> BoxFuture<'_, Result<Vec<TokenOverride>>> {
+        async move {
+            println!("->> {:<12} - get_active_token_overrides", "CONTROLLER");
+
+            let result = sqlx::query_as::<_, TokenOverride>(
+                    r#"SELECT * FROM token_overrides
+                    WHERE expires_at IS NULL OR expires_at > NOW()
+                    ORDER BY created_at"#
+                )
+                .fetch_all(&self.db)
+                .await;
+
+            match result {
+                Ok(token_overrides) => Ok(token_overrides),
+                Err(e) => {
+                    println!("Error fetching active token overrides. Error: {}", e);
+                    Err(ApiError::TokenOverrideGetFail)
+                }
+            }
+        }.boxed()
+    }
+
+    fn update_token_override<'a>(
+        &'a self,
+        override_id: &'a Uuid,
+        token_override: TokenOverrideForUpdate
+    ) -> BoxFuture<'a, Result<Option<TokenOverride>>> {
+        async move {
+            println!("->> {:<12} - update_token_override", "CONTROLLER");
+
+            token_override.validate()?;
+
+            let result = sqlx::query_as::<_, TokenOverride>(
+                    r#"UPDATE token_overrides
+                    SET kind = $2, reason = $3, expires_at = $4, updated_at = CURRENT_TIMESTAMP
+                    WHERE id = $1
+                    RETURNING *"#
+                )
+                .bind(override_id)
+                .bind(token_override.kind.as_str())
+                .bind(token_override.reason.trim())
+                .bind(token_override.expires_at)
+                .fetch_optional(&self.db)
+                .await;
+
+            match result {
+                Ok(token_override) => Ok(token_override),
+                Err(e) => {
+                    println!("Error updating token override: {}. Error: {}", override_id, e);
+                    Err(ApiError::TokenOverrideUpdateFail)
+                }
+            }
+        }.boxed()
+    }
+
+    fn delete_token_override<'a>(&'a self, override_id: &'a Uuid) -> BoxFuture<'a, Result<Option<TokenOverride>>> {
+        async move {
+            println!("->> {:<12} - delete_token_override", "CONTROLLER");
+
+            let result = sqlx::query_as::<_, TokenOverride>(
+                    "DELETE FROM token_overrides WHERE id = $1 RETURNING *"
+                )
+                .bind(override_id)
+                .fetch_optional(&self.db)
+                .await;
+
+            match result {
+                Ok(token_override) => Ok(token_override),
+                Err(e) => {
+                    println!("Error deleting token override: {}. Error: {}", override_id, e);
+                    Err(ApiError::TokenOverrideUpdateFail)
+                }
+            }
+        }.boxed()
+    }
+}
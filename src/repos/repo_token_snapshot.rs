@@ -0,0 +1,100 @@
+use chrono::{DateTime, Utc};
+use futures::{future::BoxFuture, FutureExt};
+use sqlx::PgPool;
+use crate::{
+    errors::api_errors::{ApiError, Result},
+    models::model_token_snapshot::{SnapshotForCreate, TokenPriceSnapshot}
+};
+
+/// Storage for the price history the token updater records, reached through `AppState::snapshot_repo`.
+pub trait SnapshotRepo: Send + Sync {
+    /// Stores a whole run's snapshots or none of them, so a run that fails and is retried
+    /// doesn't leave a partial set behind to be duplicated.
+    fn create_snapshots(&self, snapshots: Vec<SnapshotForCreate>) -> BoxFuture<'_, Result<()>>;
+
+    /// Snapshots of a mint taken in `[from, to)`, oldest first.
+    fn get_snapshots<'a>(
+        &'a self,
+        mint_pubkey: &'a str,
+        from: DateTime<Utc>,
+        to: DateTime<Utc>
+    ) -> BoxFuture<'a, Result<Vec<TokenPriceSnapshot>>>;
+}
+
+pub struct PgSnapshotRepo {
+    db: PgPool
+}
+
+impl PgSnapshotRepo {
+    pub fn new(db: PgPool) -> Self {
+        Self { db }
+    }
+}
+
+// CRUD implementation for TokenPriceSnapshot
+
+impl SnapshotRepo for PgSnapshotRepo {
+    fn create_snapshots(&self, snapshots: Vec<SnapshotForCreate>) -> BoxFuture<'_, Result<()>> {
+        async move {
+            println!("->> {:<12} - create_snapshots", "CONTROLLER");
+
+            let result: sqlx::Result<()> = async {
+                let mut tx = self.db.begin().await?;
+
+                for snapshot in &snapshots {
+                    sqlx::query(
+                            "INSERT INTO token_price_snapshots (mint_pubkey, price, volume_24h_usd, liquidity, market_cap) VALUES ($1, $2, $3, $4, $5)"
+                        )
+                        .bind(&snapshot.mint_pubkey)
+                        .bind(snapshot.price)
+                        .bind(snapshot.volume_24h_usd)
+                        .bind(snapshot.liquidity)
+                        .bind(snapshot.market_cap)
+                        .execute(&mut *tx)
+                        .await?;
+                }
+
+                tx.commit().await
+            }.await;
+
+            match result {
+                Ok(_) => Ok(()),
+                Err(e) => {
+                    println!("Error creating {} snapshots. Error: {}", snapshots.len(), e);
+                    Err(ApiError::TokenSnapshotCreateFail)
+                }
+            }
+        }.boxed()
+    }
+
+    fn get_snapshots<'a>(
+        &'a self,
+        mint_pubkey: &'a str,
+        from: DateTime<Utc>,
+        to: DateTime<Utc>
+    ) -> BoxFuture<'a, Result<Vec<TokenPriceSnapshot>>> {
+        async move {
+            println!("->> {:<12} - get_snapshots", "CONTROLLER");
+
+            let result = sqlx::query_as::<_, TokenPriceSnapshot>(
+                    r#"SELECT mint_pubkey, price, volume_24h_usd, liquidity, market_cap, created_at
+                    FROM token_price_snapshots
+                    WHERE mint_pubkey = $1 AND created_at >= $2 AND created_at < $3
+                    ORDER BY created_at"#
+                )
+                .bind(mint_pubkey)
+                .bind(from)
+                .bind(to)
+                .fetch_all(&self.db)
+                .await;
+
+            match result {
+                Ok(snapshots) => Ok(snapshots),
+                Err(e) => {
+                    println!("Error fetching snapshots for mint: {}. Error: {}", mint_pubkey, e);
+                    Err(ApiError::TokenGetFail)
+                }
+            }
+        }.boxed()
+    }
+}
@@ -0,0 +1,115 @@
+use futures::{future::BoxFuture, FutureExt};
+use sqlx::PgPool;
+use crate::{
+    errors::api_errors::{ApiError, Result},
+    models::{model_referral::ReferralCode, model_user::{User, UserForCreate}}
+};
+
+const USER_SELECT: &str = r#"SELECT users.user_pubkey, users.referred_by, COALESCE(points_balances.balance, 0) AS points, users.created_at
+    FROM users
+    LEFT JOIN points_balances ON points_balances.account = users.user_pubkey"#;
+
+/// Storage for users, reached through `AppState::user_repo`.
+pub trait UserRepo: Send + Sync {
+    /// Creates the user, claiming `referral_code` in the same transaction when one is given.
+    fn create_user(&self, user: UserForCreate) -> BoxFuture<'_, Result<User>>;
+
+    fn get_users(&self) -> BoxFuture<'_, Result<Vec<User>>>;
+
+    fn get_user<'a>(&'a self, pubkey: &'a str) -> BoxFuture<'a, Result<Option<User>>>;
+}
+
+pub struct PgUserRepo {
+    db: PgPool
+}
+
+impl PgUserRepo {
+    pub fn new(db: PgPool) -> Self {
+        Self { db }
+    }
+}
+
+// CRUD implementation for User
+
+impl UserRepo for PgUserRepo {
+    fn create_user(&self, user: UserForCreate) -> BoxFuture<'_, Result<User>> {
+        async move {
+            println!("->> {:<12} - create_user", "CONTROLLER");
+
+            let result: sqlx::Result<Result<User>> = async {
+                let mut tx = self.db.begin().await?;
+
+                sqlx::query("INSERT INTO users (user_pubkey) VALUES ($1)")
+                    .bind(&user.user_pubkey)
+                    .execute(&mut *tx)
+                    .await?;
+
+                if let Some(referral_code) = &user.referral_code {
+                    if let Err(e) = ReferralCode::claim_referral(&user.user_pubkey, referral_code, &mut tx).await? {
+                        return Ok(Err(e))
+                    }
+                }
+
+                let created_user = sqlx::query_as::<_, User>(
+                        &format!("{} WHERE users.user_pubkey = $1", USER_SELECT)
+                    )
+                    .bind(&user.user_pubkey)
+                    .fetch_one(&mut *tx)
+                    .await?;
+
+                tx.commit().await?;
+
+                Ok(Ok(created_user))
+            }.await;
+
+            match result {
+                Ok(user) => user,
+                Err(e) => {
+                    println!("Error creating user. Error: {}", e);
+                    Err(ApiError::UserCreateFail)
+                }
+            }
+        }.boxed()
+    }
+
+    fn get_users(&self) -> BoxFuture<'_, Result<Vec<User>>> {
+        async move {
+            println!("->> {:<12} - get_users", "CONTROLLER");
+
+            let result = sqlx::query_as::<_, User>(
+                    USER_SELECT
+                )
+                .fetch_all(&self.db)
+                .await;
+
+            match result {
+                Ok(users) => Ok(users),
+                Err(e) => {
+                    println!("Error fetching users. Error: {}", e);
+                    Err(ApiError::UserGetFail)
+                }
+            }
+        }.boxed()
+    }
+
+    fn get_user<'a>(&'a self, pubkey: &'a str) -> BoxFuture<'a, Result<Option<User>>> {
+        async move {
+            println!("->> {:<12} - get_user", "CONTROLLER");
+
+            let result = sqlx::query_as::<_, User>(
+                &format!("{} WHERE users.user_pubkey = $1", USER_SELECT)
+            )
+            .bind(pubkey)
+            .fetch_optional(&self.db)
+            .await;
+
+            match result {
+                Ok(user) => Ok(user),
+                Err(e) => {
+                    println!("Error fetching user with pubkey {}. Error: {}", pubkey, e);
+                    Err(ApiError::UserGetFail)
+                }
+            }
+        }.boxed()
+    }
+}
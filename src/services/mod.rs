@@ -0,0 +1,2 @@
+pub mod price_service;
+pub mod price_source;
\ No newline at end of file
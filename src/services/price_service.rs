@@ -0,0 +1,245 @@
+use std::{collections::HashMap, sync::{Arc, RwLock}, time::{Duration, Instant}};
+
+use crate::{clients::external_http::UpstreamStats, errors::api_errors::{ApiError, Result}};
+
+use super::price_source::{CompositePriceSource, PriceQuote, VsToken};
+
+/// Jupiter rejects requests with too many ids, so larger batches are split.
+const MAX_IDS_PER_REQUEST: usize = 100;
+
+#[derive(Debug, Clone, Copy)]
+struct CachedPrice {
+    quote: PriceQuote,
+    fetched_at: Instant
+}
+
+/// Price lookups shared by handlers and cron jobs through `AppState`.
+/// Cache misses are fetched from the price sources in one batch per vs token.
+#[derive(Clone)]
+pub struct PriceService {
+    ttl: Duration,
+    price_source: CompositePriceSource,
+    /// Keyed by (token_pubkey, vs_token_pubkey).
+    cache: Arc<RwLock<HashMap<(String, String), CachedPrice>>>
+}
+
+impl PriceService {
+    pub fn new(ttl: Duration, price_source: CompositePriceSource) -> Self {
+        Self {
+            ttl,
+            price_source,
+            cache: Arc::new(RwLock::new(HashMap::new()))
+        }
+    }
+
+    pub fn upstream_stats(&self) -> Vec<UpstreamStats> {
+        self.price_source.stats()
+    }
+}
+
+impl PriceService {
+    /// Quotes for `token_pubkeys` in `vs_token`, served from the cache when fresh.
+    pub async fn get_quotes(
+        &self,
+        token_pubkeys: &[String],
+        vs_token: &VsToken
+    ) -> Result<HashMap<String, PriceQuote>> {
+        println!("->> {:<12} - get_quotes", "SERVICE");
+
+        let mut quotes: HashMap<String, PriceQuote> = HashMap::with_capacity(token_pubkeys.len());
+        let mut missing: Vec<String> = Vec::new();
+
+        {
+            let cache = self.cache.read().expect("price cache lock poisoned");
+
+            for token_pubkey in token_pubkeys {
+                match cache.get(&(token_pubkey.clone(), vs_token.pubkey.clone())) {
+                    Some(cached) if cached.fetched_at.elapsed() < self.ttl => {
+                        quotes.insert(token_pubkey.clone(), cached.quote);
+                    },
+                    _ => {
+                        if !missing.contains(token_pubkey) {
+                            missing.push(token_pubkey.clone());
+                        }
+                    }
+                }
+            }
+        }
+
+        for chunk in missing.chunks(MAX_IDS_PER_REQUEST) {
+            let fetched = self.price_source.get_quotes(chunk, vs_token).await?;
+
+            self.store(&fetched, vs_token);
+
+            quotes.extend(fetched);
+        }
+
+        Ok(quotes)
+    }
+
+    /// Prices of `token_pubkeys` quoted in `vs_token`, served from the cache when fresh.
+    pub async fn get_prices(
+        &self,
+        token_pubkeys: &[String],
+        vs_token: &VsToken
+    ) -> Result<HashMap<String, f64>> {
+        let quotes = self.get_quotes(token_pubkeys, vs_token).await?;
+
+        Ok(quotes.into_iter().map(|(token_pubkey, quote)| (token_pubkey, quote.price)).collect())
+    }
+
+    /// Quotes for (token_pubkey, vs token) pairs, one batch per distinct vs token.
+    /// Keyed by (token_pubkey, vs_token_symbol), the way positions are grouped.
+    pub async fn get_quotes_for_pairs(
+        &self,
+        pairs: &[(String, VsToken)]
+    ) -> Result<HashMap<(String, String), PriceQuote>> {
+        let mut tokens_by_vs_token: HashMap<&VsToken, Vec<String>> = HashMap::new();
+
+        for (token_pubkey, vs_token) in pairs {
+            tokens_by_vs_token
+                .entry(vs_token)
+                .or_default()
+                .push(token_pubkey.clone());
+        }
+
+        let mut quotes: HashMap<(String, String), PriceQuote> = HashMap::with_capacity(pairs.len());
+
+        for (vs_token, token_pubkeys) in tokens_by_vs_token {
+            for (token_pubkey, quote) in self.get_quotes(&token_pubkeys, vs_token).await? {
+                quotes.insert((token_pubkey, vs_token.symbol.clone()), quote);
+            }
+        }
+
+        Ok(quotes)
+    }
+
+    /// Prices for (token_pubkey, vs token) pairs, one batch per distinct vs token.
+    /// Keyed by (token_pubkey, vs_token_symbol), the way positions are grouped.
+    pub async fn get_prices_for_pairs(
+        &self,
+        pairs: &[(String, VsToken)]
+    ) -> Result<HashMap<(String, String), f64>> {
+        let quotes = self.get_quotes_for_pairs(pairs).await?;
+
+        Ok(quotes.into_iter().map(|(pair, quote)| (pair, quote.price)).collect())
+    }
+
+    /// Bypasses the cache. Used where a stale price is not acceptable, like trade execution.
+    pub async fn get_fresh_price(
+        &self,
+        token_pubkey: &str,
+        vs_token: &VsToken
+    ) -> Result<PriceQuote> {
+        println!("->> {:<12} - get_fresh_price", "SERVICE");
+
+        let quotes = self.price_source.get_quotes(&[token_pubkey.to_string()], vs_token).await?;
+
+        self.store(&quotes, vs_token);
+
+        quotes.get(token_pubkey)
+            .copied()
+            .ok_or_else(|| ApiError::PriceUnavailable { mint: token_pubkey.to_string() })
+    }
+
+    fn store(&self, quotes: &HashMap<String, PriceQuote>, vs_token: &VsToken) {
+        let mut cache = self.cache.write().expect("price cache lock poisoned");
+        let fetched_at = Instant::now();
+
+        for (token_pubkey, quote) in quotes {
+            cache.insert(
+                (token_pubkey.clone(), vs_token.pubkey.clone()),
+                CachedPrice { quote: *quote, fetched_at }
+            );
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use crate::{services::price_source::VsToken, test_utils::MockUpstream};
+
+    #[tokio::test]
+    async fn batches_prices_and_leaves_out_unknown_mints() {
+        let upstream = MockUpstream::start().await;
+        let price_service = upstream.price_service();
+
+        let token_pubkeys = vec!["MockMintAlpha".to_string(), "MockMintBeta".to_string(), "UnknownMint".to_string()];
+
+        let quotes = price_service.get_quotes(&token_pubkeys, &VsToken::usd()).await.unwrap();
+
+        assert_eq!(quotes.len(), 2);
+        assert_eq!(quotes["MockMintAlpha"].price, 1.5);
+        assert_eq!(quotes["MockMintBeta"].price, 0.25);
+        assert_eq!(quotes["MockMintBeta"].source, "jupiter_v2");
+        assert_eq!(price_service.upstream_stats()[0].requests, 1);
+    }
+
+    #[tokio::test]
+    async fn caches_fresh_prices_for_later_lookups() {
+        let upstream = MockUpstream::start().await;
+        let price_service = upstream.price_service();
+
+        assert_eq!(price_service.get_fresh_price("MockMintGamma", &VsToken::usd()).await.unwrap().price, 0.02);
+
+        let prices = price_service.get_prices(&["MockMintGamma".to_string()], &VsToken::usd()).await.unwrap();
+
+        assert_eq!(prices["MockMintGamma"], 0.02);
+        assert_eq!(price_service.upstream_stats()[0].requests, 1);
+    }
+
+    #[tokio::test]
+    async fn falls_back_to_birdeye_when_jupiter_fails() {
+        let upstream = MockUpstream::start().await;
+        let price_service = upstream.price_service_with(
+            vec![Arc::new(upstream.broken_jupiter_v2_client()), Arc::new(upstream.birdeye_client())],
+            None
+        );
+
+        let quote = price_service.get_fresh_price("MockMintAlpha", &VsToken::from_symbol("SOL").unwrap()).await.unwrap();
+
+        // Birdeye quotes in USD, the mock prices SOL at 150
+        assert_eq!(quote.source, "birdeye");
+        assert!((quote.price - 0.01).abs() < 1e-12);
+    }
+
+    #[tokio::test]
+    async fn prices_in_vs_tokens_outside_the_known_quote_tokens() {
+        let upstream = MockUpstream::start().await;
+        let price_service = upstream.price_service_with(
+            vec![Arc::new(upstream.broken_jupiter_v2_client()), Arc::new(upstream.birdeye_client())],
+            None
+        );
+
+        // only the stored mint identifies the vs token, its symbol resolves to nothing
+        let quote = price_service.get_fresh_price("MockMintAlpha", &VsToken::new("MockMintBeta", "BETA")).await.unwrap();
+
+        assert_eq!(quote.source, "birdeye");
+        assert!((quote.price - 6.0).abs() < 1e-12);
+    }
+
+    #[tokio::test]
+    async fn cross_checks_jupiter_versions() {
+        let upstream = MockUpstream::start().await;
+        let price_service = upstream.price_service_with(
+            vec![Arc::new(upstream.jupiter_v2_client()), Arc::new(upstream.jupiter_client())],
+            Some(0.01)
+        );
+
+        let quote = price_service.get_fresh_price("MockMintBeta", &VsToken::usd()).await.unwrap();
+
+        assert_eq!(quote.source, "jupiter_v2");
+        assert_eq!(quote.divergence, Some(0.0));
+        assert!(!quote.divergent);
+    }
+
+    #[tokio::test]
+    async fn reports_unpriceable_mints_as_unavailable() {
+        let upstream = MockUpstream::start().await;
+        let price_service = upstream.price_service();
+
+        assert!(price_service.get_fresh_price("UnknownMint", &VsToken::usd()).await.is_err());
+    }
+}
@@ -0,0 +1,461 @@
+use std::{collections::HashMap, sync::Arc};
+
+use futures::{future::{self, BoxFuture}, stream, FutureExt, StreamExt};
+
+use crate::{
+    clients::{client_birdeye::BirdeyeClient, client_jupiter::JupiterClient, client_jupiter_v2::JupiterV2Client, external_http::UpstreamStats},
+    errors::api_errors::{ApiError, Result}
+};
+
+/// Birdeye prices one mint per request, its client's rate limiter still paces them.
+const BIRDEYE_PRICE_CONCURRENCY: usize = 4;
+
+/// Quote tokens a client can name by symbol alone.
+const KNOWN_QUOTE_MINTS: [(&str, &str); 3] = [
+    ("SOL", "So11111111111111111111111111111111111111112"),
+    ("USDC", "EPjFWdd5AufqSSqeM2qN1xzybapC8G4wEGGkZwyTDt1v"),
+    ("USDT", "Es9vMFrzaCERmJfrF4H2FYD4KCoNkY11McCe8BenwNYB"),
+];
+
+/// The token prices are quoted in. Sources price against the mint, only Jupiter v4 takes the symbol.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct VsToken {
+    pub pubkey: String,
+    pub symbol: String
+}
+
+impl VsToken {
+    pub fn new(pubkey: &str, symbol: &str) -> Self {
+        Self { pubkey: pubkey.to_string(), symbol: symbol.to_string() }
+    }
+
+    /// USD prices are quoted against USDC.
+    pub fn usd() -> Self {
+        Self::from_symbol("USDC").expect("USDC is a known quote token")
+    }
+
+    /// A vs token given by symbol, or by mint when the input is one.
+    pub fn from_symbol(vs_token_symbol: &str) -> Option<Self> {
+        if let Some((symbol, mint)) = KNOWN_QUOTE_MINTS.iter().find(|(symbol, _)| symbol.eq_ignore_ascii_case(vs_token_symbol)) {
+            return Some(Self::new(mint, symbol))
+        }
+
+        match bs58::decode(vs_token_symbol).into_vec() {
+            Ok(bytes) if bytes.len() == 32 => Some(Self::new(vs_token_symbol, vs_token_symbol)),
+            _ => None
+        }
+    }
+
+    /// Whether a client supplied mint and symbol name the same token.
+    /// A known quote mint must carry its symbol, and a known symbol its mint.
+    pub fn is_consistent(pubkey: &str, symbol: &str) -> bool {
+        KNOWN_QUOTE_MINTS.iter().all(|(known_symbol, known_mint)| (*known_mint == pubkey) == (*known_symbol == symbol))
+    }
+}
+
+/// Somewhere current token prices can be fetched from.
+pub trait PriceSource: Send + Sync {
+    /// Recorded on trades and shown next to position prices.
+    fn name(&self) -> &'static str;
+
+    fn stats(&self) -> UpstreamStats;
+
+    /// Prices of `token_pubkeys` in `vs_token`. Mints the source can't price are left out of the map.
+    fn get_prices<'a>(
+        &'a self,
+        token_pubkeys: &'a [String],
+        vs_token: &'a VsToken
+    ) -> BoxFuture<'a, Result<HashMap<String, f64>>>;
+}
+
+impl PriceSource for JupiterClient {
+    fn name(&self) -> &'static str {
+        "jupiter_v4"
+    }
+
+    fn stats(&self) -> UpstreamStats {
+        JupiterClient::stats(self)
+    }
+
+    fn get_prices<'a>(
+        &'a self,
+        token_pubkeys: &'a [String],
+        vs_token: &'a VsToken
+    ) -> BoxFuture<'a, Result<HashMap<String, f64>>> {
+        self.get_token_prices(token_pubkeys, &vs_token.symbol).boxed()
+    }
+}
+
+impl PriceSource for JupiterV2Client {
+    fn name(&self) -> &'static str {
+        "jupiter_v2"
+    }
+
+    fn stats(&self) -> UpstreamStats {
+        JupiterV2Client::stats(self)
+    }
+
+    fn get_prices<'a>(
+        &'a self,
+        token_pubkeys: &'a [String],
+        vs_token: &'a VsToken
+    ) -> BoxFuture<'a, Result<HashMap<String, f64>>> {
+        self.get_token_prices(token_pubkeys, &vs_token.pubkey).boxed()
+    }
+}
+
+/// Birdeye only quotes in USD, other vs tokens are priced through their own USD price.
+impl PriceSource for BirdeyeClient {
+    fn name(&self) -> &'static str {
+        "birdeye"
+    }
+
+    fn stats(&self) -> UpstreamStats {
+        BirdeyeClient::stats(self)
+    }
+
+    fn get_prices<'a>(
+        &'a self,
+        token_pubkeys: &'a [String],
+        vs_token: &'a VsToken
+    ) -> BoxFuture<'a, Result<HashMap<String, f64>>> {
+        async move {
+            let vs_token_usd_price = match self.get_token_price(&vs_token.pubkey).await?.data {
+                Some(price) if price.value > 0.0 => price.value,
+                _ => return Ok(HashMap::new())
+            };
+
+            let usd_prices: Vec<(String, Option<f64>)> = stream::iter(token_pubkeys.iter().cloned())
+                .map(|token_pubkey| get_birdeye_usd_price(self, token_pubkey))
+                .buffered(BIRDEYE_PRICE_CONCURRENCY)
+                .collect()
+                .await;
+
+            let prices: HashMap<String, f64> = usd_prices.into_iter()
+                .filter_map(|(token_pubkey, usd_price)| usd_price.map(|usd_price| (token_pubkey, usd_price / vs_token_usd_price)))
+                .collect();
+
+            Ok(prices)
+        }.boxed()
+    }
+}
+
+/// Unlike a batched source, one unpriceable mint shouldn't hide the others, so a failed lookup is just a missing price.
+async fn get_birdeye_usd_price(birdeye_client: &BirdeyeClient, token_pubkey: String) -> (String, Option<f64>) {
+    let usd_price = birdeye_client.get_token_price(&token_pubkey)
+        .await
+        .ok()
+        .and_then(|response| response.data)
+        .map(|price| price.value);
+
+    (token_pubkey, usd_price)
+}
+
+/// A price and the source that served it.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct PriceQuote {
+    pub price: f64,
+    pub source: &'static str,
+    /// Largest relative gap to another source's price, 0.05 is 5%. `None` unless cross-checked against a second price.
+    pub divergence: Option<f64>,
+    /// Whether `divergence` is over the configured threshold.
+    pub divergent: bool
+}
+
+/// Tries price sources in priority order, each one only asked for the mints the ones before it couldn't price.
+/// With a divergence threshold, every source is asked for every mint and the first price is checked against the rest.
+#[derive(Clone)]
+pub struct CompositePriceSource {
+    sources: Vec<Arc<dyn PriceSource>>,
+    divergence_threshold: Option<f64>
+}
+
+impl CompositePriceSource {
+    pub fn new(sources: Vec<Arc<dyn PriceSource>>, divergence_threshold: Option<f64>) -> Self {
+        assert!(!sources.is_empty(), "At least one price source is required");
+
+        Self { sources, divergence_threshold }
+    }
+
+    pub fn stats(&self) -> Vec<UpstreamStats> {
+        self.sources.iter().map(|source| source.stats()).collect()
+    }
+
+    /// Quotes for `token_pubkeys`, leaving out mints no source could price.
+    /// Fails only when every source failed.
+    pub async fn get_quotes(
+        &self,
+        token_pubkeys: &[String],
+        vs_token: &VsToken
+    ) -> Result<HashMap<String, PriceQuote>> {
+        match self.divergence_threshold {
+            Some(divergence_threshold) => self.get_cross_checked_quotes(token_pubkeys, vs_token, divergence_threshold).await,
+            None => self.get_fallback_quotes(token_pubkeys, vs_token).await
+        }
+    }
+
+    async fn get_fallback_quotes(
+        &self,
+        token_pubkeys: &[String],
+        vs_token: &VsToken
+    ) -> Result<HashMap<String, PriceQuote>> {
+        let mut quotes: HashMap<String, PriceQuote> = HashMap::with_capacity(token_pubkeys.len());
+        let mut missing: Vec<String> = token_pubkeys.to_vec();
+        let mut answered = false;
+        let mut last_error = None;
+
+        for source in &self.sources {
+            if missing.is_empty() {
+                break;
+            }
+
+            match source.get_prices(&missing, vs_token).await {
+                Ok(prices) => {
+                    answered = true;
+
+                    for (token_pubkey, price) in prices.into_iter().filter(|(_, price)| is_usable_price(*price)) {
+                        quotes.insert(token_pubkey, PriceQuote { price, source: source.name(), divergence: None, divergent: false });
+                    }
+
+                    missing.retain(|token_pubkey| !quotes.contains_key(token_pubkey));
+                },
+                Err(e) => {
+                    println!("->> {:<12} - price source {} failed, falling back", "SERVICE", source.name());
+                    last_error = Some(e);
+                }
+            }
+        }
+
+        // a source that answered, even without prices, means the mints are unpriceable rather than the sources down
+        match last_error {
+            Some(e) if !answered => Err(e),
+            _ => Ok(quotes)
+        }
+    }
+
+    async fn get_cross_checked_quotes(
+        &self,
+        token_pubkeys: &[String],
+        vs_token: &VsToken,
+        divergence_threshold: f64
+    ) -> Result<HashMap<String, PriceQuote>> {
+        let responses = future::join_all(
+            self.sources.iter().map(|source| source.get_prices(token_pubkeys, vs_token))
+        ).await;
+
+        let mut answered: Vec<(&'static str, HashMap<String, f64>)> = Vec::with_capacity(responses.len());
+        let mut last_error = None;
+
+        for (source, response) in self.sources.iter().zip(responses) {
+            match response {
+                Ok(prices) => answered.push((source.name(), prices)),
+                Err(e) => {
+                    println!("->> {:<12} - price source {} failed during cross-check", "SERVICE", source.name());
+                    last_error = Some(e);
+                }
+            }
+        }
+
+        if answered.is_empty() {
+            return Err(last_error.unwrap_or(ApiError::JupiterFetchFail))
+        }
+
+        let mut quotes: HashMap<String, PriceQuote> = HashMap::with_capacity(token_pubkeys.len());
+
+        for token_pubkey in token_pubkeys {
+            let mut prices = answered.iter()
+                .filter_map(|(source, prices)| prices.get(token_pubkey).map(|price| (*source, *price)))
+                .filter(|(_, price)| is_usable_price(*price));
+
+            let Some((source, price)) = prices.next() else { continue };
+
+            let divergence = prices
+                .map(|(_, other_price)| relative_divergence(price, other_price))
+                .reduce(f64::max);
+
+            quotes.insert(token_pubkey.clone(), PriceQuote {
+                price,
+                source,
+                divergence,
+                divergent: divergence.is_some_and(|divergence| divergence > divergence_threshold)
+            });
+        }
+
+        Ok(quotes)
+    }
+}
+
+/// A zero, negative or non-finite price is a broken upstream answer, treated like no price at all.
+fn is_usable_price(price: f64) -> bool {
+    price.is_finite() && price > 0.0
+}
+
+fn relative_divergence(price: f64, other_price: f64) -> f64 {
+    if price == 0.0 {
+        return if other_price == 0.0 { 0.0 } else { f64::INFINITY }
+    }
+
+    ((other_price - price) / price).abs()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::clients::external_http::CircuitState;
+
+    /// Serves fixed prices, or fails every request when `prices` is `None`.
+    struct FixedSource {
+        name: &'static str,
+        prices: Option<HashMap<String, f64>>
+    }
+
+    impl FixedSource {
+        fn serving(name: &'static str, prices: &[(&str, f64)]) -> Arc<dyn PriceSource> {
+            Arc::new(Self {
+                name,
+                prices: Some(prices.iter().map(|(mint, price)| (mint.to_string(), *price)).collect())
+            })
+        }
+
+        fn failing(name: &'static str) -> Arc<dyn PriceSource> {
+            Arc::new(Self { name, prices: None })
+        }
+    }
+
+    impl PriceSource for FixedSource {
+        fn name(&self) -> &'static str {
+            self.name
+        }
+
+        fn stats(&self) -> UpstreamStats {
+            UpstreamStats { upstream: self.name, requests: 0, errors: 0, retries: 0, short_circuited: 0, circuit_state: CircuitState::Closed }
+        }
+
+        fn get_prices<'a>(
+            &'a self,
+            token_pubkeys: &'a [String],
+            _vs_token: &'a VsToken
+        ) -> BoxFuture<'a, Result<HashMap<String, f64>>> {
+            let result = match &self.prices {
+                Some(prices) => Ok(
+                    token_pubkeys.iter()
+                        .filter_map(|mint| prices.get(mint).map(|price| (mint.clone(), *price)))
+                        .collect()
+                ),
+                None => Err(ApiError::JupiterFetchFail)
+            };
+
+            future::ready(result).boxed()
+        }
+    }
+
+    fn usdc() -> VsToken {
+        VsToken::usd()
+    }
+
+    fn mints(mints: &[&str]) -> Vec<String> {
+        mints.iter().map(|mint| mint.to_string()).collect()
+    }
+
+    #[tokio::test]
+    async fn falls_back_for_mints_the_primary_cannot_price() {
+        let composite = CompositePriceSource::new(
+            vec![FixedSource::serving("primary", &[("a", 1.0)]), FixedSource::serving("fallback", &[("a", 9.0), ("b", 2.0)])],
+            None
+        );
+
+        let quotes = composite.get_quotes(&mints(&["a", "b", "c"]), &usdc()).await.unwrap();
+
+        assert_eq!(quotes.len(), 2);
+        assert_eq!((quotes["a"].price, quotes["a"].source), (1.0, "primary"));
+        assert_eq!((quotes["b"].price, quotes["b"].source), (2.0, "fallback"));
+    }
+
+    #[tokio::test]
+    async fn ignores_prices_that_are_not_positive_and_finite() {
+        let composite = CompositePriceSource::new(
+            vec![
+                FixedSource::serving("primary", &[("a", 0.0), ("b", f64::INFINITY), ("c", -1.0)]),
+                FixedSource::serving("fallback", &[("a", 2.0), ("b", f64::NAN)])
+            ],
+            None
+        );
+
+        let quotes = composite.get_quotes(&mints(&["a", "b", "c"]), &usdc()).await.unwrap();
+
+        assert_eq!(quotes.len(), 1);
+        assert_eq!((quotes["a"].price, quotes["a"].source), (2.0, "fallback"));
+    }
+
+    #[tokio::test]
+    async fn falls_back_when_the_primary_fails() {
+        let composite = CompositePriceSource::new(
+            vec![FixedSource::failing("primary"), FixedSource::serving("fallback", &[("a", 2.0)])],
+            None
+        );
+
+        let quotes = composite.get_quotes(&mints(&["a"]), &usdc()).await.unwrap();
+
+        assert_eq!(quotes["a"].source, "fallback");
+    }
+
+    #[tokio::test]
+    async fn fails_when_every_source_fails() {
+        let composite = CompositePriceSource::new(
+            vec![FixedSource::failing("primary"), FixedSource::failing("fallback")],
+            None
+        );
+
+        assert!(composite.get_quotes(&mints(&["a"]), &usdc()).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn flags_divergence_over_the_threshold() {
+        let composite = CompositePriceSource::new(
+            vec![
+                FixedSource::serving("primary", &[("a", 1.0), ("b", 1.0)]),
+                FixedSource::serving("second", &[("a", 1.02), ("b", 1.2)]),
+                FixedSource::failing("third")
+            ],
+            Some(0.05)
+        );
+
+        let quotes = composite.get_quotes(&mints(&["a", "b"]), &usdc()).await.unwrap();
+
+        assert_eq!(quotes["a"].source, "primary");
+        assert!(!quotes["a"].divergent);
+        assert!(quotes["b"].divergent);
+        assert!((quotes["b"].divergence.unwrap() - 0.2).abs() < 1e-9);
+    }
+
+    #[tokio::test]
+    async fn leaves_divergence_unset_with_a_single_price() {
+        let composite = CompositePriceSource::new(
+            vec![FixedSource::serving("primary", &[]), FixedSource::serving("second", &[("a", 3.0)])],
+            Some(0.05)
+        );
+
+        let quotes = composite.get_quotes(&mints(&["a"]), &usdc()).await.unwrap();
+
+        assert_eq!(quotes["a"], PriceQuote { price: 3.0, source: "second", divergence: None, divergent: false });
+    }
+
+    #[test]
+    fn resolves_vs_tokens_from_symbols_and_mints() {
+        assert_eq!(VsToken::from_symbol("usdc"), Some(VsToken::new("EPjFWdd5AufqSSqeM2qN1xzybapC8G4wEGGkZwyTDt1v", "USDC")));
+        assert_eq!(
+            VsToken::from_symbol("So11111111111111111111111111111111111111112").map(|vs_token| vs_token.pubkey).as_deref(),
+            Some("So11111111111111111111111111111111111111112")
+        );
+        assert_eq!(VsToken::from_symbol("BONK"), None);
+    }
+
+    #[test]
+    fn rejects_known_mints_paired_with_another_symbol() {
+        assert!(VsToken::is_consistent("EPjFWdd5AufqSSqeM2qN1xzybapC8G4wEGGkZwyTDt1v", "USDC"));
+        assert!(VsToken::is_consistent("DezXAZ8z7PnrnRJjz3wXBoRgixCa6xjnB7YaB1pPB263", "BONK"));
+
+        assert!(!VsToken::is_consistent("EPjFWdd5AufqSSqeM2qN1xzybapC8G4wEGGkZwyTDt1v", "SOL"));
+        assert!(!VsToken::is_consistent("DezXAZ8z7PnrnRJjz3wXBoRgixCa6xjnB7YaB1pPB263", "USDC"));
+    }
+}
@@ -0,0 +1,328 @@
+use std::{collections::HashMap, sync::Arc, time::Duration};
+
+use axum::{extract::Query, http::header, response::IntoResponse, routing::get, Router};
+use sqlx::{postgres::PgPoolOptions, Connection, PgConnection};
+use tokio::net::TcpListener;
+
+use crate::{
+    clients::{client_birdeye::BirdeyeClient, client_jupiter::JupiterClient, client_jupiter_v2::JupiterV2Client},
+    models::{model_selection::SelectionConfigForUpdate, model_spin_quota::SpinQuotaConfig},
+    repos::{
+        in_memory::{
+            InMemoryLeaderboardRepo,
+            InMemoryPositionRepo,
+            InMemorySelectionRepo,
+            InMemorySnapshotRepo,
+            InMemoryTokenOverrideRepo,
+            InMemoryTokenRepo,
+            InMemoryUserRepo
+        },
+        repo_leaderboard::PgLeaderboardRepo,
+        repo_position::PgPositionRepo,
+        repo_selection::PgSelectionRepo,
+        repo_token::PgTokenRepo,
+        repo_token_override::PgTokenOverrideRepo,
+        repo_token_snapshot::PgSnapshotRepo,
+        repo_user::PgUserRepo
+    },
+    services::{price_service::PriceService, price_source::{CompositePriceSource, PriceSource}},
+    AppState
+};
+
+/// Token served by the mock upstream. Security flags are clean unless `freeze_authority` is set.
+pub struct MockToken {
+    pub address: &'static str,
+    pub market_cap: f64,
+    pub liquidity: f64,
+    pub volume_24h_usd: f64,
+    pub trade_24h: u64,
+    pub price: f64,
+    pub freeze_authority: bool
+}
+
+/// Canned listing in Birdeye's volume order. Only the first three pass the default test criteria.
+pub const MOCK_TOKENS: [MockToken; 6] = [
+    MockToken { address: "MockMintAlpha", market_cap: 9_000_000.0, liquidity: 800_000.0, volume_24h_usd: 3_000_000.0, trade_24h: 9_000, price: 1.5, freeze_authority: false },
+    MockToken { address: "MockMintBeta", market_cap: 4_000_000.0, liquidity: 400_000.0, volume_24h_usd: 2_000_000.0, trade_24h: 4_000, price: 0.25, freeze_authority: false },
+    // frozen accounts are rejected outright by the security screening
+    MockToken { address: "MockMintFrozen", market_cap: 3_000_000.0, liquidity: 300_000.0, volume_24h_usd: 1_500_000.0, trade_24h: 3_000, price: 0.1, freeze_authority: true },
+    MockToken { address: "MockMintGamma", market_cap: 2_000_000.0, liquidity: 200_000.0, volume_24h_usd: 1_000_000.0, trade_24h: 2_000, price: 0.02, freeze_authority: false },
+    // below the market cap threshold
+    MockToken { address: "MockMintTiny", market_cap: 10_000.0, liquidity: 5_000.0, volume_24h_usd: 900_000.0, trade_24h: 1_000, price: 0.001, freeze_authority: false },
+    // below the 24h trades threshold
+    MockToken { address: "MockMintQuiet", market_cap: 1_000_000.0, liquidity: 150_000.0, volume_24h_usd: 800_000.0, trade_24h: 10, price: 0.5, freeze_authority: false },
+];
+
+/// USD prices the mock reports for the quote tokens.
+const MOCK_QUOTE_PRICES: [(&str, f64); 2] = [
+    ("EPjFWdd5AufqSSqeM2qN1xzybapC8G4wEGGkZwyTDt1v", 1.0),
+    ("So11111111111111111111111111111111111111112", 150.0),
+];
+
+pub fn mock_token(address: &str) -> Option<&'static MockToken> {
+    MOCK_TOKENS.iter().find(|token| token.address == address)
+}
+
+fn mock_usd_price(address: &str) -> Option<f64> {
+    mock_token(address)
+        .map(|token| token.price)
+        .or_else(|| MOCK_QUOTE_PRICES.iter().find(|(mint, _)| *mint == address).map(|(_, price)| *price))
+}
+
+fn json(body: String) -> impl IntoResponse {
+    ([(header::CONTENT_TYPE, "application/json")], body)
+}
+
+async fn birdeye_token_list(Query(params): Query<HashMap<String, String>>) -> impl IntoResponse {
+    let offset = params.get("offset").and_then(|offset| offset.parse::<usize>().ok()).unwrap_or(0);
+
+    let tokens: Vec<String> = MOCK_TOKENS.iter()
+        .skip(offset)
+        .take(50)
+        .map(|token| format!(
+            r#"{{"address":"{}","decimals":6,"liquidity":{},"logoURI":"https://example.com/{}.png","mc":{},"name":"{}","symbol":"{}","v24hUSD":{}}}"#,
+            token.address, token.liquidity, token.address, token.market_cap, token.address, token.address.to_uppercase(), token.volume_24h_usd
+        ))
+        .collect();
+
+    json(format!(r#"{{"data":{{"tokens":[{}]}},"success":true}}"#, tokens.join(",")))
+}
+
+async fn birdeye_token_overview(Query(params): Query<HashMap<String, String>>) -> impl IntoResponse {
+    let token = params.get("address").and_then(|address| mock_token(address));
+
+    let Some(token) = token else {
+        return (axum::http::StatusCode::BAD_REQUEST, "unknown address").into_response()
+    };
+
+    json(format!(
+        r#"{{"data":{{"trade24h":{},"decimals":6,"priceChange24hPercent":12.5,"v24hUSD":{},"price":{},"liquidity":{},"mc":{},"extensions":{{"twitter":"https://twitter.com/{}","website":null,"discord":null,"telegram":null}}}},"success":true}}"#,
+        token.trade_24h, token.volume_24h_usd, token.price, token.liquidity, token.market_cap, token.address
+    )).into_response()
+}
+
+async fn birdeye_token_security(Query(params): Query<HashMap<String, String>>) -> impl IntoResponse {
+    let token = params.get("address").and_then(|address| mock_token(address));
+
+    let Some(token) = token else {
+        return (axum::http::StatusCode::BAD_REQUEST, "unknown address").into_response()
+    };
+
+    let freeze_authority = if token.freeze_authority { r#""FreezeAuthority111""# } else { "null" };
+
+    json(format!(
+        r#"{{"data":{{"ownerAddress":null,"freezeAuthority":{},"mintAuthority":null,"top10HolderPercent":0.2,"mutableMetadata":true,"transferFeeEnable":false,"creatorBalance":0,"creatorPercentage":0}},"success":true,"statusCode":200}}"#,
+        freeze_authority
+    )).into_response()
+}
+
+async fn birdeye_price(Query(params): Query<HashMap<String, String>>) -> impl IntoResponse {
+    match params.get("address").and_then(|address| mock_usd_price(address)) {
+        Some(price) => json(format!(r#"{{"data":{{"value":{},"updateUnixTime":1714000000}},"success":true}}"#, price)),
+        None => json(r#"{"data":null,"success":false}"#.to_string())
+    }
+}
+
+/// Mirrors Jupiter's Price API v2, prices are strings and unknown ids map to null. Quotes are always in USD.
+async fn jupiter_v2_price(Query(params): Query<HashMap<String, String>>) -> impl IntoResponse {
+    let prices: Vec<String> = params.get("ids")
+        .map(|ids| ids.split(',').collect::<Vec<_>>())
+        .unwrap_or_default()
+        .into_iter()
+        .map(|id| match mock_token(id) {
+            Some(token) => format!(r#""{}":{{"id":"{}","type":"derivedPrice","price":"{}"}}"#, id, id, token.price),
+            None => format!(r#""{}":null"#, id)
+        })
+        .collect();
+
+    json(format!(r#"{{"data":{{{}}},"timeTaken":0.001}}"#, prices.join(",")))
+}
+
+/// Mirrors Jupiter v4, unknown ids are left out of `data`.
+async fn jupiter_price(Query(params): Query<HashMap<String, String>>) -> impl IntoResponse {
+    let vs_token = params.get("vsToken").cloned().unwrap_or_else(|| "USDC".to_string());
+
+    let prices: Vec<String> = params.get("ids")
+        .map(|ids| ids.split(',').filter_map(mock_token).collect::<Vec<_>>())
+        .unwrap_or_default()
+        .into_iter()
+        .map(|token| format!(
+            r#""{}":{{"id":"{}","mintSymbol":"{}","vsToken":"{}","vsTokenSymbol":"{}","price":{}}}"#,
+            token.address, token.address, token.address.to_uppercase(), vs_token, vs_token, token.price
+        ))
+        .collect();
+
+    json(format!(r#"{{"data":{{{}}},"timeTaken":0.001}}"#, prices.join(",")))
+}
+
+/// Serves canned Birdeye and Jupiter payloads from `MOCK_TOKENS` on a random local port until the test ends.
+/// Birdeye is mounted at `/birdeye`, Jupiter v4 at `/jupiter` and Jupiter v2 at `/jupiter/v2`.
+pub struct MockUpstream {
+    pub base_url: String
+}
+
+impl MockUpstream {
+    pub async fn start() -> Self {
+        let router = Router::new()
+            .route("/birdeye/defi/tokenlist", get(birdeye_token_list))
+            .route("/birdeye/defi/token_overview", get(birdeye_token_overview))
+            .route("/birdeye/defi/token_security", get(birdeye_token_security))
+            .route("/birdeye/defi/price", get(birdeye_price))
+            .route("/jupiter/price", get(jupiter_price))
+            .route("/jupiter/v2", get(jupiter_v2_price));
+
+        let listener = TcpListener::bind("127.0.0.1:0")
+            .await.expect("Failed to bind mock upstream");
+
+        let address = listener.local_addr().expect("Mock upstream has no local address");
+
+        tokio::spawn(async move {
+            axum::serve(listener, router).await.expect("Mock upstream stopped");
+        });
+
+        Self { base_url: format!("http://{}", address) }
+    }
+
+    pub fn birdeye_client(&self) -> BirdeyeClient {
+        BirdeyeClient::new("test-api-key", &format!("{}/birdeye", self.base_url), 100.0)
+    }
+
+    pub fn jupiter_client(&self) -> JupiterClient {
+        JupiterClient::new(&format!("{}/jupiter", self.base_url))
+    }
+
+    pub fn jupiter_v2_client(&self) -> JupiterV2Client {
+        JupiterV2Client::new(&format!("{}/jupiter/v2", self.base_url))
+    }
+
+    /// A Jupiter v2 client whose every request fails with a 404.
+    pub fn broken_jupiter_v2_client(&self) -> JupiterV2Client {
+        JupiterV2Client::new(&format!("{}/missing", self.base_url))
+    }
+
+    /// Jupiter v2 with Birdeye as fallback, the production default.
+    pub fn price_service(&self) -> PriceService {
+        self.price_service_with(vec![Arc::new(self.jupiter_v2_client()), Arc::new(self.birdeye_client())], None)
+    }
+
+    pub fn price_service_with(&self, sources: Vec<Arc<dyn PriceSource>>, divergence_threshold: Option<f64>) -> PriceService {
+        PriceService::new(Duration::from_secs(30), CompositePriceSource::new(sources, divergence_threshold))
+    }
+
+    /// An `AppState` backed by in-memory repos, for handler and cron tests that don't need Postgres.
+    /// The pool never connects, so trades, spins, points, referrals, Telegram users, auth and API keys, which still query `state.db`, fail.
+    pub fn in_memory_state(&self) -> AppState {
+        let db = PgPoolOptions::new()
+            .connect_lazy("postgres://localhost/unused")
+            .expect("Failed to build lazy pool");
+
+        AppState {
+            db,
+            birdeye_client: self.birdeye_client(),
+            price_service: self.price_service(),
+            spin_quota: test_spin_quota(),
+            token_repo: Arc::new(InMemoryTokenRepo::default()),
+            position_repo: Arc::new(InMemoryPositionRepo::default()),
+            user_repo: Arc::new(InMemoryUserRepo::default()),
+            selection_repo: Arc::new(InMemorySelectionRepo::with_config(SelectionConfigForUpdate::default())),
+            token_override_repo: Arc::new(InMemoryTokenOverrideRepo::default()),
+            snapshot_repo: Arc::new(InMemorySnapshotRepo::default()),
+            leaderboard_repo: Arc::new(InMemoryLeaderboardRepo::default())
+        }
+    }
+}
+
+fn test_spin_quota() -> SpinQuotaConfig {
+    SpinQuotaConfig {
+        spins_per_day: 10,
+        cooldown: Duration::from_secs(0)
+    }
+}
+
+/// An `AppState` on a freshly migrated database with the default selection config, and its clients pointed at a `MockUpstream`.
+pub struct TestContext {
+    pub state: AppState,
+    admin_url: String,
+    database_name: String
+}
+
+impl TestContext {
+    /// Creates a throwaway database on the server in `DATABASE_URL`.
+    /// Database tests are `#[ignore]`d, run them with `cargo test -- --ignored` against a local Postgres.
+    pub async fn start() -> Self {
+        let admin_url = std::env::var("DATABASE_URL")
+            .expect("DATABASE_URL must be set to run database tests");
+
+        let database_name = format!("solspinner_test_{}", uuid::Uuid::new_v4().simple());
+
+        let mut admin = PgConnection::connect(&admin_url)
+            .await.expect("Failed to connect to DATABASE_URL");
+
+        sqlx::query(&format!("CREATE DATABASE {}", database_name))
+            .execute(&mut admin)
+            .await.expect("Failed to create test database");
+
+        let db = PgPoolOptions::new()
+            .max_connections(5)
+            .connect(&database_url(&admin_url, &database_name))
+            .await.expect("Failed to connect to test database");
+
+        sqlx::migrate!().run(&db)
+            .await.expect("Failed to migrate test database");
+
+        let upstream = MockUpstream::start().await;
+
+        let state = AppState {
+            token_repo: Arc::new(PgTokenRepo::new(db.clone())),
+            position_repo: Arc::new(PgPositionRepo::new(db.clone())),
+            user_repo: Arc::new(PgUserRepo::new(db.clone())),
+            selection_repo: Arc::new(PgSelectionRepo::new(db.clone())),
+            token_override_repo: Arc::new(PgTokenOverrideRepo::new(db.clone())),
+            snapshot_repo: Arc::new(PgSnapshotRepo::new(db.clone())),
+            leaderboard_repo: Arc::new(PgLeaderboardRepo::new(db.clone())),
+            db,
+            birdeye_client: upstream.birdeye_client(),
+            price_service: upstream.price_service(),
+            spin_quota: test_spin_quota()
+        };
+
+        // seeded at startup in main, the selector can't run without it
+        state.selection_repo.ensure_selection_config(SelectionConfigForUpdate::default())
+            .await.expect("Failed to store selection config");
+
+        Self { state, admin_url, database_name }
+    }
+
+    /// Drops the test database. Skipped when a test panics, leaving the database behind for inspection.
+    pub async fn cleanup(self) {
+        self.state.db.close().await;
+
+        let mut admin = PgConnection::connect(&self.admin_url)
+            .await.expect("Failed to connect to DATABASE_URL");
+
+        sqlx::query(&format!("DROP DATABASE IF EXISTS {} WITH (FORCE)", self.database_name))
+            .execute(&mut admin)
+            .await.expect("Failed to drop test database");
+    }
+}
+
+/// Swaps the database in a postgres url, keeping any query string.
+fn database_url(admin_url: &str, database_name: &str) -> String {
+    let (url, query) = match admin_url.split_once('?') {
+        Some((url, query)) => (url, Some(query)),
+        None => (admin_url, None)
+    };
+
+    // the path after the host is the database, it may be missing entirely
+    let authority_end = url.find("://").map(|scheme_end| scheme_end + 3).unwrap_or(0);
+
+    let server = match url[authority_end..].find('/') {
+        Some(path_start) => &url[..authority_end + path_start],
+        None => url
+    };
+
+    match query {
+        Some(query) => format!("{}/{}?{}", server, database_name, query),
+        None => format!("{}/{}", server, database_name)
+    }
+}
@@ -1,10 +1,108 @@
+use ed25519_dalek::{Signature, VerifyingKey};
+use sha2::{Digest, Sha256};
+
 pub fn calculate_price_change(new_price: f64, old_price: f64) -> (f64, f64) {
     let change_in_price = new_price - old_price;
     let percentage_change = if old_price != 0.0 {
         (change_in_price / old_price) * 100.0
     } else {
-        0.0 
+        0.0
     };
 
     (change_in_price, percentage_change)
-}
\ No newline at end of file
+}
+
+/// 32 random bytes, hex encoded. Used for nonces and bearer tokens.
+pub fn generate_random_token() -> String {
+    hex::encode(rand::random::<[u8; 32]>())
+}
+
+/// Fresh codes tried before giving up, collisions are rare at 8 hex characters.
+pub const ACCESS_CODE_ATTEMPTS: u32 = 5;
+
+/// 8 uppercase hex characters, short enough to type into a chat.
+pub fn generate_access_code() -> String {
+    hex::encode_upper(rand::random::<[u8; 4]>())
+}
+
+pub fn hash_token(token: &str) -> String {
+    hex::encode(Sha256::digest(token.as_bytes()))
+}
+
+/// Checks a base58 ed25519 signature of `message` against a base58 wallet pubkey.
+pub fn verify_wallet_signature(user_pubkey: &str, message: &str, signature: &str) -> bool {
+    let Ok(pubkey_bytes) = bs58::decode(user_pubkey).into_vec() else {
+        return false
+    };
+
+    let Ok(pubkey_bytes) = <[u8; 32]>::try_from(pubkey_bytes.as_slice()) else {
+        return false
+    };
+
+    let Ok(verifying_key) = VerifyingKey::from_bytes(&pubkey_bytes) else {
+        return false
+    };
+
+    let Ok(signature_bytes) = bs58::decode(signature).into_vec() else {
+        return false
+    };
+
+    let Ok(signature) = Signature::from_slice(&signature_bytes) else {
+        return false
+    };
+
+    verifying_key.verify_strict(message.as_bytes(), &signature).is_ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use ed25519_dalek::{Signer, SigningKey};
+
+    use super::*;
+
+    fn wallet(seed: u8) -> (SigningKey, String) {
+        let signing_key = SigningKey::from_bytes(&[seed; 32]);
+        let pubkey = bs58::encode(signing_key.verifying_key().as_bytes()).into_string();
+
+        (signing_key, pubkey)
+    }
+
+    fn sign(signing_key: &SigningKey, message: &str) -> String {
+        bs58::encode(signing_key.sign(message.as_bytes()).to_bytes()).into_string()
+    }
+
+    #[test]
+    fn accepts_a_signature_from_the_wallet() {
+        let (signing_key, pubkey) = wallet(1);
+        let signature = sign(&signing_key, "Sign in to SolSpinner");
+
+        assert!(verify_wallet_signature(&pubkey, "Sign in to SolSpinner", &signature));
+    }
+
+    #[test]
+    fn rejects_a_tampered_message() {
+        let (signing_key, pubkey) = wallet(1);
+        let signature = sign(&signing_key, "Sign in to SolSpinner\nNonce: a");
+
+        assert!(!verify_wallet_signature(&pubkey, "Sign in to SolSpinner\nNonce: b", &signature));
+    }
+
+    #[test]
+    fn rejects_a_signature_from_another_wallet() {
+        let (signing_key, _) = wallet(1);
+        let (_, other_pubkey) = wallet(2);
+        let signature = sign(&signing_key, "Sign in to SolSpinner");
+
+        assert!(!verify_wallet_signature(&other_pubkey, "Sign in to SolSpinner", &signature));
+    }
+
+    #[test]
+    fn rejects_malformed_keys_and_signatures() {
+        let (signing_key, pubkey) = wallet(1);
+        let signature = sign(&signing_key, "Sign in to SolSpinner");
+
+        assert!(!verify_wallet_signature("not-base58-0OIl", "Sign in to SolSpinner", &signature));
+        assert!(!verify_wallet_signature(&bs58::encode([1u8; 16]).into_string(), "Sign in to SolSpinner", &signature));
+        assert!(!verify_wallet_signature(&pubkey, "Sign in to SolSpinner", "3xTrUnCaTeD"));
+    }
+}
@@ -2,4 +2,11 @@ pub mod routes_tokens;
 pub mod routes_positions;
 pub mod routes_users;
 pub mod mw_auth;
-pub mod routes_play;
\ No newline at end of file
+pub mod routes_play;
+pub mod routes_auth;
+pub mod routes_api_keys;
+pub mod routes_portfolio;
+pub mod routes_leaderboard;
+pub mod routes_tg_users;
+pub mod routes_token_overrides;
+pub mod routes_upstreams;
\ No newline at end of file
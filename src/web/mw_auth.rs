@@ -1,30 +1,95 @@
-use axum::{extract::Request, http::HeaderMap, middleware::Next, response::{IntoResponse, Response}, Extension};
-use reqwest::StatusCode;
-use crate::AppState;
+use axum::{extract::{Request, State}, http::HeaderMap, middleware::Next, response::{IntoResponse, Response}, Extension};
+use crate::{errors::api_errors::{ApiError, Result}, models::{model_api_key::{ApiKey, Scope}, model_auth::AuthSession}, AppState};
+
+/// Scopes granted to every signed in wallet. Ownership is still checked per handler.
+const WALLET_SCOPES: [Scope; 6] = [
+    Scope::PositionsRead,
+    Scope::PositionsWrite,
+    Scope::UsersRead,
+    Scope::UsersWrite,
+    Scope::TokensRead,
+    Scope::Play,
+];
+
+/// Who is making the request, inserted into the request extensions by `auth_middleware`.
+#[derive(Clone, Debug)]
+pub enum Caller {
+    /// Integration holding an API key. May act for any wallet within its scopes.
+    Service(Vec<Scope>),
+    /// Wallet that signed in with its ed25519 key. May only act for itself.
+    Wallet(String),
+}
+
+impl Caller {
+    pub fn has_scope(&self, scope: Scope) -> bool {
+        match self {
+            Caller::Service(scopes) => scopes.contains(&Scope::Admin) || scopes.contains(&scope),
+            Caller::Wallet(_) => WALLET_SCOPES.contains(&scope)
+        }
+    }
+
+    pub fn authorize_user(&self, user_pubkey: &str) -> Result<()> {
+        match self {
+            Caller::Service(_) => Ok(()),
+            Caller::Wallet(wallet_pubkey) if wallet_pubkey == user_pubkey => Ok(()),
+            Caller::Wallet(_) => Err(ApiError::Forbidden)
+        }
+    }
+
+    pub fn require_service(&self) -> Result<()> {
+        match self {
+            Caller::Service(_) => Ok(()),
+            Caller::Wallet(_) => Err(ApiError::Forbidden)
+        }
+    }
+}
 
 pub async fn auth_middleware(
     Extension(state): Extension<AppState>,
     headers: HeaderMap,
-    request: Request,
+    mut request: Request,
     next: Next,
 ) -> Response {
     println!("->> {:<12} - auth_middleware", "MIDDLEWARE");
 
-    let api_key = &state.api_key;
-
-    match headers.get("authorization") {
-        Some(header_value) => {
-            match header_value.to_str() {
-                Ok(auth_str) => {
-                    if api_key == auth_str {
-                        next.run(request).await
-                    } else {
-                        StatusCode::UNAUTHORIZED.into_response()
-                    }
-                },
-                Err(_) => StatusCode::UNAUTHORIZED.into_response()
-            }
+    let auth_str = match headers.get("authorization").map(|header_value| header_value.to_str()) {
+        Some(Ok(auth_str)) => auth_str,
+        _ => return ApiError::Unauthorized.into_response()
+    };
+
+    let token = auth_str.strip_prefix("Bearer ").unwrap_or(auth_str);
+
+    match ApiKey::get_active_api_key(token, state.clone()).await {
+        Ok(Some(api_key)) => {
+            request.extensions_mut().insert(Caller::Service(api_key.parsed_scopes()));
+            return next.run(request).await
         },
-        None => StatusCode::UNAUTHORIZED.into_response()
+        Ok(None) => {},
+        Err(e) => return e.into_response()
+    }
+
+    match AuthSession::get_active_session(token, state).await {
+        Ok(Some(session)) => {
+            request.extensions_mut().insert(Caller::Wallet(session.user_pubkey));
+            next.run(request).await
+        },
+        Ok(None) => ApiError::Unauthorized.into_response(),
+        Err(e) => e.into_response()
+    }
+}
+
+/// Route layer rejecting callers without `scope`. Must run after `auth_middleware`.
+pub async fn scope_middleware(
+    State(scope): State<Scope>,
+    Extension(caller): Extension<Caller>,
+    request: Request,
+    next: Next,
+) -> Response {
+    println!("->> {:<12} - scope_middleware {}", "MIDDLEWARE", scope.as_str());
+
+    if caller.has_scope(scope) {
+        next.run(request).await
+    } else {
+        ApiError::Forbidden.into_response()
     }
-}
\ No newline at end of file
+}
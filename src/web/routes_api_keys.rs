@@ -0,0 +1,45 @@
+use axum::{extract::{Path, State}, middleware, routing::{delete, get}, Json, Router};
+use uuid::Uuid;
+use crate::{errors::api_errors::{ApiError, Result}, models::model_api_key::{ApiKey, ApiKeyForCreate, ApiKeyWithSecret, Scope}, web::mw_auth::scope_middleware, AppState};
+
+pub fn routes(state: AppState) -> Router {
+    Router::new()
+        .route("/admin/api-keys", get(get_api_keys).post(create_api_key))
+        .route("/admin/api-keys/:key_id", delete(revoke_api_key))
+        .route_layer(middleware::from_fn_with_state(Scope::Admin, scope_middleware))
+        .with_state(state)
+}
+
+async fn create_api_key(
+    State(state): State<AppState>,
+    Json(api_key): Json<ApiKeyForCreate>
+) -> Result<Json<ApiKeyWithSecret>> {
+    println!("->> {:<12} - create_api_key", "HANDLER");
+
+    let api_key = ApiKey::create_api_key(api_key, state).await?;
+
+    Ok(Json(api_key))
+}
+
+async fn get_api_keys(
+    State(state): State<AppState>
+) -> Result<Json<Vec<ApiKey>>> {
+    println!("->> {:<12} - get_api_keys", "HANDLER");
+
+    let api_keys = ApiKey::get_api_keys(state).await?;
+
+    Ok(Json(api_keys))
+}
+
+async fn revoke_api_key(
+    State(state): State<AppState>,
+    Path(key_id): Path<Uuid>
+) -> Result<Json<ApiKey>> {
+    println!("->> {:<12} - revoke_api_key", "HANDLER");
+
+    let api_key = ApiKey::revoke_api_key(&key_id, state)
+        .await?
+        .ok_or(ApiError::ApiKeyNotFound)?;
+
+    Ok(Json(api_key))
+}
@@ -0,0 +1,56 @@
+use axum::{extract::State, routing::post, Json, Router};
+use crate::{errors::api_errors::{ApiError, Result}, models::model_auth::{AuthNonce, AuthSession, NonceChallenge, NonceForCreate, SessionForCreate, SessionToken}, utils, AppState};
+
+pub fn routes(state: AppState) -> Router {
+    Router::new()
+        .route("/auth/nonce", post(create_nonce))
+        .route("/auth/session", post(create_session))
+        .with_state(state)
+}
+
+async fn create_nonce(
+    State(state): State<AppState>,
+    Json(nonce_request): Json<NonceForCreate>
+) -> Result<Json<NonceChallenge>> {
+    println!("->> {:<12} - create_nonce", "HANDLER");
+
+    let nonce = AuthNonce::create_nonce(&nonce_request.user_pubkey, state).await?;
+
+    Ok(Json(NonceChallenge {
+        message: nonce.sign_in_message(),
+        user_pubkey: nonce.user_pubkey,
+        expires_at: nonce.expires_at
+    }))
+}
+
+async fn create_session(
+    State(state): State<AppState>,
+    Json(session_request): Json<SessionForCreate>
+) -> Result<Json<SessionToken>> {
+    println!("->> {:<12} - create_session", "HANDLER");
+
+    let nonce = AuthNonce::get_active_nonce(&session_request.user_pubkey, state.clone())
+        .await?
+        .ok_or(ApiError::AuthSignatureInvalid)?;
+
+    // verified before the nonce is consumed, so a forged signature can't burn the owner's challenge
+    if !utils::verify_wallet_signature(
+        &session_request.user_pubkey,
+        &nonce.sign_in_message(),
+        &session_request.signature
+    ) {
+        return Err(ApiError::AuthSignatureInvalid)
+    }
+
+    if !nonce.consume_nonce(state.clone()).await? {
+        return Err(ApiError::AuthSignatureInvalid)
+    }
+
+    let (token, session) = AuthSession::create_session(&session_request.user_pubkey, state).await?;
+
+    Ok(Json(SessionToken {
+        token,
+        user_pubkey: session.user_pubkey,
+        expires_at: session.expires_at
+    }))
+}
@@ -0,0 +1,20 @@
+use axum::{extract::{Query, State}, middleware, routing::get, Json, Router};
+use crate::{errors::api_errors::Result, models::{model_api_key::Scope, model_leaderboard::{Leaderboard, LeaderboardParams}}, web::mw_auth::scope_middleware, AppState};
+
+pub fn routes(state: AppState) -> Router {
+    Router::new()
+        .route("/leaderboard", get(get_leaderboard)
+            .route_layer(middleware::from_fn_with_state(Scope::Play, scope_middleware)))
+        .with_state(state)
+}
+
+async fn get_leaderboard(
+    State(state): State<AppState>,
+    Query(params): Query<LeaderboardParams>
+) -> Result<Json<Leaderboard>> {
+    println!("->> {:<12} - get_leaderboard", "HANDLER");
+
+    let leaderboard = state.leaderboard_repo.get_leaderboard(params).await?;
+
+    Ok(Json(leaderboard))
+}
@@ -1,13 +1,39 @@
-use axum::{extract::State, routing::get, Json, Router};
+use axum::{extract::{Path, Query, State}, middleware, routing::{get, post}, Extension, Json, Router};
+use uuid::Uuid;
 
-use crate::{errors::api_errors::Result, models::model_token::Token, AppState};
+use crate::{
+    errors::api_errors::{ApiError, Result}, 
+    models::{model_api_key::Scope, model_token::Token, model_spin::{self, Spin, SpinForCreate, SpinHistory, SpinHistoryParams, SpinResult, SpinSeedCommitment, SpinVerification}, model_spin_mode::{SpinModeParams, SpinOdds}, model_spin_quota::{SpinQuota, SpinQuotaStatus}, model_trade::{self, BuyOrder, Quote, QuoteParams, SellOrder, SellReceipt, Trade, TradeReceipt, TradeSide}}, 
+    services::price_source::VsToken,
+    web::mw_auth::{scope_middleware, Caller}, 
+    AppState
+};
 
 
 pub fn routes(state: AppState) -> Router {
     Router::new()
-        .route("/play/coins", get(get_all_active_tokens))
-        .route("/play/coins-filtered", get(get_7_active_selected_tokens))
-        .route("/play/run", get(get_random_token))
+        .route("/play/coins", get(get_all_active_tokens)
+            .route_layer(middleware::from_fn_with_state(Scope::TokensRead, scope_middleware)))
+        .route("/play/coins-filtered", get(get_7_active_selected_tokens)
+            .route_layer(middleware::from_fn_with_state(Scope::TokensRead, scope_middleware)))
+        .route("/play/seed", post(create_spin_seed)
+            .route_layer(middleware::from_fn_with_state(Scope::Play, scope_middleware)))
+        .route("/play/run", post(spin_random_token)
+            .route_layer(middleware::from_fn_with_state(Scope::Play, scope_middleware)))
+        .route("/play/odds", get(get_spin_odds)
+            .route_layer(middleware::from_fn_with_state(Scope::Play, scope_middleware)))
+        .route("/play/verify/:spin_id", get(verify_spin)
+            .route_layer(middleware::from_fn_with_state(Scope::Play, scope_middleware)))
+        .route("/play/quota/:user_pubkey", get(get_spin_quota)
+            .route_layer(middleware::from_fn_with_state(Scope::Play, scope_middleware)))
+        .route("/play/history/:user_pubkey", get(get_spin_history)
+            .route_layer(middleware::from_fn_with_state(Scope::Play, scope_middleware)))
+        .route("/play/quote", get(get_quote)
+            .route_layer(middleware::from_fn_with_state(Scope::Play, scope_middleware)))
+        .route("/play/buy", post(buy_token)
+            .route_layer(middleware::from_fn_with_state(Scope::PositionsWrite, scope_middleware)))
+        .route("/play/sell", post(sell_token)
+            .route_layer(middleware::from_fn_with_state(Scope::PositionsWrite, scope_middleware)))
         .with_state(state)
 }
 
@@ -16,7 +42,7 @@ async fn get_all_active_tokens(
 ) -> Result<Json<Vec<Token>>> {
     println!("->> {:<12} - get_all_active_tokens", "HANDLER");
 
-    let tokens = Token::get_all_active_tokens(state).await?;
+    let tokens = state.token_repo.get_all_active_tokens().await?;
 
     Ok(Json(tokens))
 }
@@ -26,25 +52,249 @@ async fn get_7_active_selected_tokens(
 ) -> Result<Json<Vec<Token>>> {
     println!("->> {:<12} - get_7_active_selected_tokens", "HANDLER");
 
-    let tokens = Token::get_7_active_tokens(state).await?;
+    let tokens = state.token_repo.get_7_active_tokens().await?;
 
     Ok(Json(tokens))
 }
 
-async fn get_random_token(
+async fn create_spin_seed(
     State(state): State<AppState>
-) -> Result<Json<Option<Token>>> {
-    println!("->> {:<12} - get_random_token", "HANDLER");
+) -> Result<Json<SpinSeedCommitment>> {
+    println!("->> {:<12} - create_spin_seed", "HANDLER");
 
-    let tokens = Token::get_all_active_tokens(state).await?;
+    let commitment = Spin::create_seed(state).await?;
+
+    Ok(Json(commitment))
+}
+
+async fn spin_random_token(
+    State(state): State<AppState>,
+    Extension(caller): Extension<Caller>,
+    Query(params): Query<SpinModeParams>,
+    Json(spin): Json<SpinForCreate>
+) -> Result<Json<Option<SpinResult>>> {
+    println!("->> {:<12} - spin_random_token", "HANDLER");
+
+    caller.authorize_user(&spin.user_pubkey)?;
+    spin.validate()?;
+
+    let tokens = state.token_repo.get_all_active_tokens().await?;
 
     if tokens.is_empty() {
         return Ok(Json(None))
     }
-    
-    let random_index = rand::random::<usize>() % tokens.len();
-    
-    let randomly_selected_token = tokens[random_index].clone();
 
-    Ok(Json(Some(randomly_selected_token)))
+    let spin_result = Spin::create_spin(spin, params.mode, tokens, state).await?;
+
+    Ok(Json(Some(spin_result)))
+}
+
+async fn get_spin_odds(
+    State(state): State<AppState>,
+    Query(params): Query<SpinModeParams>
+) -> Result<Json<SpinOdds>> {
+    println!("->> {:<12} - get_spin_odds", "HANDLER");
+
+    let mut tokens = state.token_repo.get_all_active_tokens().await?;
+
+    model_spin::sort_candidates(&mut tokens);
+
+    Ok(Json(SpinOdds::new(params.mode, &tokens)))
+}
+
+async fn verify_spin(
+    State(state): State<AppState>,
+    Path(spin_id): Path<Uuid>
+) -> Result<Json<SpinVerification>> {
+    println!("->> {:<12} - verify_spin", "HANDLER");
+
+    let spin = Spin::get_spin(&spin_id, state)
+        .await?
+        .ok_or(ApiError::SpinNotFound)?;
+
+    Ok(Json(spin.verify()))
+}
+
+async fn get_spin_quota(
+    State(state): State<AppState>,
+    Extension(caller): Extension<Caller>,
+    Path(user_pubkey): Path<String>
+) -> Result<Json<SpinQuotaStatus>> {
+    println!("->> {:<12} - get_spin_quota", "HANDLER");
+
+    caller.authorize_user(&user_pubkey)?;
+
+    let quota = SpinQuota::get_spin_quota(&user_pubkey, state.clone()).await?;
+
+    Ok(Json(state.spin_quota.status(&user_pubkey, quota.as_ref(), chrono::Utc::now())))
+}
+
+async fn get_spin_history(
+    State(state): State<AppState>,
+    Extension(caller): Extension<Caller>,
+    Path(user_pubkey): Path<String>,
+    Query(params): Query<SpinHistoryParams>
+) -> Result<Json<SpinHistory>> {
+    println!("->> {:<12} - get_spin_history", "HANDLER");
+
+    caller.authorize_user(&user_pubkey)?;
+
+    let history = Spin::get_user_spin_history(&user_pubkey, params, state).await?;
+
+    Ok(Json(history))
+}
+
+async fn get_quote(
+    State(state): State<AppState>,
+    Query(params): Query<QuoteParams>
+) -> Result<Json<Quote>> {
+    println!("->> {:<12} - get_quote", "HANDLER");
+
+    params.validate()?;
+
+    let vs_token = VsToken::from_symbol(&params.vs_token_symbol).ok_or(ApiError::VsTokenUnknown)?;
+
+    let price = state.price_service.get_fresh_price(&params.token_pubkey, &vs_token).await?.price;
+
+    Ok(Json(Quote {
+        quantity: params.amount / price,
+        token_pubkey: params.token_pubkey,
+        vs_token_symbol: params.vs_token_symbol,
+        amount: params.amount,
+        price,
+        quoted_at: chrono::Utc::now()
+    }))
+}
+
+async fn buy_token(
+    State(state): State<AppState>,
+    Extension(caller): Extension<Caller>,
+    Json(order): Json<BuyOrder>
+) -> Result<Json<TradeReceipt>> {
+    println!("->> {:<12} - buy_token", "HANDLER");
+
+    caller.authorize_user(&order.user_pubkey)?;
+    order.validate()?;
+
+    let token = state.token_repo.get_token(&order.token_pubkey)
+        .await?
+        .filter(|token| token.is_active)
+        .ok_or(ApiError::TokenNotTradable)?;
+
+    if let Some(spin_id) = order.spin_id {
+        let spin = Spin::get_spin(&spin_id, state.clone())
+            .await?
+            .ok_or(ApiError::SpinNotFound)?;
+
+        if !spin.can_open_position(&order.user_pubkey, &order.token_pubkey) {
+            return Err(ApiError::SpinNotLinkable)
+        }
+    }
+
+    // the price is always fetched server side, the client only supplies the reference it was quoted
+    let vs_token = VsToken::new(&order.vs_token_pubkey, &order.vs_token_symbol);
+    let quote = state.price_service.get_fresh_price(&order.token_pubkey, &vs_token).await?;
+    let execution_price = quote.price;
+
+    let slippage_bps = model_trade::calculate_slippage_bps(TradeSide::Buy, order.expected_price, execution_price);
+
+    if slippage_bps > order.max_slippage_bps as f64 {
+        println!(
+            "Rejecting buy of {} for user {}: slippage {:.2} bps over max {} bps",
+            order.token_pubkey,
+            order.user_pubkey,
+            slippage_bps,
+            order.max_slippage_bps
+        );
+        return Err(ApiError::TradeSlippageExceeded)
+    }
+
+    let receipt = Trade::execute_buy(order, token, execution_price, quote.source, state).await?;
+
+    Ok(Json(receipt))
+}
+
+async fn sell_token(
+    State(state): State<AppState>,
+    Extension(caller): Extension<Caller>,
+    Json(order): Json<SellOrder>
+) -> Result<Json<SellReceipt>> {
+    println!("->> {:<12} - sell_token", "HANDLER");
+
+    let position = state.position_repo.get_position(&order.position_id)
+        .await?
+        .ok_or(ApiError::PositionNotFound)?;
+
+    caller.authorize_user(&position.user_pubkey)?;
+
+    let quantity = order.validate(&position)?;
+
+    let vs_token = VsToken::new(&position.vs_token_pubkey, &position.vs_token_symbol);
+    let quote = state.price_service.get_fresh_price(&position.token_pubkey, &vs_token).await?;
+    let execution_price = quote.price;
+
+    let slippage_bps = model_trade::calculate_slippage_bps(TradeSide::Sell, order.expected_price, execution_price);
+
+    if slippage_bps > order.max_slippage_bps as f64 {
+        println!(
+            "Rejecting sell of position {}: slippage {:.2} bps over max {} bps",
+            order.position_id,
+            slippage_bps,
+            order.max_slippage_bps
+        );
+        return Err(ApiError::TradeSlippageExceeded)
+    }
+
+    let receipt = Trade::execute_sell(order, quantity, execution_price, quote.source, state).await?;
+
+    Ok(Json(receipt))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::MockUpstream;
+
+    fn quote_params(amount: f64) -> QuoteParams {
+        QuoteParams {
+            token_pubkey: "MockMintAlpha".to_string(),
+            vs_token_symbol: "USDC".to_string(),
+            amount
+        }
+    }
+
+    #[tokio::test]
+    async fn quotes_positive_amounts_only() {
+        let upstream = MockUpstream::start().await;
+        let state = upstream.in_memory_state();
+
+        let Json(quote) = get_quote(State(state.clone()), Query(quote_params(3.0))).await.unwrap();
+        assert_eq!(quote.quantity, 2.0);
+
+        for amount in [0.0, -3.0, f64::NAN, f64::INFINITY] {
+            let result = get_quote(State(state.clone()), Query(quote_params(amount))).await;
+            assert!(matches!(result, Err(ApiError::TradeInvalidOrder)));
+        }
+    }
+
+    #[tokio::test]
+    async fn rejects_a_vs_token_symbol_that_is_not_the_mints() {
+        let upstream = MockUpstream::start().await;
+        let state = upstream.in_memory_state();
+
+        let order = BuyOrder {
+            user_pubkey: "Wallet".to_string(),
+            token_pubkey: "MockMintAlpha".to_string(),
+            vs_token_pubkey: "EPjFWdd5AufqSSqeM2qN1xzybapC8G4wEGGkZwyTDt1v".to_string(),
+            vs_token_symbol: "SOL".to_string(),
+            vs_token_logo_url: String::new(),
+            amount: 1.0,
+            expected_price: 1.5,
+            max_slippage_bps: 100,
+            spin_id: None
+        };
+
+        let result = buy_token(State(state), Extension(Caller::Wallet("Wallet".to_string())), Json(order)).await;
+        assert!(matches!(result, Err(ApiError::VsTokenMismatch)));
+    }
 }
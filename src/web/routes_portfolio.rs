@@ -0,0 +1,58 @@
+use std::collections::HashMap;
+
+use axum::{extract::{Path, State}, middleware, routing::get, Extension, Json, Router};
+use crate::{errors::api_errors::Result, models::{model_api_key::Scope, model_portfolio::Portfolio}, services::price_source::VsToken, web::mw_auth::{scope_middleware, Caller}, AppState};
+
+pub fn routes(state: AppState) -> Router {
+    Router::new()
+        .route("/portfolio/:user_pubkey", get(get_portfolio)
+            .route_layer(middleware::from_fn_with_state(Scope::PositionsRead, scope_middleware)))
+        .with_state(state)
+}
+
+async fn get_portfolio(
+    State(state): State<AppState>,
+    Extension(caller): Extension<Caller>,
+    Path(user_pubkey): Path<String>
+) -> Result<Json<Portfolio>> {
+    println!("->> {:<12} - get_portfolio", "HANDLER");
+
+    caller.authorize_user(&user_pubkey)?;
+
+    let unique_tokens_and_vs_tokens = state.position_repo.get_user_unique_tokens_and_vs_tokens(&user_pubkey).await?;
+
+    let positions = state.position_repo.get_user_positions(&user_pubkey).await?;
+
+    let pairs: Vec<(String, VsToken)> = unique_tokens_and_vs_tokens.iter()
+        .map(|token_and_vs_token| (
+            token_and_vs_token.token_pubkey.clone(),
+            VsToken::new(&token_and_vs_token.vs_token_pubkey, &token_and_vs_token.vs_token_symbol)
+        ))
+        .collect();
+
+    let token_prices = state.price_service.get_prices_for_pairs(&pairs).await?;
+
+    let vs_token_pubkeys: Vec<String> = unique_tokens_and_vs_tokens.iter()
+        .map(|token_and_vs_token| token_and_vs_token.vs_token_pubkey.clone())
+        .collect();
+
+    let usd_prices_by_pubkey = state.price_service.get_prices(&vs_token_pubkeys, &VsToken::usd()).await?;
+
+    let usd_prices: HashMap<String, f64> = unique_tokens_and_vs_tokens.iter()
+        .filter_map(|token_and_vs_token| {
+            usd_prices_by_pubkey
+                .get(&token_and_vs_token.vs_token_pubkey)
+                .map(|usd_price| (token_and_vs_token.vs_token_symbol.clone(), *usd_price))
+        })
+        .collect();
+
+    let portfolio = Portfolio::build(
+        &user_pubkey,
+        unique_tokens_and_vs_tokens,
+        positions,
+        &token_prices,
+        &usd_prices
+    );
+
+    Ok(Json(portfolio))
+}
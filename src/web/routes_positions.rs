@@ -1,94 +1,150 @@
-use axum::{extract::{Path, State}, routing::{get, post}, Json, Router};
-use crate::{clients::client_jupiter::JupiterClient, errors::api_errors::Result, models::model_position::{Position, PositionForCreate, PositionWithProfit, UpdatePositionData}, utils, AppState};
+use axum::{extract::{Path, State}, middleware, routing::{get, post}, Extension, Json, Router};
+use uuid::Uuid;
+use crate::{errors::api_errors::{ApiError, Result}, models::{model_api_key::Scope, model_position::{Position, PositionFill, PositionForCreate, PositionWithProfit, UpdatePositionData}}, services::price_source::VsToken, web::mw_auth::{scope_middleware, Caller}, AppState};
 
 pub fn routes(state: AppState) -> Router {
     Router::new()
-        .route("/positions", post(create_position).get(get_positions).put(update_position_quantity))
-        .route("/positions/user/:user_pubkey", get(get_user_positions))
-        .route("/positions/user/:user_pubkey/mint/:mint_pubkey", get(get_user_positions_by_token))
-        .route("/positions-profit/user/:user_pubkey", get(get_user_positions_and_profit))
-        .route("/positions/mint/:mint_pubkey", get(get_token_positions))
+        // raw writes trust client prices, wallets open positions through /play/buy instead
+        .route("/positions", post(create_position).put(update_position_quantity)
+            .route_layer(middleware::from_fn_with_state(Scope::Admin, scope_middleware)))
+        .route("/positions", get(get_positions)
+            .route_layer(middleware::from_fn_with_state(Scope::PositionsRead, scope_middleware)))
+        .route("/positions/user/:user_pubkey", get(get_user_positions)
+            .route_layer(middleware::from_fn_with_state(Scope::PositionsRead, scope_middleware)))
+        .route("/positions/user/:user_pubkey/mint/:mint_pubkey", get(get_user_positions_by_token)
+            .route_layer(middleware::from_fn_with_state(Scope::PositionsRead, scope_middleware)))
+        .route("/positions-profit/user/:user_pubkey", get(get_user_positions_and_profit)
+            .route_layer(middleware::from_fn_with_state(Scope::PositionsRead, scope_middleware)))
+        .route("/positions/:position_id/fills", get(get_position_fills)
+            .route_layer(middleware::from_fn_with_state(Scope::PositionsRead, scope_middleware)))
+        .route("/positions/mint/:mint_pubkey", get(get_token_positions)
+            .route_layer(middleware::from_fn_with_state(Scope::PositionsRead, scope_middleware)))
         .with_state(state)
 }
 
 async fn create_position(
     State(state): State<AppState>,
+    Extension(caller): Extension<Caller>,
     Json(position): Json<PositionForCreate>
 ) -> Result<Json<Position>> {
     println!("->> {:<12} - create_position", "HANDLER");
 
-    let position = Position::create_position(position, state).await?;
+    caller.authorize_user(&position.user_pubkey)?;
+
+    let position = state.position_repo.create_position(position).await?;
 
     Ok(Json(position))
 }
 
 async fn update_position_quantity(
     State(state): State<AppState>,
+    Extension(caller): Extension<Caller>,
     Json(update_data): Json<UpdatePositionData>
 ) -> Result<Json<Position>> {
     println!("->> {:<12} - update_position_quantity", "HANDLER");
 
-    let position = Position::update_position_quantity(update_data, state).await?;
+    let position = state.position_repo.get_position(&update_data.position_id)
+        .await?
+        .ok_or(ApiError::PositionNotFound)?;
+
+    caller.authorize_user(&position.user_pubkey)?;
+
+    let position = state.position_repo.update_position_quantity(update_data).await?;
 
     Ok(Json(position))
 }
 
 async fn get_positions(
-    State(state): State<AppState>
+    State(state): State<AppState>,
+    Extension(caller): Extension<Caller>
 ) -> Result<Json<Vec<Position>>> {
     println!("->> {:<12} - get_positions", "HANDLER");
 
-    let positions = Position::get_positions(state).await?;
+    caller.require_service()?;
+
+    let positions = state.position_repo.get_positions().await?;
 
     Ok(Json(positions))
 }
 
+async fn get_position_fills(
+    State(state): State<AppState>,
+    Extension(caller): Extension<Caller>,
+    Path(position_id): Path<Uuid>
+) -> Result<Json<Vec<PositionFill>>> {
+    println!("->> {:<12} - get_position_fills", "HANDLER");
+
+    let position = state.position_repo.get_position(&position_id)
+        .await?
+        .ok_or(ApiError::PositionNotFound)?;
+
+    caller.authorize_user(&position.user_pubkey)?;
+
+    let fills = PositionFill::get_position_fills(&position_id, state).await?;
+
+    Ok(Json(fills))
+}
+
 async fn get_user_positions(
     State(state): State<AppState>,
+    Extension(caller): Extension<Caller>,
     Path(user_pubkey): Path<String>
 ) -> Result<Json<Vec<Position>>> {
     println!("->> {:<12} - get_user_positions", "HANDLER");
 
-    let positions = Position::get_user_positions(&user_pubkey, state).await?;
+    caller.authorize_user(&user_pubkey)?;
+
+    let positions = state.position_repo.get_user_positions(&user_pubkey).await?;
 
     Ok(Json(positions))
 }
 
 async fn get_user_positions_and_profit(
     State(state): State<AppState>,
+    Extension(caller): Extension<Caller>,
     Path(user_pubkey): Path<String>
 ) -> Result<Json<Vec<PositionWithProfit>>> {
     println!("->> {:<12} - get_user_positions_and_profit", "HANDLER");
 
-    let unique_tokens_and_vs_tokens = Position::get_user_unique_tokens_and_vs_tokens(&user_pubkey, state.clone()).await?;
+    caller.authorize_user(&user_pubkey)?;
+
+    let unique_tokens_and_vs_tokens = state.position_repo.get_user_unique_tokens_and_vs_tokens(&user_pubkey).await?;
 
     let positions = 
-        Position::get_user_positions(&user_pubkey, state)
+        state.position_repo.get_user_positions(&user_pubkey)
         .await?;
 
+    let pairs: Vec<(String, VsToken)> = unique_tokens_and_vs_tokens.iter()
+        .map(|token_and_vs_tokens| (
+            token_and_vs_tokens.token_pubkey.clone(),
+            VsToken::new(&token_and_vs_tokens.vs_token_pubkey, &token_and_vs_tokens.vs_token_symbol)
+        ))
+        .collect();
+
+    let quotes = state.price_service.get_quotes_for_pairs(&pairs).await?;
+
     let mut positions_with_profit: Vec<PositionWithProfit> = Vec::with_capacity(positions.len());
 
     for token_and_vs_tokens in unique_tokens_and_vs_tokens {
-        let new_price = 
-            JupiterClient::get_token_price(&token_and_vs_tokens.token_pubkey, &token_and_vs_tokens.vs_token_symbol).await?;
+        // a token without a price is reported as unavailable instead of failing the response
+        let quote = quotes
+            .get(&(token_and_vs_tokens.token_pubkey.clone(), token_and_vs_tokens.vs_token_symbol.clone()))
+            .copied();
 
         let matching_positions: Vec<&Position> = positions.iter()
             .filter(|position| position.token_pubkey == token_and_vs_tokens.token_pubkey && position.vs_token_symbol == token_and_vs_tokens.vs_token_symbol)
             .collect();
 
         for matching_position in matching_positions {
-            let (price_change, percentage_change) = utils::calculate_price_change(
-                new_price, 
-                matching_position.purchase_price
-            );
-
-            let position_with_profit = PositionWithProfit::new(
+            let mut position_with_profit = PositionWithProfit::new(
                 matching_position.clone(), 
-                new_price, 
-                percentage_change, 
-                price_change
+                quote.map(|quote| quote.price)
             );
 
+            if let Some(quote) = quote {
+                position_with_profit = position_with_profit.with_price_source(quote.source, quote.divergence, quote.divergent);
+            }
+
             positions_with_profit.push(position_with_profit)
         }
     }
@@ -98,14 +154,16 @@ async fn get_user_positions_and_profit(
 
 async fn get_user_positions_by_token(
     State(state): State<AppState>,
+    Extension(caller): Extension<Caller>,
     Path((user_pubkey, mint_pubkey)): Path<(String, String)>
 ) -> Result<Json<Vec<Position>>> {
     println!("->> {:<12} - get_user_positions_by_token", "HANDLER");
 
-    let positions = Position::get_user_positions_by_token(
+    caller.authorize_user(&user_pubkey)?;
+
+    let positions = state.position_repo.get_user_positions_by_token(
         &user_pubkey,
-        &mint_pubkey, 
-        state
+        &mint_pubkey
     ).await?;
 
     Ok(Json(positions))
@@ -113,11 +171,73 @@ async fn get_user_positions_by_token(
 
 async fn get_token_positions(
     State(state): State<AppState>,
+    Extension(caller): Extension<Caller>,
     Path(mint_pubkey): Path<String>
 ) -> Result<Json<Vec<Position>>> {
     println!("->> {:<12} - get_token_positions", "HANDLER");
 
-    let positions = Position::get_token_positions(&mint_pubkey, state).await?;
+    caller.require_service()?;
+
+    let positions = state.position_repo.get_token_positions(&mint_pubkey).await?;
 
     Ok(Json(positions))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{models::model_position::PriceStatus, test_utils::MockUpstream};
+
+    fn position(token_pubkey: &str, quantity: f64) -> PositionForCreate {
+        PositionForCreate {
+            user_pubkey: "Wallet".to_string(),
+            token_pubkey: token_pubkey.to_string(),
+            token_symbol: token_pubkey.to_string(),
+            token_logo_url: String::new(),
+            vs_token_pubkey: "EPjFWdd5AufqSSqeM2qN1xzybapC8G4wEGGkZwyTDt1v".to_string(),
+            vs_token_symbol: "USDC".to_string(),
+            vs_token_logo_url: String::new(),
+            quantity,
+            purchase_price: 1.0
+        }
+    }
+
+    #[tokio::test]
+    async fn prices_positions_and_flags_unpriceable_tokens() {
+        let upstream = MockUpstream::start().await;
+        let state = upstream.in_memory_state();
+
+        state.position_repo.create_position(position("MockMintAlpha", 10.0)).await.unwrap();
+        state.position_repo.create_position(position("UnknownMint", 3.0)).await.unwrap();
+
+        let Json(positions) = get_user_positions_and_profit(
+            State(state),
+            Extension(Caller::Wallet("Wallet".to_string())),
+            Path("Wallet".to_string())
+        ).await.unwrap();
+
+        let alpha = positions.iter().find(|position| position.token_pubkey == "MockMintAlpha").unwrap();
+        assert_eq!(alpha.current_price, Some(1.5));
+        assert_eq!(alpha.price_status, PriceStatus::Available);
+        assert_eq!(alpha.price_source.as_deref(), Some("jupiter_v2"));
+        assert_eq!(alpha.unrealized_pnl, Some(5.0));
+
+        let unknown = positions.iter().find(|position| position.token_pubkey == "UnknownMint").unwrap();
+        assert_eq!(unknown.current_price, None);
+        assert_eq!(unknown.price_status, PriceStatus::Unavailable);
+    }
+
+    #[tokio::test]
+    async fn wallets_cannot_read_other_wallets_positions() {
+        let upstream = MockUpstream::start().await;
+        let state = upstream.in_memory_state();
+
+        let result = get_user_positions(
+            State(state),
+            Extension(Caller::Wallet("Other".to_string())),
+            Path("Wallet".to_string())
+        ).await;
+
+        assert!(matches!(result, Err(ApiError::Forbidden)));
+    }
 }
\ No newline at end of file
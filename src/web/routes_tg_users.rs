@@ -0,0 +1,111 @@
+use axum::{extract::{Path, Query, State}, middleware, routing::{get, post}, Extension, Json, Router};
+use crate::{
+    errors::api_errors::{ApiError, Result},
+    models::{model_api_key::Scope, model_spin_mode::SpinModeParams, model_tg_user::{AccessCodeRedeem, TgSpinReceipt, TgSpinRequest, TgUser, TgUserForCreate}},
+    web::mw_auth::{scope_middleware, Caller},
+    AppState
+};
+
+// telegram users have no wallet, so only service keys held by the bot can act for them
+pub fn routes(state: AppState) -> Router {
+    Router::new()
+        .route("/tg/users", post(create_tg_user)
+            .route_layer(middleware::from_fn_with_state(Scope::UsersWrite, scope_middleware)))
+        .route("/tg/users/:user_id", get(get_tg_user)
+            .route_layer(middleware::from_fn_with_state(Scope::UsersRead, scope_middleware)))
+        .route("/tg/users/:user_id/referrals", get(get_referrals)
+            .route_layer(middleware::from_fn_with_state(Scope::UsersRead, scope_middleware)))
+        .route("/tg/users/:user_id/redeem", post(redeem_access_code)
+            .route_layer(middleware::from_fn_with_state(Scope::UsersWrite, scope_middleware)))
+        .route("/tg/users/:user_id/spin", post(spin_with_points)
+            .route_layer(middleware::from_fn_with_state(Scope::Play, scope_middleware)))
+        .with_state(state)
+}
+
+async fn create_tg_user(
+    State(state): State<AppState>,
+    Extension(caller): Extension<Caller>,
+    Json(user): Json<TgUserForCreate>
+) -> Result<Json<TgUser>> {
+    println!("->> {:<12} - create_tg_user", "HANDLER");
+
+    caller.require_service()?;
+
+    let user = TgUser::create_tg_user(user, state).await?;
+
+    Ok(Json(user))
+}
+
+async fn get_tg_user(
+    State(state): State<AppState>,
+    Extension(caller): Extension<Caller>,
+    Path(user_id): Path<i64>
+) -> Result<Json<TgUser>> {
+    println!("->> {:<12} - get_tg_user", "HANDLER");
+
+    caller.require_service()?;
+
+    let user = TgUser::get_tg_user(user_id, state)
+        .await?
+        .ok_or(ApiError::TgUserNotFound)?;
+
+    Ok(Json(user))
+}
+
+async fn get_referrals(
+    State(state): State<AppState>,
+    Extension(caller): Extension<Caller>,
+    Path(user_id): Path<i64>
+) -> Result<Json<Vec<TgUser>>> {
+    println!("->> {:<12} - get_referrals", "HANDLER");
+
+    caller.require_service()?;
+
+    let referrals = TgUser::get_referrals(user_id, state).await?;
+
+    Ok(Json(referrals))
+}
+
+async fn redeem_access_code(
+    State(state): State<AppState>,
+    Extension(caller): Extension<Caller>,
+    Path(user_id): Path<i64>,
+    Json(redeem): Json<AccessCodeRedeem>
+) -> Result<Json<TgUser>> {
+    println!("->> {:<12} - redeem_access_code", "HANDLER");
+
+    caller.require_service()?;
+
+    let user = TgUser::redeem_access_code(user_id, &redeem.access_code, state).await?;
+
+    Ok(Json(user))
+}
+
+async fn spin_with_points(
+    State(state): State<AppState>,
+    Extension(caller): Extension<Caller>,
+    Path(user_id): Path<i64>,
+    Query(params): Query<SpinModeParams>,
+    Json(spin): Json<TgSpinRequest>
+) -> Result<Json<TgSpinReceipt>> {
+    println!("->> {:<12} - spin_with_points", "HANDLER");
+
+    caller.require_service()?;
+
+    let spin = spin.into_spin(user_id);
+    spin.validate()?;
+
+    TgUser::get_tg_user(user_id, state.clone())
+        .await?
+        .ok_or(ApiError::TgUserNotFound)?;
+
+    let tokens = state.token_repo.get_all_active_tokens().await?;
+
+    if tokens.is_empty() {
+        return Err(ApiError::TokenNotTradable)
+    }
+
+    let receipt = TgUser::spin_with_points(user_id, spin, params.mode, tokens, state).await?;
+
+    Ok(Json(receipt))
+}
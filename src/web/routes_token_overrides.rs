@@ -0,0 +1,59 @@
+use axum::{extract::{Path, State}, middleware, routing::{get, put}, Json, Router};
+use uuid::Uuid;
+use crate::{errors::api_errors::{ApiError, Result}, models::{model_api_key::Scope, model_token_override::{TokenOverride, TokenOverrideForCreate, TokenOverrideForUpdate}}, web::mw_auth::scope_middleware, AppState};
+
+pub fn routes(state: AppState) -> Router {
+    Router::new()
+        .route("/admin/token-overrides", get(get_token_overrides).post(create_token_override))
+        .route("/admin/token-overrides/:override_id", put(update_token_override).delete(delete_token_override))
+        .route_layer(middleware::from_fn_with_state(Scope::Admin, scope_middleware))
+        .with_state(state)
+}
+
+async fn create_token_override(
+    State(state): State<AppState>,
+    Json(token_override): Json<TokenOverrideForCreate>
+) -> Result<Json<TokenOverride>> {
+    println!("->> {:<12} - create_token_override", "HANDLER");
+
+    let token_override = state.token_override_repo.create_token_override(token_override).await?;
+
+    Ok(Json(token_override))
+}
+
+async fn get_token_overrides(
+    State(state): State<AppState>
+) -> Result<Json<Vec<TokenOverride>>> {
+    println!("->> {:<12} - get_token_overrides", "HANDLER");
+
+    let token_overrides = state.token_override_repo.get_token_overrides().await?;
+
+    Ok(Json(token_overrides))
+}
+
+async fn update_token_override(
+    State(state): State<AppState>,
+    Path(override_id): Path<Uuid>,
+    Json(token_override): Json<TokenOverrideForUpdate>
+) -> Result<Json<TokenOverride>> {
+    println!("->> {:<12} - update_token_override", "HANDLER");
+
+    let token_override = state.token_override_repo.update_token_override(&override_id, token_override)
+        .await?
+        .ok_or(ApiError::TokenOverrideNotFound)?;
+
+    Ok(Json(token_override))
+}
+
+async fn delete_token_override(
+    State(state): State<AppState>,
+    Path(override_id): Path<Uuid>
+) -> Result<Json<TokenOverride>> {
+    println!("->> {:<12} - delete_token_override", "HANDLER");
+
+    let token_override = state.token_override_repo.delete_token_override(&override_id)
+        .await?
+        .ok_or(ApiError::TokenOverrideNotFound)?;
+
+    Ok(Json(token_override))
+}
@@ -1,9 +1,16 @@
-use axum::{extract::State, routing::get, Json, Router};
-use crate::{models::model_token::Token, AppState, errors::api_errors::Result};
+use axum::{extract::{Path, Query, State}, middleware, routing::get, Json, Router};
+use crate::{models::{model_api_key::Scope, model_selection::{SelectionConfig, SelectionConfigForUpdate, SelectionParams, SelectionRunWithTokens}, model_token::Token, model_token_snapshot::{self, HistoryParams, PriceCandle}}, web::mw_auth::scope_middleware, AppState, errors::api_errors::Result};
 
 pub fn routes(state: AppState) -> Router {
     Router::new()
-        .route("/tokens", get(get_tokens))
+        .route("/tokens", get(get_tokens)
+            .route_layer(middleware::from_fn_with_state(Scope::TokensRead, scope_middleware)))
+        .route("/tokens/selections", get(get_selections)
+            .route_layer(middleware::from_fn_with_state(Scope::TokensRead, scope_middleware)))
+        .route("/admin/selection-config", get(get_selection_config).put(update_selection_config)
+            .route_layer(middleware::from_fn_with_state(Scope::Admin, scope_middleware)))
+        .route("/tokens/:mint_pubkey/history", get(get_token_history)
+            .route_layer(middleware::from_fn_with_state(Scope::TokensRead, scope_middleware)))
         .with_state(state)
 }
 
@@ -12,8 +19,55 @@ async fn get_tokens(
 ) -> Result<Json<Vec<Token>>> {
     println!("->> {:<12} - get_tokens", "HANDLER");
 
-    let tokens = Token::get_tokens(state).await?;
+    let tokens = state.token_repo.get_tokens().await?;
 
     Ok(Json(tokens))
 }
 
+async fn get_token_history(
+    State(state): State<AppState>,
+    Path(mint_pubkey): Path<String>,
+    Query(params): Query<HistoryParams>
+) -> Result<Json<Vec<PriceCandle>>> {
+    println!("->> {:<12} - get_token_history", "HANDLER");
+
+    let (from, to) = params.resolve_range()?;
+
+    let snapshots = state.snapshot_repo.get_snapshots(&mint_pubkey, from, to).await?;
+
+    Ok(Json(model_token_snapshot::build_candles(&snapshots, params.interval)))
+}
+
+async fn get_selections(
+    State(state): State<AppState>,
+    Query(params): Query<SelectionParams>
+) -> Result<Json<Vec<SelectionRunWithTokens>>> {
+    println!("->> {:<12} - get_selections", "HANDLER");
+
+    let date = params.date.unwrap_or_else(|| chrono::Utc::now().date_naive());
+
+    let selections = state.selection_repo.get_selections(date).await?;
+
+    Ok(Json(selections))
+}
+
+async fn get_selection_config(
+    State(state): State<AppState>
+) -> Result<Json<SelectionConfig>> {
+    println!("->> {:<12} - get_selection_config", "HANDLER");
+
+    let config = state.selection_repo.get_selection_config().await?;
+
+    Ok(Json(config))
+}
+
+async fn update_selection_config(
+    State(state): State<AppState>,
+    Json(config): Json<SelectionConfigForUpdate>
+) -> Result<Json<SelectionConfig>> {
+    println!("->> {:<12} - update_selection_config", "HANDLER");
+
+    let config = state.selection_repo.update_selection_config(config).await?;
+
+    Ok(Json(config))
+}
\ No newline at end of file
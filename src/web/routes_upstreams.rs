@@ -0,0 +1,25 @@
+use axum::{extract::State, middleware, routing::get, Json, Router};
+use crate::{clients::external_http::UpstreamStats, errors::api_errors::Result, models::model_api_key::Scope, web::mw_auth::scope_middleware, AppState};
+
+pub fn routes(state: AppState) -> Router {
+    Router::new()
+        .route("/admin/upstreams", get(get_upstream_stats))
+        .route_layer(middleware::from_fn_with_state(Scope::Admin, scope_middleware))
+        .with_state(state)
+}
+
+/// Request, error and circuit breaker counters per external API since startup.
+async fn get_upstream_stats(
+    State(state): State<AppState>
+) -> Result<Json<Vec<UpstreamStats>>> {
+    println!("->> {:<12} - get_upstream_stats", "HANDLER");
+
+    let mut upstreams = state.price_service.upstream_stats();
+
+    // the Birdeye price source shares the selector's client, so list it once
+    if !upstreams.iter().any(|stats| stats.upstream == "birdeye") {
+        upstreams.insert(0, state.birdeye_client.stats());
+    }
+
+    Ok(Json(upstreams))
+}
@@ -1,41 +1,132 @@
-use axum::{extract::{Path, State}, routing::{get, post}, Json, Router};
-use crate::{errors::api_errors::Result, models::model_user::{User, UserForCreate}, AppState};
+use axum::{extract::{Path, Query, State}, middleware, routing::{get, post}, Extension, Json, Router};
+use crate::{errors::api_errors::Result, models::{model_api_key::Scope, model_points::{PointsBalance, PointsLedger, PointsParams}, model_referral::ReferralCode, model_user::{User, UserForCreate}}, web::mw_auth::{scope_middleware, Caller}, AppState};
 
 pub fn routes(state: AppState) -> Router {
     Router::new()
-        .route("/users", post(create_user).get(get_users))
-        .route("/users/:pubkey", get(get_user))
+        .route("/users", post(create_user)
+            .route_layer(middleware::from_fn_with_state(Scope::UsersWrite, scope_middleware)))
+        .route("/users", get(get_users)
+            .route_layer(middleware::from_fn_with_state(Scope::UsersRead, scope_middleware)))
+        .route("/users/:pubkey", get(get_user)
+            .route_layer(middleware::from_fn_with_state(Scope::UsersRead, scope_middleware)))
+        .route("/users/:pubkey/points", get(get_points)
+            .route_layer(middleware::from_fn_with_state(Scope::UsersRead, scope_middleware)))
+        .route("/users/:pubkey/referral-code", post(get_or_create_referral_code)
+            .route_layer(middleware::from_fn_with_state(Scope::UsersWrite, scope_middleware)))
         .with_state(state)
 }
 
 async fn create_user(
     State(state): State<AppState>,
+    Extension(caller): Extension<Caller>,
     Json(user): Json<UserForCreate>
 ) -> Result<Json<User>> {
     println!("->> {:<12} - create_user", "HANDLER");
 
-    let user = User::create_user(user, state).await?;
+    caller.authorize_user(&user.user_pubkey)?;
+
+    let user = state.user_repo.create_user(user).await?;
 
     Ok(Json(user))
 }
 
 async fn get_users(
-    State(state): State<AppState>
+    State(state): State<AppState>,
+    Extension(caller): Extension<Caller>
 ) -> Result<Json<Vec<User>>> {
     println!("->> {:<12} - get_users", "HANDLER");
 
-    let users = User::get_users(state).await?;
+    caller.require_service()?;
+
+    let users = state.user_repo.get_users().await?;
 
     Ok(Json(users))
 }
 
 async fn get_user(
     State(state): State<AppState>,
+    Extension(caller): Extension<Caller>,
     Path(pubkey): Path<String>
 ) -> Result<Json<Option<User>>> {
     println!("->> {:<12} - get_user", "HANDLER");
 
-    let user = User::get_user(&pubkey, state).await?;
+    caller.authorize_user(&pubkey)?;
+
+    let user = state.user_repo.get_user(&pubkey).await?;
 
     Ok(Json(user))
+}
+
+async fn get_points(
+    State(state): State<AppState>,
+    Extension(caller): Extension<Caller>,
+    Path(pubkey): Path<String>,
+    Query(params): Query<PointsParams>
+) -> Result<Json<PointsBalance>> {
+    println!("->> {:<12} - get_points", "HANDLER");
+
+    caller.authorize_user(&pubkey)?;
+
+    let points = PointsLedger::get_points(&pubkey, params, state).await?;
+
+    Ok(Json(points))
+}
+
+async fn get_or_create_referral_code(
+    State(state): State<AppState>,
+    Extension(caller): Extension<Caller>,
+    Path(pubkey): Path<String>
+) -> Result<Json<ReferralCode>> {
+    println!("->> {:<12} - get_or_create_referral_code", "HANDLER");
+
+    caller.authorize_user(&pubkey)?;
+
+    let referral_code = ReferralCode::get_or_create_referral_code(&pubkey, state).await?;
+
+    Ok(Json(referral_code))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{errors::api_errors::ApiError, test_utils::MockUpstream};
+
+    fn user(user_pubkey: &str) -> UserForCreate {
+        UserForCreate { user_pubkey: user_pubkey.to_string(), referral_code: None }
+    }
+
+    #[tokio::test]
+    async fn wallets_create_and_read_only_themselves() {
+        let upstream = MockUpstream::start().await;
+        let state = upstream.in_memory_state();
+        let wallet = Caller::Wallet("Wallet".to_string());
+
+        let Json(created) = create_user(State(state.clone()), Extension(wallet.clone()), Json(user("Wallet"))).await.unwrap();
+        assert_eq!(created.user_pubkey, "Wallet");
+
+        let Json(fetched) = get_user(State(state.clone()), Extension(wallet.clone()), Path("Wallet".to_string())).await.unwrap();
+        assert_eq!(fetched.unwrap().user_pubkey, "Wallet");
+
+        let other = create_user(State(state.clone()), Extension(wallet.clone()), Json(user("Other"))).await;
+        assert!(matches!(other, Err(ApiError::Forbidden)));
+
+        assert!(matches!(get_users(State(state), Extension(wallet)).await, Err(ApiError::Forbidden)));
+    }
+
+    #[tokio::test]
+    async fn services_list_every_user() {
+        let upstream = MockUpstream::start().await;
+        let state = upstream.in_memory_state();
+        let service = Caller::Service(vec![Scope::UsersRead, Scope::UsersWrite]);
+
+        for user_pubkey in ["WalletA", "WalletB"] {
+            assert!(create_user(State(state.clone()), Extension(service.clone()), Json(user(user_pubkey))).await.is_ok());
+        }
+
+        let duplicate = create_user(State(state.clone()), Extension(service.clone()), Json(user("WalletA"))).await;
+        assert!(matches!(duplicate, Err(ApiError::UserCreateFail)));
+
+        let Json(users) = get_users(State(state), Extension(service)).await.unwrap();
+        assert_eq!(users.len(), 2);
+    }
 }
\ No newline at end of file